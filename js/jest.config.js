@@ -127,7 +127,7 @@ module.exports = {
   // setupFiles: [],
 
   // A list of paths to modules that run some code to configure or set up the testing framework before each test
-  setupFilesAfterEnv: ['<rootDir>/tests/jest.setup.ts'],
+  // setupFilesAfterEnv: [],
 
   // The test environment that will be used for testing
   // testEnvironment: "jest-environment-jsdom",
@@ -1,4 +1,5 @@
 import { Chip8 } from './Chip8';
+import { Xorshift32 } from './Random';
 
 const choice = (
   key: string | number,
@@ -119,6 +120,14 @@ export class CPU {
 
   running = true;
 
+  /**
+   * FX0A 正在等待的按键，与 Rust 版本的 `KeyWait` 相同：
+   * held 是开始等待时已经按住的键，pressed 是新按下、等待松开的键
+   */
+  #keyWait: { x: number; held: number; pressed: number | null } | null = null;
+
+  #rng = new Xorshift32(Math.floor(Math.random() * 0xffffffff));
+
   /**
    * 指令集
    * @see https://github.com/trapexit/chip-8_documentation#opcodes
@@ -417,7 +426,7 @@ export class CPU {
        * Set VX equal to a random number ranging from 0 to 255 which is logically anded with NN.
        */
       RND_VX_NN: () => {
-        this.#register_V[x] = Math.floor(Math.random() * 0xff) & kk;
+        this.#register_V[x] = this.#rng.nextByte() & kk;
       },
       /**
        * DXY0
@@ -429,19 +438,20 @@ export class CPU {
        */
       S8_DRW_VX_VY_0: () => {
         this.#register_V[0xf] = 0;
-        const vx = this.#register_V[x];
-        const vy = this.#register_V[y];
+        const [width, height] = this.#displaySize();
+        const vx = this.#register_V[x] % width;
+        const vy = this.#register_V[y] % height;
 
         for (let row = 0; row < 16; row++) {
-          if (vy + row >= this.c8.display.rows) {
+          if (vy + row >= height) {
             break;
           }
           let spriteByte1 = this.#memory[this.#register_Index + row * 2];
           let spriteByte2 = this.#memory[this.#register_Index + (row * 2 + 1)];
 
           for (let bitIndex = 0; bitIndex < 16; bitIndex++) {
-            if (vx + bitIndex >= this.c8.display.columns) {
-              continue;
+            if (vx + bitIndex >= width) {
+              break;
             }
 
             let spriteBit =
@@ -472,56 +482,35 @@ export class CPU {
        */
       DRW_VX_VX_N: () => {
         this.#register_V[0xf] = 0;
-        const vx = this.#register_V[x];
-        const vy = this.#register_V[y];
-        const nums = n;
-
-        // 64 * 32
-        for (let yy = 0; yy < nums; yy++) {
+        // 低分辨率下屏幕是 64 * 32，起点坐标取模回绕，超出屏幕的部分裁剪掉
+        const [width, height] = this.#displaySize();
+        const vx = this.#register_V[x] % width;
+        const vy = this.#register_V[y] % height;
+
+        for (let yy = 0; yy < n; yy++) {
+          const y_cord = vy + yy;
+          if (y_cord >= height) {
+            break;
+          }
           const sys_byte = this.#memory[this.#register_Index + yy];
           for (let xx = 0; xx < 8; xx++) {
             const x_cord = vx + xx;
-            const y_cord = vy + yy;
-            if (
-              x_cord < this.c8.display.columns &&
-              y_cord < this.c8.display.rows
-            ) {
-              const sys_bit = (sys_byte >> (7 - xx)) & 0x01;
-              const xy_bit = this.c8.display.getPixel(x_cord, y_cord);
-              if ((xy_bit & sys_bit) === 1) {
-                this.#register_V[0xf] = 1;
-              }
-              this.c8.display.setPixel(
-                x_cord,
-                y_cord,
-                (xy_bit ^ sys_bit) as 0 | 1
-              );
+            if (x_cord >= width) {
+              break;
+            }
+            const sys_bit = (sys_byte >> (7 - xx)) & 0x01;
+            const xy_bit = this.c8.display.getPixel(x_cord, y_cord);
+            if ((xy_bit & sys_bit) === 1) {
+              this.#register_V[0xf] = 1;
             }
+            this.c8.display.setPixel(
+              x_cord,
+              y_cord,
+              (xy_bit ^ sys_bit) as 0 | 1
+            );
           }
         }
 
-        // for (let row = 0; row < nums; row++) {
-        //   if (vy + row >= this.c8.display.rows) {
-        //     break;
-        //   }
-        //   const spriteByte = this.#memory[this.#register_Index + row];
-        //   for (let xbit = 0; xbit < 8; xbit++) {
-        //     if (vx + xbit > this.c8.display.columns) {
-        //       continue;
-        //     }
-        //     const spriteBit = (spriteByte >> (7 - xbit)) & 1;
-        //     const displayBit = this.c8.display.getPixel(vx + xbit, vy + row);
-        //     // XOR logic and setting of VF
-        //     if (spriteBit && displayBit) {
-        //       this.#register_V[0xf] = 1;
-        //       this.c8.display.setPixel(vx + xbit, vy + row, 0);
-        //     }
-        //     if (spriteBit && !displayBit) {
-        //       this.c8.display.setPixel(vx + xbit, vy + row, 1);
-        //     }
-        //   }
-        // }
-
         this.drawFlag = true;
       },
       /**
@@ -554,15 +543,8 @@ export class CPU {
        * Wait for a key press and store the value of the key into VX.
        */
       LD_VX_KEY: () => {
-        let pressed = false;
-        for (const [i, bit] of this.#keysBuff.entries()) {
-          if (bit) {
-            this.#register_V[x] = i;
-            pressed = true;
-            break;
-          }
-        }
-        if (!pressed) this.#register_PC -= 2;
+        // 记下此刻已经按住的键，之后只接受新按下的键，松开后才写入 VX
+        this.#keyWait = { x, held: this.#keysMask(), pressed: null };
       },
       /**
        * FX15
@@ -664,6 +646,42 @@ export class CPU {
     return this.c8.keyboard.keysBuff;
   }
 
+  /** 当前按住的键，第 i 位对应第 i 个键 */
+  #keysMask() {
+    let mask = 0;
+    for (const [i, bit] of this.#keysBuff.entries()) {
+      if (bit) mask |= 1 << i;
+    }
+    return mask;
+  }
+
+  /** 当前分辨率下屏幕的宽和高 */
+  #displaySize(): [number, number] {
+    return this.c8.highRes
+      ? [this.c8.display.columns, this.c8.display.rows]
+      : [this.c8.display.columns / 2, this.c8.display.rows / 2];
+  }
+
+  /**
+   * 检查 FX0A 等待的按键，按下的键松开时写入 VX 并结束等待，返回是否结束
+   */
+  #pollKeyWait() {
+    const wait = this.#keyWait!;
+    const keys = this.#keysMask();
+    if (wait.pressed !== null) {
+      if (keys & (1 << wait.pressed)) return false;
+      this.#register_V[wait.x] = wait.pressed;
+      this.#keyWait = null;
+      return true;
+    }
+    const pressed = keys & ~wait.held;
+    if (pressed) {
+      wait.pressed = 31 - Math.clz32(pressed & -pressed);
+    }
+    wait.held = keys;
+    return false;
+  }
+
   drawFlag = false;
 
   constructor(chip8: Chip8) {
@@ -707,6 +725,34 @@ export class CPU {
 
     this.drawFlag = false;
     this.running = true;
+    this.#keyWait = null;
+  }
+
+  /**
+   * 固定随机数种子，使 CXNN 的结果可以复现
+   */
+  seed(seed: number) {
+    this.#rng = new Xorshift32(seed);
+  }
+
+  /**
+   * 当前的寄存器与计时器，用于执行轨迹
+   */
+  snapshot() {
+    return {
+      pc: this.#register_PC,
+      v: Array.from(this.#register_V),
+      i: this.#register_Index,
+      dt: this.#delayTimer,
+      st: this.soundTimer,
+    };
+  }
+
+  /**
+   * 读取 addr 处的指令，不移动 PC
+   */
+  peekOpcode(addr: number) {
+    return (this.#memory[addr] << 8) | this.#memory[addr + 1];
   }
 
   fetch() {
@@ -790,6 +836,11 @@ export class CPU {
   }
 
   cycle() {
+    // FX0A 等待按键时不取指令
+    if (this.#keyWait) {
+      this.#pollKeyWait();
+      return;
+    }
     // 指令循环 fetch-decode-execute
     // fetch
     const instruction = this.fetch();
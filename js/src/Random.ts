@@ -0,0 +1,31 @@
+/**
+ * xorshift32 随机数发生器
+ *
+ * 与 Rust 版本（rust/packages/core/src/rng.rs）算法一致，同样的种子得到同样的序列，
+ * 两个实现可以逐条指令对比执行结果。
+ */
+export class Xorshift32 {
+  #state: number;
+
+  constructor(seed: number) {
+    seed >>>= 0;
+    // 种子为 0 时 xorshift 会一直输出 0，这里换成一个固定的非零值
+    this.#state = seed === 0 ? 0x2545f491 : seed;
+  }
+
+  nextU32() {
+    let x = this.#state;
+    x ^= x << 13;
+    x >>>= 0;
+    x ^= x >>> 17;
+    x ^= x << 5;
+    x >>>= 0;
+    this.#state = x;
+    return x;
+  }
+
+  /** 0 ~ 255，取高 8 位 */
+  nextByte() {
+    return this.nextU32() >>> 24;
+  }
+}
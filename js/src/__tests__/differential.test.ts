@@ -0,0 +1,93 @@
+import { execFileSync } from 'child_process';
+import { mkdtempSync, readFileSync, writeFileSync } from 'fs';
+import * as os from 'os';
+import * as path from 'path';
+import { firstDivergence, InputEvent, runTrace } from '../trace';
+
+/**
+ * 用同样的种子和输入分别运行 Rust 与 TypeScript 两个实现，逐条对比执行轨迹。
+ * 需要本机可以执行 cargo。
+ */
+
+const ROOT = path.resolve(__dirname, '../../..');
+const SEED = 0xc8;
+const STEPS = 3000;
+
+/** 先后按一下 1 和 4（键盘上的 1、q），让等待按键（FX0A）的 ROM 走下去 */
+const PRESSES: InputEvent[] = [
+  { frame: 20, key: '1', down: true },
+  { frame: 24, key: '1', down: false },
+  { frame: 60, key: 'q', down: true },
+  { frame: 64, key: 'q', down: false },
+];
+
+const ROMS: [string, InputEvent[]][] = [
+  ['chip8/TEST/IBM.ch8', []],
+  ['chip8/TEST/test_opcode.ch8', []],
+  ['chip8/TEST/test_bc.ch8', []],
+  ['chip8/BRIX.ch8', []],
+  ['chip8/MAZE.ch8', []],
+  ['chip8/PONG.ch8', []],
+  ['chip8/TICTAC.ch8', PRESSES],
+  ['chip8/CONNECT4.ch8', PRESSES],
+];
+
+/** 写成 `--input` 接受的格式：每行 `<frame> <key> down|up` */
+function inputFile(inputs: InputEvent[]) {
+  const dir = mkdtempSync(path.join(os.tmpdir(), 'chip8-'));
+  const file = path.join(dir, 'input');
+  writeFileSync(
+    file,
+    inputs
+      .map((it) => `${it.frame} ${it.key} ${it.down ? 'down' : 'up'}\n`)
+      .join('')
+  );
+  return file;
+}
+
+function rustTrace(rom: string, inputs: InputEvent[]) {
+  const output = execFileSync(
+    'cargo',
+    [
+      'run',
+      '--quiet',
+      '--manifest-path',
+      path.join(ROOT, 'rust/Cargo.toml'),
+      '-p',
+      'chip8_core',
+      '--',
+      'trace',
+      rom,
+      '--seed',
+      String(SEED),
+      '--steps',
+      String(STEPS),
+      '--input',
+      inputFile(inputs),
+    ],
+    { encoding: 'utf8', maxBuffer: 64 * 1024 * 1024 }
+  );
+  return output.trim().split('\n');
+}
+
+describe('differential trace', () => {
+  it.each(ROMS)('%s runs the same in Rust and TypeScript', (rom, inputs) => {
+    const romPath = path.join(ROOT, 'assets/roms', rom);
+    const ts = runTrace(new Uint8Array(readFileSync(romPath)), {
+      seed: SEED,
+      steps: STEPS,
+      inputs,
+    });
+    const divergence = firstDivergence(rustTrace(romPath, inputs), ts);
+
+    if (divergence) {
+      throw new Error(
+        [
+          `first divergence at record ${divergence.index}`,
+          `  rust: ${divergence.left ?? '<end of trace>'}`,
+          `  ts:   ${divergence.right ?? '<end of trace>'}`,
+        ].join('\n')
+      );
+    }
+  });
+});
@@ -1,6 +1,7 @@
 import { Chip8 } from './Chip8';
 
 export { KEY_MAP } from './Keyboard';
+export { runTrace, firstDivergence } from './trace';
 
 export function createChip8() {
   return new Chip8();
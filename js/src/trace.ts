@@ -0,0 +1,102 @@
+import { Chip8 } from './Chip8';
+
+/**
+ * 执行轨迹，与 Rust 版本（rust/packages/core/src/trace.rs）的文本格式一致：
+ *
+ * `<step> <PC> <opcode> V:<V0..VF> I:<I> DT:<DT> ST:<ST>`
+ *
+ * PC 与 opcode 是这一步开始时的值，寄存器与计时器是这一步执行完之后的值。
+ */
+
+/** 在某一帧开始时按下或松开一个键 */
+export interface InputEvent {
+  frame: number;
+  key: string;
+  down: boolean;
+}
+
+export interface TraceOptions {
+  seed: number;
+  steps: number;
+  /** 每帧执行的指令数，每帧结束时调用一次 ticker */
+  cyclesPerFrame?: number;
+  inputs?: InputEvent[];
+}
+
+const hex = (value: number, width: number) =>
+  value.toString(16).toUpperCase().padStart(width, '0');
+
+export function formatRecord(
+  step: number,
+  pc: number,
+  opcode: number,
+  state: ReturnType<Chip8['cpu']['snapshot']>
+) {
+  return [
+    String(step).padStart(8, '0'),
+    hex(pc, 4),
+    hex(opcode, 4),
+    `V:${state.v.map((reg) => hex(reg, 2)).join('')}`,
+    `I:${hex(state.i, 4)}`,
+    `DT:${hex(state.dt, 2)}`,
+    `ST:${hex(state.st, 2)}`,
+  ].join(' ');
+}
+
+/**
+ * 按与 Rust `trace::run` 相同的节奏运行 ROM，每一步返回一条记录
+ */
+export function runTrace(rom: Uint8Array, options: TraceOptions) {
+  const { seed, steps, cyclesPerFrame = 8, inputs = [] } = options;
+  const chip8 = new Chip8();
+  chip8.reset();
+  chip8.cpu.seed(seed);
+  chip8.loadRom(rom);
+
+  const records: string[] = [];
+  for (let step = 0; step < steps; step++) {
+    if (step % cyclesPerFrame === 0) {
+      const frame = step / cyclesPerFrame;
+      for (const event of inputs.filter((it) => it.frame === frame)) {
+        if (event.down) {
+          chip8.keyboard.keyDown(event.key);
+        } else {
+          chip8.keyboard.keyUp(event.key);
+        }
+      }
+    }
+
+    const { pc } = chip8.cpu.snapshot();
+    const opcode = chip8.cpu.peekOpcode(pc);
+    chip8.cpu.cycle();
+    if ((step + 1) % cyclesPerFrame === 0) {
+      chip8.cpu.ticker();
+    }
+
+    records.push(formatRecord(step, pc, opcode, chip8.cpu.snapshot()));
+  }
+  return records;
+}
+
+export interface Divergence {
+  index: number;
+  /** 某一份提前结束时为 undefined */
+  left?: string;
+  right?: string;
+}
+
+/**
+ * 两份轨迹第一次出现差异的位置，完全一致时返回 null
+ */
+export function firstDivergence(
+  left: string[],
+  right: string[]
+): Divergence | null {
+  const length = Math.max(left.length, right.length);
+  for (let index = 0; index < length; index++) {
+    if (left[index] !== right[index]) {
+      return { index, left: left[index], right: right[index] };
+    }
+  }
+  return null;
+}
@@ -57,20 +57,23 @@ init($sourceSelect.value === 'wasm')
     const draw = () => {
       if (!chip8.isDrawFlag()) return;
       chip8.setDrawFlag(false);
+      const width = chip8.width();
+      const height = chip8.height();
       const cellSize = {
-        w: $canvas.width / chip8.columns(),
-        h: $canvas.height / chip8.rows(),
+        w: $canvas.width / width,
+        h: $canvas.height / height,
       };
 
-      if (!chip8.highRes()) {
-        cellSize.w *= 2;
-        cellSize.h *= 2;
-      }
-
+      const rgba = chip8.framebuffer();
       canvasCtx.clearRect(0, 0, $canvas.width, $canvas.height);
-      for (let y = 0; y < chip8.rows(); y++) {
-        for (let x = 0; x < chip8.columns(); x++) {
-          canvasCtx.fillStyle = chip8.getPixel(x, y) ? '#6cf' : '#000';
+      for (let y = 0; y < height; y++) {
+        for (let x = 0; x < width; x++) {
+          if (rgba) {
+            const i = (y * width + x) * 4;
+            canvasCtx.fillStyle = `rgb(${rgba[i]}, ${rgba[i + 1]}, ${rgba[i + 2]})`;
+          } else {
+            canvasCtx.fillStyle = chip8.getPixel(x, y) ? '#6cf' : '#000';
+          }
           canvasCtx.fillRect(x * cellSize.w, y * cellSize.h, cellSize.w, cellSize.h);
         }
       }
@@ -81,13 +84,7 @@ init($sourceSelect.value === 'wasm')
       stop?.();
 
       let t2 = requestAnimationFrame(function loop(t) {
-        let count = 16 / (1000 / chip8.rate());
-        while (count > 0) {
-          chip8.cycle();
-          count--;
-        }
-
-        chip8.ticker();
+        chip8.runFrame();
         if (chip8.soundTimer()) $audio.play();
         draw();
         t2 = requestAnimationFrame(loop);
@@ -6,10 +6,17 @@ interface IOut {
   columns: () => number;
   rows: () => number;
   highRes: () => boolean;
+  // 当前平台与模式下可见区域的大小
+  width: () => number;
+  height: () => number;
   getPixel: (x: number, y: number) => boolean;
+  // 可见区域的 RGBA 像素，不支持颜色时为 null，按 getPixel 绘制单色画面
+  framebuffer: () => Uint8Array | null;
   rate: () => number;
-  cycle: VoidFunction;
+  cycle: () => boolean | void;
   ticker: VoidFunction;
+  // 运行一帧：执行 rate / 60 条指令后更新计时器
+  runFrame: VoidFunction;
   keyDown: (key: string) => boolean;
   keyUp: (key: string) => boolean;
   reset: VoidFunction;
@@ -29,10 +36,19 @@ async function js(): Promise<IOut> {
     columns: () => chip8.display.columns,
     rows: () => chip8.display.rows,
     highRes: () => chip8.highRes,
+    width: () => (chip8.highRes ? chip8.display.columns : chip8.display.columns / 2),
+    height: () => (chip8.highRes ? chip8.display.rows : chip8.display.rows / 2),
     getPixel: (x: number, y: number) => !!chip8.display.getPixel(x, y),
+    framebuffer: () => null,
     rate: () => chip8.RATE,
     cycle: () => chip8.cpu.cycle(),
     ticker: () => chip8.cpu.ticker(),
+    runFrame: () => {
+      for (let count = 16 / (1000 / chip8.RATE); count > 0; count--) {
+        chip8.cpu.cycle();
+      }
+      chip8.cpu.ticker();
+    },
     keyDown: (key: string) => chip8.keyboard.keyDown(key),
     keyUp: (key: string) => chip8.keyboard.keyUp(key),
     reset: () => chip8.reset(),
@@ -47,6 +63,7 @@ async function wasm(): Promise<IOut> {
   const wasmModule = await import('chip8_rust');
   await wasmModule.default();
   const chip8 = new wasmModule.Emulator();
+  chip8.persist_rpl_flags();
   return {
     isDrawFlag: () => chip8.is_draw_flag(),
     setDrawFlag: (flag: boolean) => {
@@ -55,10 +72,14 @@ async function wasm(): Promise<IOut> {
     columns: () => chip8.get_columns(),
     rows: () => chip8.get_rows(),
     highRes: () => chip8.get_high_res(),
+    width: () => chip8.get_width(),
+    height: () => chip8.get_height(),
     getPixel: (x: number, y: number) => chip8.get_pixel(x, y),
+    framebuffer: () => chip8.get_framebuffer(),
     rate: () => chip8.get_rate(),
     cycle: () => chip8.cycle(),
     ticker: () => chip8.ticker(),
+    runFrame: () => chip8.run_frame(),
     keyDown: (key: string) => chip8.key_down(key.toLowerCase()),
     keyUp: (key: string) => chip8.key_up(key.toLowerCase()),
     reset: () => chip8.reset(),
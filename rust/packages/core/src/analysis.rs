@@ -0,0 +1,289 @@
+//! 静态分析：从入口开始沿控制流遍历可达的指令，根据用到的指令猜测 ROM 需要的平台与 quirks。
+//!
+//! 用于数据库（[`romdb`](crate::romdb)）中没有收录的 ROM。结论是启发式的，附带一个 0 ~ 1 的置信度。
+
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+
+use crate::disasm;
+use crate::platform::Platform;
+use crate::quirks::Quirks;
+use crate::vm::Instruction;
+
+/// FX55、FX65 之后最多向后查看这么多条指令，寻找回到它之前的跳转
+const MEMORY_INCREMENT_WINDOW: usize = 16;
+
+/// 分析中发现的线索
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Hint {
+    /// SUPER-CHIP 才有的指令：00CN、00FB ~ 00FF、DXY0、FX30、FX75、FX85
+    SuperChipOpcode,
+    /// XO-CHIP 才有的指令：00DN、5XY2、5XY3、F000 NNNN、FN01、F002、FX3A
+    XoChipOpcode,
+    /// Y 不为 0 且与 X 不同的 8XY6、8XYE，结果取决于 `shift_vy`
+    ShiftUsesVy,
+    /// 循环执行 FX55、FX65 而不重新设置 I，依赖 `memory_increment`
+    MemoryIncrement,
+    /// NNN 最高位不为 0 的 BNNN，结果取决于 `jump_vx`
+    JumpWithOffset,
+    /// 0NNN 调用机器码，只能在 COSMAC VIP 上运行
+    MachineCall,
+}
+
+impl Hint {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Hint::SuperChipOpcode => "SUPER-CHIP opcode",
+            Hint::XoChipOpcode => "XO-CHIP opcode",
+            Hint::ShiftUsesVy => "shift reads VY",
+            Hint::MemoryIncrement => "FX55/FX65 loop without reloading I",
+            Hint::JumpWithOffset => "BNNN with a register in the high nibble",
+            Hint::MachineCall => "machine code call",
+        }
+    }
+}
+
+/// 一条线索及其所在的指令
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Finding {
+    pub addr: u16,
+    pub ir_code: u16,
+    pub hint: Hint,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04X}  {:04X}  {:<16}  {}",
+            self.addr,
+            self.ir_code,
+            disasm::disassemble(&Instruction::new(self.ir_code)),
+            self.hint.description()
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    /// 可达指令的地址
+    pub reachable: BTreeSet<u16>,
+    pub findings: Vec<Finding>,
+    /// 遇到了无法静态确定目标的 BNNN，可达代码可能不完整
+    pub incomplete: bool,
+    pub platform: Platform,
+    pub quirks: Quirks,
+    /// 推荐结果的可信程度，0 ~ 1
+    pub confidence: f64,
+}
+
+impl Analysis {
+    pub fn has(&self, hint: Hint) -> bool {
+        self.count(hint) > 0
+    }
+
+    pub fn count(&self, hint: Hint) -> usize {
+        self.findings.iter().filter(|f| f.hint == hint).count()
+    }
+
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "reachable instructions: {}", self.reachable.len());
+        if self.incomplete {
+            let _ = writeln!(out, "computed jumps: some code may not have been reached");
+        }
+        let _ = writeln!(
+            out,
+            "platform: {} ({:.0}% confidence)",
+            self.platform.name(),
+            self.confidence * 100.0
+        );
+        let _ = writeln!(out, "quirks: {:?}", self.quirks);
+        let _ = writeln!(out, "\nfindings:");
+        for finding in &self.findings {
+            let _ = writeln!(out, "  {}", finding);
+        }
+        out
+    }
+}
+
+/// 分析加载到 `load_address` 的 `rom`，从 `load_address` 开始遍历
+pub fn analyze(rom: &[u8], load_address: u16) -> Analysis {
+    let code = Code { rom, load_address };
+    let mut reachable = BTreeSet::new();
+    let mut findings = vec![];
+    let mut incomplete = false;
+    let mut pending = vec![load_address];
+
+    while let Some(addr) = pending.pop() {
+        let Some(ir) = code.fetch(addr) else {
+            continue;
+        };
+        if !reachable.insert(addr) {
+            continue;
+        }
+
+        for hint in hints(&code, addr, &ir) {
+            findings.push(Finding {
+                addr,
+                ir_code: ir.ir_code,
+                hint,
+            });
+        }
+
+        let next = addr + code.length(addr);
+        match ir.opcode {
+            0x0000 if ir.ir_code == 0x00ee || ir.ir_code == 0x00fd => {}
+            0x1000 => pending.push(ir.nnn),
+            0x2000 => pending.extend([ir.nnn, next]),
+            0x3000 | 0x4000 | 0x5000 | 0x9000 | 0xe000 => {
+                pending.extend([next, next + code.length(next)])
+            }
+            0xb000 => incomplete = true,
+            _ => pending.push(next),
+        }
+    }
+
+    findings.sort_by_key(|f| (f.addr, f.hint));
+    let mut analysis = Analysis {
+        reachable,
+        findings,
+        incomplete,
+        platform: Platform::Chip8,
+        quirks: Quirks::default(),
+        confidence: 0.0,
+    };
+    recommend(&mut analysis);
+    analysis
+}
+
+/// 内存中 ROM 所在的部分，超出 ROM 的地址视为不可执行
+struct Code<'a> {
+    rom: &'a [u8],
+    load_address: u16,
+}
+
+impl Code<'_> {
+    fn fetch(&self, addr: u16) -> Option<Instruction> {
+        let offset = addr.checked_sub(self.load_address)? as usize;
+        let bytes = self.rom.get(offset..offset + 2)?;
+        Some(Instruction::new((bytes[0] as u16) << 8 | bytes[1] as u16))
+    }
+
+    /// XO-CHIP 的 F000 NNNN 占 4 个字节，跳过它的条件跳转也要跳过 4 个字节
+    fn length(&self, addr: u16) -> u16 {
+        match self.fetch(addr) {
+            Some(ir) if ir.ir_code == 0xf000 => 4,
+            _ => 2,
+        }
+    }
+}
+
+fn hints(code: &Code, addr: u16, ir: &Instruction) -> Vec<Hint> {
+    let mut hints = vec![];
+    match ir.opcode {
+        0x0000 => match ir.nnn {
+            0x0c0..=0x0cf | 0x0fb..=0x0ff => hints.push(Hint::SuperChipOpcode),
+            0x0d0..=0x0df => hints.push(Hint::XoChipOpcode),
+            0x0e0 | 0x0ee => {}
+            _ => hints.push(Hint::MachineCall),
+        },
+        0x5000 if ir.n == 2 || ir.n == 3 => hints.push(Hint::XoChipOpcode),
+        // CHIP-48 的汇编器把 `SHR VX` 编码为 8X06，Y 为 0 时不算
+        0x8000 if (ir.n == 0x6 || ir.n == 0xe) && ir.y != 0 && ir.x != ir.y => {
+            hints.push(Hint::ShiftUsesVy)
+        }
+        0xb000 if ir.x != 0 => hints.push(Hint::JumpWithOffset),
+        0xd000 if ir.n == 0 => hints.push(Hint::SuperChipOpcode),
+        0xf000 => match ir.kk {
+            0x30 | 0x75 | 0x85 => hints.push(Hint::SuperChipOpcode),
+            0x00 | 0x01 | 0x02 | 0x3a => hints.push(Hint::XoChipOpcode),
+            0x55 | 0x65 if loops_over_i(code, addr) => hints.push(Hint::MemoryIncrement),
+            _ => {}
+        },
+        _ => {}
+    }
+    hints
+}
+
+/// `addr` 处的 FX55、FX65 是否在一个循环中，且循环体内没有重新设置 I，
+/// 即每一轮都依赖上一轮执行后 I 的自增
+fn loops_over_i(code: &Code, addr: u16) -> bool {
+    let mut pc = addr + 2;
+    for _ in 0..MEMORY_INCREMENT_WINDOW {
+        let Some(ir) = code.fetch(pc) else {
+            return false;
+        };
+        match ir.opcode {
+            0x1000 if ir.nnn <= addr => {
+                return (ir.nnn..pc)
+                    .step_by(2)
+                    .filter_map(|body| code.fetch(body))
+                    .all(|ir| !sets_i(&ir));
+            }
+            0x1000 => pc = ir.nnn,
+            0x0000 | 0x2000 | 0xb000 => return false,
+            _ => pc += 2,
+        }
+    }
+    false
+}
+
+/// 不依赖 I 原来的值而重新设置 I 的指令
+fn sets_i(ir: &Instruction) -> bool {
+    ir.opcode == 0xa000 || (ir.opcode == 0xf000 && matches!(ir.kk, 0x1e | 0x29 | 0x30))
+}
+
+/// 根据线索推荐平台与 quirks，并估计置信度
+fn recommend(analysis: &mut Analysis) {
+    let xo = analysis.count(Hint::XoChipOpcode);
+    let schip = analysis.count(Hint::SuperChipOpcode);
+    let (platform, evidence) = if xo > 0 {
+        (Platform::XoChip, xo)
+    } else if schip > 0 {
+        (Platform::SuperChip, schip)
+    } else {
+        (Platform::Chip8, 0)
+    };
+
+    let mut quirks = platform.quirks();
+    // 没有扩展指令时，平台只是默认值
+    let mut confidence: f64 = match evidence {
+        0 => 0.6,
+        1 => 0.75,
+        _ => 0.9,
+    };
+
+    if analysis.has(Hint::MachineCall) {
+        if platform == Platform::Chip8 {
+            quirks = Quirks::vip();
+        } else {
+            confidence -= 0.2;
+        }
+    }
+    // 很多 CHIP-48 程序也这样写移位，在扩展平台上只说明结果取决于 quirk
+    if analysis.has(Hint::ShiftUsesVy) && !quirks.shift_vy {
+        if platform == Platform::Chip8 {
+            quirks.shift_vy = true;
+        } else {
+            confidence -= 0.1;
+        }
+    }
+    // 循环依赖 I 的自增，不打开这个 quirk 程序一定不能正常运行
+    if analysis.has(Hint::MemoryIncrement) && !quirks.memory_increment {
+        quirks.memory_increment = true;
+        if platform != Platform::Chip8 {
+            confidence -= 0.2;
+        }
+    }
+    if analysis.incomplete {
+        confidence -= 0.1;
+    }
+
+    analysis.platform = platform;
+    analysis.quirks = quirks;
+    analysis.confidence = confidence.clamp(0.1, 0.95);
+}
@@ -0,0 +1,327 @@
+//! RCA CDP1802 CPU，COSMAC VIP 的处理器。
+//!
+//! 只模拟指令层面的行为与机器周期数：普通指令 2 个周期，长跳转与长跳过 3 个周期，响应中断 1 个周期。
+//! 内存、I/O 端口与 EF1~EF4 标志由 [`Bus`] 提供，DMA 由外部调用 [`Cdp1802::dma_out`] 完成。
+
+/// CPU 看到的外部世界
+pub trait Bus {
+    fn read(&mut self, addr: u16) -> u8;
+
+    fn write(&mut self, addr: u16, value: u8);
+
+    /// OUT N（N 为 1~7），`value` 为 M(R(X))
+    fn output(&mut self, port: u8, value: u8);
+
+    /// INP N（N 为 1~7），返回值写入 D 与 M(R(X))
+    fn input(&mut self, port: u8) -> u8;
+
+    /// EF1~EF4 标志，`flag` 为 1~4
+    fn flag(&mut self, flag: u8) -> bool;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cdp1802 {
+    /// 16 个 16 位寄存器 R0~RF
+    pub r: [u16; 16],
+    /// 程序计数器所在的寄存器编号
+    pub p: u8,
+    /// 数据指针所在的寄存器编号
+    pub x: u8,
+    pub d: u8,
+    pub df: bool,
+    /// 中断时保存的 X、P
+    pub t: u8,
+    /// 中断允许
+    pub ie: bool,
+    pub q: bool,
+    /// 执行了 IDL，等待 DMA 或中断
+    pub idle: bool,
+    /// 已经执行的机器周期数
+    pub cycles: u64,
+}
+
+impl Cdp1802 {
+    /// 复位后的状态：X、P、R0、Q 为 0，允许中断
+    pub fn new() -> Self {
+        Cdp1802 {
+            r: [0; 16],
+            p: 0,
+            x: 0,
+            d: 0,
+            df: false,
+            t: 0,
+            ie: true,
+            q: false,
+            idle: false,
+            cycles: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// 中断请求有效时调用：IE 为 1 时把 X、P 保存到 T，P = 1，X = 2，关闭中断，返回是否响应
+    pub fn interrupt(&mut self) -> bool {
+        if !self.ie {
+            return false;
+        }
+        self.t = self.x << 4 | self.p;
+        self.p = 1;
+        self.x = 2;
+        self.ie = false;
+        self.idle = false;
+        self.cycles += 1;
+        true
+    }
+
+    /// 一个 DMA 输出周期：读出 M(R0) 交给外设，R0 加 1
+    pub fn dma_out(&mut self, bus: &mut impl Bus) -> u8 {
+        let value = bus.read(self.r[0]);
+        self.r[0] = self.r[0].wrapping_add(1);
+        self.idle = false;
+        self.cycles += 1;
+        value
+    }
+
+    fn fetch(&mut self, bus: &mut impl Bus) -> u8 {
+        let pc = self.p as usize;
+        let value = bus.read(self.r[pc]);
+        self.r[pc] = self.r[pc].wrapping_add(1);
+        value
+    }
+
+    fn rx(&self) -> u16 {
+        self.r[self.x as usize]
+    }
+
+    fn inc_x(&mut self) {
+        let x = self.x as usize;
+        self.r[x] = self.r[x].wrapping_add(1);
+    }
+
+    /// D = a + b + carry，DF 为进位
+    fn add(&mut self, a: u8, b: u8, carry: bool) {
+        let sum = a as u16 + b as u16 + carry as u16;
+        self.d = sum as u8;
+        self.df = sum > 0xff;
+    }
+
+    /// D = a - b - borrow，DF 为 1 表示没有借位
+    fn sub(&mut self, a: u8, b: u8, borrow: bool) {
+        let diff = a as i16 - b as i16 - borrow as i16;
+        self.d = diff as u8;
+        self.df = diff >= 0;
+    }
+
+    fn short_branch(&mut self, bus: &mut impl Bus, taken: bool) {
+        let pc = self.p as usize;
+        if taken {
+            let low = bus.read(self.r[pc]);
+            self.r[pc] = self.r[pc] & 0xff00 | low as u16;
+        } else {
+            self.r[pc] = self.r[pc].wrapping_add(1);
+        }
+    }
+
+    fn long_branch(&mut self, bus: &mut impl Bus, taken: bool) {
+        let pc = self.p as usize;
+        if taken {
+            let high = bus.read(self.r[pc]);
+            let low = bus.read(self.r[pc].wrapping_add(1));
+            self.r[pc] = u16::from_be_bytes([high, low]);
+        } else {
+            self.r[pc] = self.r[pc].wrapping_add(2);
+        }
+    }
+
+    fn long_skip(&mut self, taken: bool) {
+        if taken {
+            let pc = self.p as usize;
+            self.r[pc] = self.r[pc].wrapping_add(2);
+        }
+    }
+
+    /// 执行一条指令，返回用掉的机器周期数。IDL 状态下不执行，返回 0
+    pub fn step(&mut self, bus: &mut impl Bus) -> u8 {
+        if self.idle {
+            return 0;
+        }
+        let opcode = self.fetch(bus);
+        let n = opcode & 0xf;
+        let rn = n as usize;
+        let mut cycles = 2;
+        match opcode >> 4 {
+            0x0 if n == 0 => self.idle = true,
+            0x0 => self.d = bus.read(self.r[rn]),
+            0x1 => self.r[rn] = self.r[rn].wrapping_add(1),
+            0x2 => self.r[rn] = self.r[rn].wrapping_sub(1),
+            0x3 => {
+                let condition = match n & 0x7 {
+                    0x0 => true,
+                    0x1 => self.q,
+                    0x2 => self.d == 0,
+                    0x3 => self.df,
+                    flag => bus.flag(flag - 3),
+                };
+                // 0x38 为 SKP：不跳转，跳过一个字节
+                let taken = if n == 0x8 {
+                    false
+                } else {
+                    condition ^ (n >= 0x8)
+                };
+                self.short_branch(bus, taken);
+            }
+            0x4 => {
+                self.d = bus.read(self.r[rn]);
+                self.r[rn] = self.r[rn].wrapping_add(1);
+            }
+            0x5 => bus.write(self.r[rn], self.d),
+            0x6 => match n {
+                0x0 => self.inc_x(),
+                0x1..=0x7 => {
+                    let value = bus.read(self.rx());
+                    bus.output(n, value);
+                    self.inc_x();
+                }
+                // 0x68 在 CDP1802 上没有定义
+                0x8 => {}
+                _ => {
+                    self.d = bus.input(n - 8);
+                    bus.write(self.rx(), self.d);
+                }
+            },
+            0x7 => self.execute_7(bus, n),
+            0x8 => self.d = self.r[rn] as u8,
+            0x9 => self.d = (self.r[rn] >> 8) as u8,
+            0xa => self.r[rn] = self.r[rn] & 0xff00 | self.d as u16,
+            0xb => self.r[rn] = self.r[rn] & 0x00ff | (self.d as u16) << 8,
+            0xc => {
+                cycles = 3;
+                match n {
+                    0x4 => {}
+                    0xc => self.long_skip(self.ie),
+                    0x5 | 0x6 | 0x7 | 0x8 | 0xd | 0xe | 0xf => {
+                        let condition = match n & 0x3 {
+                            0x0 => true,
+                            0x1 => self.q,
+                            0x2 => self.d == 0,
+                            _ => self.df,
+                        };
+                        // C5~C7 为条件不成立时跳过，C8 为无条件跳过
+                        let taken = if n == 0x8 {
+                            true
+                        } else {
+                            condition ^ (n < 0x8)
+                        };
+                        self.long_skip(taken);
+                    }
+                    _ => {
+                        let condition = match n & 0x3 {
+                            0x0 => true,
+                            0x1 => self.q,
+                            0x2 => self.d == 0,
+                            _ => self.df,
+                        };
+                        self.long_branch(bus, condition ^ (n >= 0x8));
+                    }
+                }
+            }
+            0xd => self.p = n,
+            0xe => self.x = n,
+            _ => self.execute_f(bus, n),
+        }
+        self.cycles += cycles as u64;
+        cycles
+    }
+
+    fn execute_7(&mut self, bus: &mut impl Bus, n: u8) {
+        match n {
+            0x0 | 0x1 => {
+                let value = bus.read(self.rx());
+                self.inc_x();
+                self.x = value >> 4;
+                self.p = value & 0xf;
+                self.ie = n == 0x0;
+            }
+            0x2 => {
+                self.d = bus.read(self.rx());
+                self.inc_x();
+            }
+            0x3 => {
+                bus.write(self.rx(), self.d);
+                let x = self.x as usize;
+                self.r[x] = self.r[x].wrapping_sub(1);
+            }
+            0x4 => {
+                let m = bus.read(self.rx());
+                self.add(m, self.d, self.df);
+            }
+            0x5 => {
+                let m = bus.read(self.rx());
+                self.sub(m, self.d, !self.df);
+            }
+            0x6 => {
+                let carry = self.df;
+                self.df = self.d & 1 != 0;
+                self.d = self.d >> 1 | (carry as u8) << 7;
+            }
+            0x7 => {
+                let m = bus.read(self.rx());
+                self.sub(self.d, m, !self.df);
+            }
+            0x8 => bus.write(self.rx(), self.t),
+            0x9 => {
+                self.t = self.x << 4 | self.p;
+                bus.write(self.r[2], self.t);
+                self.x = self.p;
+                self.r[2] = self.r[2].wrapping_sub(1);
+            }
+            0xa => self.q = false,
+            0xb => self.q = true,
+            0xc => {
+                let m = self.fetch(bus);
+                self.add(m, self.d, self.df);
+            }
+            0xd => {
+                let m = self.fetch(bus);
+                self.sub(m, self.d, !self.df);
+            }
+            0xe => {
+                let carry = self.df;
+                self.df = self.d & 0x80 != 0;
+                self.d = self.d << 1 | carry as u8;
+            }
+            _ => {
+                let m = self.fetch(bus);
+                self.sub(self.d, m, !self.df);
+            }
+        }
+    }
+
+    fn execute_f(&mut self, bus: &mut impl Bus, n: u8) {
+        // F0~F7 的操作数是 M(R(X))，F8~FF 是紧跟的立即数
+        let operand = match n {
+            0x6 | 0xe => 0,
+            0x0..=0x7 => bus.read(self.rx()),
+            _ => self.fetch(bus),
+        };
+        match n & 0x7 {
+            0x0 => self.d = operand,
+            0x1 => self.d |= operand,
+            0x2 => self.d &= operand,
+            0x3 => self.d ^= operand,
+            0x4 => self.add(operand, self.d, false),
+            0x5 => self.sub(operand, self.d, false),
+            0x6 if n == 0x6 => {
+                self.df = self.d & 1 != 0;
+                self.d >>= 1;
+            }
+            0x6 => {
+                self.df = self.d & 0x80 != 0;
+                self.d <<= 1;
+            }
+            _ => self.sub(self.d, operand, false),
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use alloc::format;
+use alloc::string::String;
+
+use crate::font::FontSet;
+use crate::hardware::MAX_MEMORY_SIZE;
+
+/// 机器的内存配置：程序加载地址、入口地址、内存大小与内置字体
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MachineConfig {
+    /// ROM 的加载地址
+    pub load_address: u16,
+    /// PC 的初始值
+    pub entry_point: u16,
+    /// 内存大小（字节），4 KiB ~ 16 MiB。PC 只有 16 位，64 KiB 之后的内存只能通过 I 访问
+    pub memory_size: usize,
+    /// `reset` 时写入内存的字体，FX29、FX30 据此取得字形地址
+    pub font_set: FontSet,
+    /// 小字体的起始地址，默认为 `FontSet::small_base`
+    pub small_font_base: u16,
+    /// 大字体的起始地址，默认为 `FontSet::large_base`
+    pub large_font_base: u16,
+}
+
+impl MachineConfig {
+    /// COSMAC VIP，使用 VIP 解释器的字形
+    pub fn vip() -> Self {
+        MachineConfig {
+            font_set: FontSet::Vip,
+            ..Self::default()
+        }
+    }
+
+    /// DREAM 6800，使用它自己的字形
+    pub fn dream6800() -> Self {
+        MachineConfig {
+            font_set: FontSet::Dream6800,
+            ..Self::default()
+        }
+    }
+
+    /// ETI-660 上的程序从 0x600 开始
+    pub fn eti660() -> Self {
+        MachineConfig {
+            load_address: 0x600,
+            entry_point: 0x600,
+            font_set: FontSet::Eti660,
+            ..Self::default()
+        }
+    }
+
+    /// HIRES CHIP-8：ROM 从 0x200 加载，跳过开头切换 VIP 解释器的引导代码，从 0x2C0 开始执行
+    pub fn hires() -> Self {
+        MachineConfig {
+            entry_point: 0x2C0,
+            ..Self::default()
+        }
+    }
+
+    /// CHIP-8X 的解释器更大，程序从 0x300 开始
+    pub fn chip8x() -> Self {
+        MachineConfig {
+            load_address: 0x300,
+            entry_point: 0x300,
+            font_set: FontSet::Vip,
+            ..Self::default()
+        }
+    }
+
+    /// XO-CHIP 有 64 KiB 内存，使用 Octo 的字形
+    pub fn xochip() -> Self {
+        MachineConfig {
+            memory_size: 0x10000,
+            font_set: FontSet::Octo,
+            large_font_base: FontSet::Octo.large_base(),
+            ..Self::default()
+        }
+    }
+
+    /// 检查各地址是否落在内存范围内，字体是否与程序重叠
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0x1000..=MAX_MEMORY_SIZE).contains(&self.memory_size) {
+            return Err(format!(
+                "memory size must be between 4 KiB and 16 MiB, got {} bytes",
+                self.memory_size
+            ));
+        }
+        if self.load_address as usize >= self.memory_size {
+            return Err(format!(
+                "load address 0x{:X} is outside memory",
+                self.load_address
+            ));
+        }
+        if self.entry_point as usize + 1 >= self.memory_size {
+            return Err(format!(
+                "entry point 0x{:X} is outside memory",
+                self.entry_point
+            ));
+        }
+        let small =
+            self.small_font_base..self.small_font_base.saturating_add(self.small_font_len());
+        let large =
+            self.large_font_base..self.large_font_base.saturating_add(self.large_font_len());
+        if !large.is_empty() && small.start < large.end && large.start < small.end {
+            return Err(format!(
+                "small font at 0x{:X} overlaps the large font at 0x{:X}",
+                small.start, large.start
+            ));
+        }
+        if self.font_end() > self.load_address {
+            return Err(format!(
+                "font data ends at 0x{:X}, past the load address 0x{:X}",
+                self.font_end(),
+                self.load_address
+            ));
+        }
+        Ok(())
+    }
+
+    fn small_font_len(&self) -> u16 {
+        self.font_set.small_glyphs().len() as u16
+    }
+
+    fn large_font_len(&self) -> u16 {
+        self.font_set.large_glyphs().len() as u16
+    }
+
+    /// FX29 取得的字形地址
+    pub fn small_glyph(&self, digit: u8) -> u16 {
+        self.small_font_base.wrapping_add((digit & 0xF) as u16 * 5)
+    }
+
+    /// FX30 取得的字形地址，没有这个大字形时退回小字形
+    pub fn large_glyph(&self, digit: u8) -> u16 {
+        if (digit as u16 + 1) * 10 <= self.large_font_len() {
+            self.large_font_base.wrapping_add(digit as u16 * 10)
+        } else {
+            self.small_glyph(digit)
+        }
+    }
+
+    /// 字形占用的最高地址（不含）
+    pub fn font_end(&self) -> u16 {
+        let small = self.small_font_base.saturating_add(self.small_font_len());
+        let large = self.large_font_base.saturating_add(self.large_font_len());
+        small.max(large)
+    }
+
+    /// 加载地址之后可以存放 ROM 的字节数
+    pub fn program_space(&self) -> usize {
+        self.memory_size - self.load_address as usize
+    }
+}
+
+impl Default for MachineConfig {
+    /// 4 KiB 内存，程序从 0x200 开始，与 COSMAC VIP 相同；字体沿用本模拟器一直使用的 SUPER-CHIP 字形
+    fn default() -> Self {
+        MachineConfig {
+            load_address: 0x200,
+            entry_point: 0x200,
+            memory_size: 0x1000,
+            font_set: FontSet::default(),
+            small_font_base: FontSet::default().small_base(),
+            large_font_base: FontSet::default().large_base(),
+        }
+    }
+}
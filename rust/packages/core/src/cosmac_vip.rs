@@ -0,0 +1,350 @@
+//! 原版 COSMAC VIP：用 [`Cdp1802`] 执行用户提供的 CHIP-8 解释器二进制，而不是在 [`Chip8`](crate::vm::Chip8)
+//! 中直接解释 CHIP-8 指令。VIP quirks、绘制后等待 vblank、随机数等行为都由解释器本身决定。
+//!
+//! 内存布局：RAM 从 0x0000 开始（默认 4 KiB，镜像到 0x7FFF），解释器放在 0x0000，CHIP-8 程序放在 0x0200；
+//! 0x8000 起是 512 字节的监控 ROM。CDP1861 每帧 262 行、每行 14 个机器周期，第 62 行发出中断，
+//! 第 64~191 行每行用 DMA 读出 8 个字节。128 行画面按 4 行一组对应 [`Screen`] 左上角的 64x32。
+//!
+//! 没有提供监控 ROM 时，在 0x8146 放一段自带的中断程序（解释器把 R1 指向这里），行为与 VIP 上的相同：
+//! 从 RB.1 指向的页显示画面，R8.1 与 R8.0 作为 delay 与 sound 计时器递减，sound 计时器归零时关闭 Q。
+//! 启动时直接从 0x0000 运行解释器，R1.1 为 RAM 最高页，与监控 ROM 交给解释器时相同。
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+
+use crate::cdp1802::{Bus, Cdp1802};
+use crate::hardware::{Keyboard, Screen};
+
+/// VIP 的时钟频率（Hz）
+pub const CLOCK_HZ: u32 = 1_760_900;
+/// 每个机器周期 8 个时钟
+pub const CYCLES_PER_SECOND: u32 = CLOCK_HZ / 8;
+/// CDP1861 每行的机器周期数
+pub const CYCLES_PER_LINE: u32 = 14;
+pub const LINES_PER_FRAME: u32 = 262;
+/// 每帧（约 1/60 秒）的机器周期数
+pub const CYCLES_PER_FRAME: u32 = CYCLES_PER_LINE * LINES_PER_FRAME;
+
+/// CDP1861 发出中断的行，中断保持两行
+const INTERRUPT_LINE: u32 = 62;
+/// 第一行与最后一行画面
+const FIRST_DISPLAY_LINE: u32 = 64;
+const LAST_DISPLAY_LINE: u32 = 191;
+/// 每行前 6 个周期留给 CPU，之后 8 个周期 DMA
+const DMA_OFFSET: u32 = 6;
+
+/// 监控 ROM 的大小
+pub const MONITOR_SIZE: usize = 0x200;
+const MONITOR_BASE: u16 = 0x8000;
+/// 解释器的大小上限，之后是 CHIP-8 程序
+pub const INTERPRETER_SIZE: usize = 0x200;
+/// RAM 大小的上限，0x8000 起是 ROM
+pub const MAX_RAM_SIZE: usize = 0x8000;
+
+/// 自带的中断程序放在 0x8144，入口为 0x8146
+const INTERRUPT_ROUTINE_OFFSET: usize = 0x144;
+#[rustfmt::skip]
+const INTERRUPT_ROUTINE: [u8; 45] = [
+    0x72,             // 8144: LDXA         出口：恢复 D
+    0x70,             // 8145: RET          恢复 X、P，允许中断
+    0x22,             // 8146: DEC 2        入口
+    0x78,             // 8147: SAV          保存 T
+    0x22,             // 8148: DEC 2
+    0x52,             // 8149: STR 2        保存 D
+    0xC4, 0xC4, 0xC4, // 814A: NOP x3       等到画面开始
+    0xE2,             // 814D: SEX 2
+    0xF8, 0x00,       // 814E: LDI 00
+    0xA0,             // 8150: PLO 0
+    0x9B,             // 8151: GHI B
+    0xB0,             // 8152: PHI 0        R0 = 显示页
+    0x80,             // 8153: GLO 0        每组 4 行的第一行
+    0xE2,             // 8154: SEX 2
+    0xE2,             // 8155: SEX 2        DMA
+    0xA0,             // 8156: PLO 0        R0 退回本组开头
+    0xE2,             // 8157: SEX 2
+    0xE2,             // 8158: SEX 2        DMA
+    0xA0,             // 8159: PLO 0
+    0xE2,             // 815A: SEX 2
+    0xE2,             // 815B: SEX 2        DMA
+    0xA0,             // 815C: PLO 0
+    0xE2,             // 815D: SEX 2
+    0x3C, 0x53,       // 815E: BN1 8153     DMA，EF1 在最后 4 行有效
+    0x98,             // 8160: GHI 8        delay 计时器
+    0x32, 0x66,       // 8161: BZ 8166
+    0xFF, 0x01,       // 8163: SMI 01
+    0xB8,             // 8165: PHI 8
+    0x88,             // 8166: GLO 8        sound 计时器
+    0x32, 0x6F,       // 8167: BZ 816F
+    0xFF, 0x01,       // 8169: SMI 01
+    0xA8,             // 816B: PLO 8
+    0x3A, 0x6F,       // 816C: BNZ 816F
+    0x7A,             // 816E: REQ          归零时关闭蜂鸣器
+    0x30, 0x44,       // 816F: BR 8144
+];
+
+/// 内存与外设，CPU 通过 [`Bus`] 访问
+#[derive(Debug)]
+struct Board {
+    ram: Box<[u8]>,
+    monitor: [u8; MONITOR_SIZE],
+    /// 复位后 ROM 也出现在 0x0000，直到监控程序执行 OUT 4
+    rom_at_zero: bool,
+    /// CDP1861 是否打开（INP 1 打开，OUT 1 关闭）
+    display_on: bool,
+    /// OUT 2 选中的按键，EF3 为它是否按下
+    key_latch: u8,
+    keys: [bool; 16],
+    /// 当前帧内的机器周期
+    frame_cycle: u32,
+}
+
+impl Board {
+    fn line(&self) -> u32 {
+        self.frame_cycle / CYCLES_PER_LINE
+    }
+}
+
+impl Bus for Board {
+    fn read(&mut self, addr: u16) -> u8 {
+        if addr >= MONITOR_BASE || self.rom_at_zero {
+            self.monitor[addr as usize % MONITOR_SIZE]
+        } else {
+            self.ram[addr as usize % self.ram.len()]
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        if addr < MONITOR_BASE {
+            let len = self.ram.len();
+            self.ram[addr as usize % len] = value;
+        }
+    }
+
+    fn output(&mut self, port: u8, value: u8) {
+        match port {
+            1 => self.display_on = false,
+            2 => self.key_latch = value & 0xf,
+            4 => self.rom_at_zero = false,
+            _ => {}
+        }
+    }
+
+    fn input(&mut self, port: u8) -> u8 {
+        if port == 1 {
+            self.display_on = true;
+        }
+        0
+    }
+
+    fn flag(&mut self, flag: u8) -> bool {
+        match flag {
+            // CDP1861：画面开始前与结束前各 4 行
+            1 => {
+                let line = self.line();
+                (FIRST_DISPLAY_LINE - 4..FIRST_DISPLAY_LINE).contains(&line)
+                    || (LAST_DISPLAY_LINE - 3..=LAST_DISPLAY_LINE).contains(&line)
+            }
+            3 => self.keys[self.key_latch as usize],
+            _ => false,
+        }
+    }
+}
+
+/// 运行原版解释器的 COSMAC VIP
+#[derive(Debug)]
+pub struct CosmacVip {
+    pub cpu: Cdp1802,
+    board: Board,
+    pub screen: Screen,
+    pub keyboard: Keyboard,
+    /// 本帧画面有变化
+    pub draw_flag: bool,
+    /// 本帧已经完成 DMA 的下一行
+    next_dma_line: u32,
+    interpreter: [u8; INTERPRETER_SIZE],
+    has_monitor: bool,
+}
+
+impl CosmacVip {
+    /// 用 `interpreter`（例如 VIP 手册中的 CHIP-8 解释器）和 4 KiB RAM 创建
+    pub fn new(interpreter: &[u8]) -> Result<Self, String> {
+        Self::with_ram(interpreter, 0x1000)
+    }
+
+    /// RAM 大小为 `ram_size` 字节，必须是 1 KiB 的倍数且不超过 32 KiB
+    pub fn with_ram(interpreter: &[u8], ram_size: usize) -> Result<Self, String> {
+        if interpreter.len() > INTERPRETER_SIZE {
+            return Err(format!(
+                "interpreter is {} bytes, at most {} bytes fit below 0x200",
+                interpreter.len(),
+                INTERPRETER_SIZE
+            ));
+        }
+        if ram_size == 0 || !ram_size.is_multiple_of(0x400) || ram_size > MAX_RAM_SIZE {
+            return Err(format!(
+                "RAM size must be a multiple of 1 KiB up to 32 KiB, got {} bytes",
+                ram_size
+            ));
+        }
+        let mut monitor = [0; MONITOR_SIZE];
+        monitor[INTERRUPT_ROUTINE_OFFSET..INTERRUPT_ROUTINE_OFFSET + INTERRUPT_ROUTINE.len()]
+            .copy_from_slice(&INTERRUPT_ROUTINE);
+        let mut code = [0; INTERPRETER_SIZE];
+        code[..interpreter.len()].copy_from_slice(interpreter);
+
+        let mut vip = CosmacVip {
+            cpu: Cdp1802::new(),
+            board: Board {
+                ram: alloc::vec![0; ram_size].into_boxed_slice(),
+                monitor,
+                rom_at_zero: false,
+                display_on: false,
+                key_latch: 0,
+                keys: [false; 16],
+                frame_cycle: 0,
+            },
+            screen: Screen::new(),
+            keyboard: Keyboard::new(),
+            draw_flag: false,
+            next_dma_line: FIRST_DISPLAY_LINE,
+            interpreter: code,
+            has_monitor: false,
+        };
+        vip.reset();
+        Ok(vip)
+    }
+
+    /// 使用真实的监控 ROM，之后从 ROM 启动，中断程序也使用 ROM 中的版本
+    pub fn set_monitor(&mut self, rom: &[u8]) -> Result<(), String> {
+        if rom.len() != MONITOR_SIZE {
+            return Err(format!(
+                "monitor ROM must be {} bytes, got {}",
+                MONITOR_SIZE,
+                rom.len()
+            ));
+        }
+        self.board.monitor.copy_from_slice(rom);
+        self.has_monitor = true;
+        self.reset();
+        Ok(())
+    }
+
+    /// 清空 RAM，重新放入解释器并启动
+    pub fn reset(&mut self) {
+        self.board.ram.fill(0);
+        self.board.ram[..INTERPRETER_SIZE].copy_from_slice(&self.interpreter);
+        self.board.key_latch = 0;
+        self.board.frame_cycle = 0;
+        self.next_dma_line = FIRST_DISPLAY_LINE;
+        self.screen.clear();
+        self.keyboard.reset();
+        self.cpu.reset();
+        if self.has_monitor {
+            self.board.rom_at_zero = true;
+            self.board.display_on = false;
+        } else {
+            // 监控 ROM 交给解释器时的状态
+            self.board.rom_at_zero = false;
+            self.board.display_on = true;
+            let top_page = (self.board.ram.len() / 0x100 - 1) as u16;
+            self.cpu.r[1] = top_page << 8;
+        }
+    }
+
+    /// 把 CHIP-8 程序放到 0x0200
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), String> {
+        let space = self.board.ram.len() - INTERPRETER_SIZE;
+        if rom.len() > space {
+            return Err(format!(
+                "ROM is {} bytes, only {} bytes fit in RAM",
+                rom.len(),
+                space
+            ));
+        }
+        self.board.ram[INTERPRETER_SIZE..INTERPRETER_SIZE + rom.len()].copy_from_slice(rom);
+        Ok(())
+    }
+
+    pub fn ram(&self) -> &[u8] {
+        &self.board.ram
+    }
+
+    pub fn ram_mut(&mut self) -> &mut [u8] {
+        &mut self.board.ram
+    }
+
+    /// 蜂鸣器由 Q 驱动
+    pub fn tone(&self) -> bool {
+        self.cpu.q
+    }
+
+    pub fn display_on(&self) -> bool {
+        self.board.display_on
+    }
+
+    /// 运行一帧，即 [`CYCLES_PER_FRAME`] 个机器周期
+    pub fn run_frame(&mut self) {
+        self.board.keys = self.keyboard.keys;
+        while self.board.frame_cycle < CYCLES_PER_FRAME {
+            let line = self.board.line();
+            if self.board.display_on
+                && (INTERRUPT_LINE..FIRST_DISPLAY_LINE).contains(&line)
+                && self.cpu.interrupt()
+            {
+                self.board.frame_cycle += 1;
+                continue;
+            }
+            if self.board.display_on
+                && self.next_dma_line <= LAST_DISPLAY_LINE
+                && self.board.frame_cycle >= self.next_dma_line * CYCLES_PER_LINE + DMA_OFFSET
+            {
+                self.dma_line();
+                continue;
+            }
+
+            let cycles = self.cpu.step(&mut self.board) as u32;
+            self.board.frame_cycle += if cycles == 0 {
+                // IDL：直接等到下一次中断、DMA 或帧结束
+                self.cycles_until_event()
+            } else {
+                cycles
+            };
+        }
+        self.board.frame_cycle -= CYCLES_PER_FRAME;
+        self.next_dma_line = FIRST_DISPLAY_LINE;
+    }
+
+    /// 一行的 DMA：读出 8 个字节，画到这一行所在的屏幕行
+    fn dma_line(&mut self) {
+        let y = ((self.next_dma_line - FIRST_DISPLAY_LINE) / 4) as u8;
+        for byte_index in 0..8u8 {
+            let byte = self.cpu.dma_out(&mut self.board);
+            for bit in 0..8u8 {
+                let on = byte & (0x80 >> bit) != 0;
+                let x = byte_index * 8 + bit;
+                if self.screen.get_pixel(x, y) != on {
+                    self.screen.set_pixel(x, y, on);
+                    self.draw_flag = true;
+                }
+            }
+        }
+        self.board.frame_cycle += 8;
+        self.next_dma_line += 1;
+    }
+
+    fn cycles_until_event(&self) -> u32 {
+        let now = self.board.frame_cycle;
+        let mut next = CYCLES_PER_FRAME;
+        if self.board.display_on {
+            let interrupt = INTERRUPT_LINE * CYCLES_PER_LINE;
+            if now < interrupt && self.cpu.ie {
+                next = next.min(interrupt);
+            }
+            if self.next_dma_line <= LAST_DISPLAY_LINE {
+                next = next.min(self.next_dma_line * CYCLES_PER_LINE + DMA_OFFSET);
+            }
+        }
+        next.saturating_sub(now).max(1)
+    }
+}
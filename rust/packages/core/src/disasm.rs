@@ -0,0 +1,176 @@
+//! 反汇编：把指令翻译成助记符，并按用途分类。译码规则与 `Chip8::execute` 保持一致。
+
+use alloc::format;
+use alloc::string::{String, ToString};
+
+use crate::vm::Instruction;
+
+/// 指令的大类，用于过滤执行轨迹、统计执行次数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum OpcodeClass {
+    /// 跳转、调用、返回
+    Flow,
+    /// 条件跳过下一条指令
+    Skip,
+    /// 寄存器赋值
+    Load,
+    /// 算术与位运算
+    Alu,
+    /// 读写内存、修改 I
+    Memory,
+    /// 清屏、绘制、滚动、切换分辨率
+    Draw,
+    /// 读取按键
+    Input,
+    /// 读写计时器
+    Timer,
+    Random,
+    /// 机器码调用、退出等解释器控制
+    System,
+    /// 无法识别的指令
+    Unknown,
+}
+
+impl OpcodeClass {
+    pub const ALL: [OpcodeClass; 11] = [
+        OpcodeClass::Flow,
+        OpcodeClass::Skip,
+        OpcodeClass::Load,
+        OpcodeClass::Alu,
+        OpcodeClass::Memory,
+        OpcodeClass::Draw,
+        OpcodeClass::Input,
+        OpcodeClass::Timer,
+        OpcodeClass::Random,
+        OpcodeClass::System,
+        OpcodeClass::Unknown,
+    ];
+
+    pub fn of(ir: &Instruction) -> Self {
+        match ir.opcode {
+            0x0000 => match ir.y {
+                0x1 => OpcodeClass::System,
+                0xc => OpcodeClass::Draw,
+                _ => match ir.kk {
+                    0xe0 | 0xfb | 0xfc | 0xfe | 0xff => OpcodeClass::Draw,
+                    0xee => OpcodeClass::Flow,
+                    _ => OpcodeClass::System,
+                },
+            },
+            0x1000 | 0x2000 | 0xb000 => OpcodeClass::Flow,
+            0x3000 | 0x4000 | 0x5000 | 0x9000 => OpcodeClass::Skip,
+            0x6000 => OpcodeClass::Load,
+            0x7000 => OpcodeClass::Alu,
+            0x8000 => match ir.n {
+                0x0 => OpcodeClass::Load,
+                0x1..=0x7 | 0xe => OpcodeClass::Alu,
+                _ => OpcodeClass::Unknown,
+            },
+            0xa000 => OpcodeClass::Memory,
+            0xc000 => OpcodeClass::Random,
+            0xd000 => OpcodeClass::Draw,
+            0xe000 => match ir.kk {
+                0x9e | 0xa1 => OpcodeClass::Skip,
+                _ => OpcodeClass::Unknown,
+            },
+            _ => match ir.kk {
+                0x07 | 0x15 | 0x18 => OpcodeClass::Timer,
+                0x0a => OpcodeClass::Input,
+                0x1e | 0x29 | 0x30 | 0x33 | 0x55 | 0x65 | 0x75 | 0x85 => OpcodeClass::Memory,
+                _ => OpcodeClass::Unknown,
+            },
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            OpcodeClass::Flow => "flow",
+            OpcodeClass::Skip => "skip",
+            OpcodeClass::Load => "load",
+            OpcodeClass::Alu => "alu",
+            OpcodeClass::Memory => "memory",
+            OpcodeClass::Draw => "draw",
+            OpcodeClass::Input => "input",
+            OpcodeClass::Timer => "timer",
+            OpcodeClass::Random => "random",
+            OpcodeClass::System => "system",
+            OpcodeClass::Unknown => "unknown",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|class| class.name() == name)
+    }
+}
+
+/// 助记符，如 `LD V0, 0x0C`、`DRW V0, V1, 15`；无法识别的指令输出为 `DW 0xXXXX`
+pub fn disassemble(ir: &Instruction) -> String {
+    let (x, y, n, kk, nnn) = (ir.x, ir.y, ir.n, ir.kk, ir.nnn);
+    match ir.opcode {
+        0x0000 => match y {
+            0x1 => format!("EXIT {}", n),
+            0xc => format!("SCD {}", n),
+            _ => match kk {
+                0xe0 => "CLS".to_string(),
+                0xee => "RET".to_string(),
+                0xfb => "SCR".to_string(),
+                0xfc => "SCL".to_string(),
+                0xfd => "EXIT".to_string(),
+                0xfe => "LOW".to_string(),
+                0xff => "HIGH".to_string(),
+                _ => format!("SYS 0x{:03X}", nnn),
+            },
+        },
+        0x1000 => format!("JP 0x{:03X}", nnn),
+        0x2000 => format!("CALL 0x{:03X}", nnn),
+        0x3000 => format!("SE V{:X}, 0x{:02X}", x, kk),
+        0x4000 => format!("SNE V{:X}, 0x{:02X}", x, kk),
+        0x5000 => format!("SE V{:X}, V{:X}", x, y),
+        0x6000 => format!("LD V{:X}, 0x{:02X}", x, kk),
+        0x7000 => format!("ADD V{:X}, 0x{:02X}", x, kk),
+        0x8000 => {
+            let op = match n {
+                0x0 => "LD",
+                0x1 => "OR",
+                0x2 => "AND",
+                0x3 => "XOR",
+                0x4 => "ADD",
+                0x5 => "SUB",
+                0x6 => "SHR",
+                0x7 => "SUBN",
+                0xe => "SHL",
+                _ => return unknown(ir),
+            };
+            format!("{} V{:X}, V{:X}", op, x, y)
+        }
+        0x9000 => format!("SNE V{:X}, V{:X}", x, y),
+        0xa000 => format!("LD I, 0x{:03X}", nnn),
+        0xb000 => format!("JP V0, 0x{:03X}", nnn),
+        0xc000 => format!("RND V{:X}, 0x{:02X}", x, kk),
+        0xd000 => format!("DRW V{:X}, V{:X}, {}", x, y, n),
+        0xe000 => match kk {
+            0x9e => format!("SKP V{:X}", x),
+            0xa1 => format!("SKNP V{:X}", x),
+            _ => unknown(ir),
+        },
+        _ => match kk {
+            0x07 => format!("LD V{:X}, DT", x),
+            0x0a => format!("LD V{:X}, K", x),
+            0x15 => format!("LD DT, V{:X}", x),
+            0x18 => format!("LD ST, V{:X}", x),
+            0x1e => format!("ADD I, V{:X}", x),
+            0x29 => format!("LD F, V{:X}", x),
+            0x30 => format!("LD HF, V{:X}", x),
+            0x33 => format!("LD B, V{:X}", x),
+            0x55 => format!("LD [I], V{:X}", x),
+            0x65 => format!("LD V{:X}, [I]", x),
+            0x75 => format!("LD R, V{:X}", x),
+            0x85 => format!("LD V{:X}, R", x),
+            _ => unknown(ir),
+        },
+    }
+}
+
+fn unknown(ir: &Instruction) -> String {
+    format!("DW 0x{:04X}", ir.ir_code)
+}
@@ -1,9 +1,165 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+/// 内存大小的上限：MegaChip 的 24 位地址空间，16 MiB
+pub const MAX_MEMORY_SIZE: usize = 0x100_0000;
+
+/// PC 只有 16 位，指令只能位于前 64 KiB。按地址记录代码的表（解码缓存、自修改检测、profiler）只覆盖这一段
+pub const CODE_SPACE_SIZE: usize = 0x10000;
+
+/// 不超过这个大小（CHIP-8 的 4 KiB）的内存直接放在 [`Memory`] 里，不做堆分配
+const INLINE_MEMORY_SIZE: usize = 0x1000;
+
+/// RAM，只有前 `len` 个字节可见，按切片读写。不超过 4 KiB 时使用内联的数组，
+/// 更大的内存（XO-CHIP 的 64 KiB、MegaChip 的 16 MiB）在创建时于堆上分配一次
+#[derive(Clone)]
+pub struct Memory {
+    bytes: [u8; INLINE_MEMORY_SIZE],
+    heap: Vec<u8>,
+    len: usize,
+}
+
+impl Memory {
+    /// 清零的 `len` 字节内存，`len` 超过 [`MAX_MEMORY_SIZE`] 时取上限
+    pub fn new(len: usize) -> Self {
+        let len = len.min(MAX_MEMORY_SIZE);
+        Memory {
+            bytes: [0; INLINE_MEMORY_SIZE],
+            heap: if len > INLINE_MEMORY_SIZE {
+                vec![0; len]
+            } else {
+                Vec::new()
+            },
+            len,
+        }
+    }
+}
+
+impl Deref for Memory {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        if self.len > INLINE_MEMORY_SIZE {
+            &self.heap
+        } else {
+            &self.bytes[..self.len]
+        }
+    }
+}
+
+impl DerefMut for Memory {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        if self.len > INLINE_MEMORY_SIZE {
+            &mut self.heap
+        } else {
+            &mut self.bytes[..self.len]
+        }
+    }
+}
+
+impl PartialEq for Memory {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for Memory {}
+
+impl fmt::Debug for Memory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// 颜色层的列数，每列 8 个像素宽
+pub const COLOR_ZONE_COLUMNS: usize = 8;
+/// 颜色层的行数，每行 1 个像素高
+pub const COLOR_ZONE_ROWS: usize = 32;
+
+/// CHIP-8X（VP-590 彩色板）的前景色：黑、红、蓝、紫、绿、黄、青、白
+pub const CHIP8X_FOREGROUND: [[u8; 3]; 8] = [
+    [0x00, 0x00, 0x00],
+    [0xff, 0x00, 0x00],
+    [0x00, 0x00, 0xff],
+    [0xff, 0x00, 0xff],
+    [0x00, 0xff, 0x00],
+    [0xff, 0xff, 0x00],
+    [0x00, 0xff, 0xff],
+    [0xff, 0xff, 0xff],
+];
+
+/// CHIP-8X 的背景色，02A0 按黑、蓝、绿、红的顺序切换
+pub const CHIP8X_BACKGROUND: [[u8; 3]; 4] = [
+    [0x00, 0x00, 0x00],
+    [0x00, 0x00, 0x80],
+    [0x00, 0x80, 0x00],
+    [0x80, 0x00, 0x00],
+];
+
+/// 前景色的初始值：红
+const DEFAULT_ZONE_COLOR: u8 = 1;
+
+/// CHIP-8X 的颜色属性：64x32 画面上每个 8x1 的区域一个前景色，整个画面一个背景色。
+/// 清屏不改变颜色，只有 `reset` 恢复初始值
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorLayer {
+    /// [`CHIP8X_BACKGROUND`] 的下标
+    pub background: u8,
+    /// [`CHIP8X_FOREGROUND`] 的下标，按行排列
+    pub zones: [u8; COLOR_ZONE_COLUMNS * COLOR_ZONE_ROWS],
+}
+
+impl ColorLayer {
+    pub fn new() -> Self {
+        ColorLayer {
+            background: 0,
+            zones: [DEFAULT_ZONE_COLOR; COLOR_ZONE_COLUMNS * COLOR_ZONE_ROWS],
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// 02A0：切换到下一个背景色
+    pub fn cycle_background(&mut self) {
+        self.background = (self.background + 1) % CHIP8X_BACKGROUND.len() as u8;
+    }
+
+    /// 设置第 `column` 列、第 `row` 行区域的前景色，超出范围的区域被忽略
+    pub fn set_zone(&mut self, column: usize, row: usize, color: u8) {
+        if column < COLOR_ZONE_COLUMNS && row < COLOR_ZONE_ROWS {
+            self.zones[row * COLOR_ZONE_COLUMNS + column] = color % CHIP8X_FOREGROUND.len() as u8;
+        }
+    }
+
+    /// 像素 (x, y) 所在区域的前景色下标
+    pub fn foreground(&self, x: u8, y: u8) -> u8 {
+        let column = x as usize / 8 % COLOR_ZONE_COLUMNS;
+        let row = y as usize % COLOR_ZONE_ROWS;
+        self.zones[row * COLOR_ZONE_COLUMNS + column]
+    }
+
+    /// 像素 (x, y) 的 RGB 颜色
+    pub fn rgb(&self, x: u8, y: u8, on: bool) -> [u8; 3] {
+        if on {
+            CHIP8X_FOREGROUND[self.foreground(x, y) as usize]
+        } else {
+            CHIP8X_BACKGROUND[self.background as usize]
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Screen {
     pub rows: u8,
     pub columns: u8,
 
-    pub bit_map: Vec<bool>,
+    pub bit_map: [bool; 128 * 64],
+    /// CHIP-8X 的颜色属性，其它平台不使用
+    pub colors: ColorLayer,
 }
 
 impl Screen {
@@ -11,7 +167,8 @@ impl Screen {
         Screen {
             rows: 64,
             columns: 128,
-            bit_map: vec![false; 128 * 64],
+            bit_map: [false; 128 * 64],
+            colors: ColorLayer::new(),
         }
     }
 
@@ -33,15 +190,40 @@ impl Screen {
     }
 }
 
+const IDENTITY_KEYMAP: [u8; 16] = [
+    0x0, 0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, 0x8, 0x9, 0xA, 0xB, 0xC, 0xD, 0xE, 0xF,
+];
+
 #[derive(Debug)]
 pub struct Keyboard {
-    pub keys: Vec<bool>,
+    pub keys: [bool; 16],
+    /// 第 N 项是触发 CHIP-8 按键 N 的键
+    keymap: [u8; 16],
 }
 
 impl Keyboard {
     pub fn new() -> Self {
         Keyboard {
-            keys: vec![false; 16],
+            keys: [false; 16],
+            keymap: IDENTITY_KEYMAP,
+        }
+    }
+
+    /// 设置 `.c8k` 风格的按键映射：`keymap[N]` 是按下后触发 CHIP-8 按键 N 的键，
+    /// 一个键可以同时触发多个按键。为空时恢复一一对应
+    pub fn set_keymap(&mut self, keymap: Option<[u8; 16]>) {
+        self.keymap = keymap.unwrap_or(IDENTITY_KEYMAP);
+    }
+
+    pub fn keymap(&self) -> &[u8; 16] {
+        &self.keymap
+    }
+
+    fn set_key(&mut self, key: usize, down: bool) {
+        for (i, &mapped) in self.keymap.iter().enumerate() {
+            if mapped as usize == key {
+                self.keys[i] = down;
+            }
         }
     }
 
@@ -79,9 +261,18 @@ impl Keyboard {
         self.keys.fill(false);
     }
 
+    /// 当前按下的键，第 N 位对应按键 N
+    pub fn mask(&self) -> u16 {
+        self.keys
+            .iter()
+            .enumerate()
+            .filter(|(_, down)| **down)
+            .fold(0, |mask, (i, _)| mask | (1 << i))
+    }
+
     pub fn key_down(&mut self, key: char) -> bool {
         if let Some(key_code) = self.map_to(key) {
-            self.keys[key_code as usize] = true;
+            self.set_key(key_code, true);
             return true;
         }
         false
@@ -89,7 +280,7 @@ impl Keyboard {
 
     pub fn key_up(&mut self, key: char) -> bool {
         if let Some(key_code) = self.map_to(key) {
-            self.keys[key_code as usize] = false;
+            self.set_key(key_code, false);
             return true;
         }
         false
@@ -1,4 +1,38 @@
+//! CHIP-8 / SUPER-CHIP / XO-CHIP 模拟器核心。
+//!
+//! 默认开启 `std` feature。关闭后以 `#![no_std]` 编译，但仍然依赖 `alloc`，目标平台需要提供全局分配器。
+//! 寄存器、栈、屏幕和键盘都是定长数组，不超过 4 KiB 的内存内联在 VM 中；
+//! 更大的内存、自修改代码的记录（定长，见 [`selfmod::MODIFIED_CODE_CAPACITY`]）以及装箱的随机数源、
+//! RPL 存储和 profiler 都在创建或配置 VM 时分配，
+//! 之后 `cycle` 本身不分配内存。`recompiler` 后端会记录写入日志，不在此列。
+//! 没有 `std` 时不能读取系统熵源，CXNN 默认使用固定种子，用 `Chip8::seed_rng` 或 `Chip8::set_rng` 接入硬件随机数。
+//! `trace` 模块、基于文件的 RPL 存储和 `runner` 中依赖终端与系统时钟的实现只在 `std` 下可用。
+
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(clippy::new_without_default)]
 
+extern crate alloc;
+
+pub mod analysis;
+pub mod cdp1802;
+pub mod config;
+pub mod cosmac_vip;
+pub mod disasm;
+pub mod font;
 pub mod hardware;
+pub mod hash;
+pub mod megachip;
+pub mod platform;
+pub mod profile;
+pub mod quirks;
+pub mod recompiler;
+pub mod rng;
+pub mod romdb;
+pub mod rpl;
+pub mod runner;
+pub mod screenshot;
+pub mod selfmod;
+pub mod test_runner;
+#[cfg(feature = "std")]
+pub mod trace;
 pub mod vm;
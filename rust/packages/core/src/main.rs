@@ -1,4 +1,516 @@
+use std::io::{self, BufWriter, Write};
+use std::time::{Duration, Instant};
+use std::{env, fs, process};
+
+use chip8_core::analysis;
+use chip8_core::config::MachineConfig;
+use chip8_core::cosmac_vip::CosmacVip;
+use chip8_core::disasm::OpcodeClass;
+use chip8_core::hash;
+use chip8_core::platform::Platform;
+use chip8_core::profile::Profiler;
+use chip8_core::recompiler::Recompiler;
+use chip8_core::romdb;
+use chip8_core::runner::{
+    self, AudioSink, BellAudio, Clock, DisplaySink, FixedClock, InputSource, NoAudio, NoDisplay,
+    RealTimeClock, Runner, ScriptedInput, TextDisplay,
+};
+use chip8_core::screenshot::{Colors, ImageFormat, Screenshot};
+use chip8_core::trace::{
+    self, BinarySink, InputEvent, JsonLinesSink, TextSink, TraceFilter, TraceOptions, TraceRecord,
+    Tracer,
+};
+use chip8_core::vm::{Backend, Chip8, Interpreter, StepOutcome};
+
+const USAGE: &str = "usage:
+  chip8_core run <rom> [RUN OPTIONS] [--frames N] [--realtime]
+                 [--screenshot-at-frame N [--screenshot FILE] [--scale N] [--colors RRGGBB,RRGGBB]]
+  chip8_core trace <rom> [RUN OPTIONS]
+  chip8_core diff <left.trace> <right.trace>
+  chip8_core info <rom>
+  chip8_core analyze <rom>
+  chip8_core profile <rom> [RUN OPTIONS] [--listing]
+  chip8_core bench <rom> [RUN OPTIONS] [--frames N] [--cycles N]
+                 time the interpreter against the recompiler, N cycles per frame (default 1000)
+  chip8_core vip <interpreter> <rom> [--monitor FILE] [--frames N]
+                 run the original interpreter binary on an emulated COSMAC VIP
+  chip8_core log <rom> [RUN OPTIONS]
+                 [--format text|json|binary] [--pc START-END] [--class flow,draw,...] [--output FILE]
+
+run options:
+  --seed N  --steps N  --input FILE  --machine vip|dream6800|eti660|hires|xochip
+  --platform originalChip8|hiresChip8|chip10|chip8e|chip8x|modernChip8|chip48|superchip1|superchip|superchipModern|xochip|megachip8
+            use the platform's memory map, opcode set, quirks, font and timing
+  --auto    apply the quirks, speed and keymap recorded in the ROM database
+
+screenshot options:
+  --screenshot FILE  output file, .png, .pbm or .pgm (default screenshot.png)
+  --scale N          pixel size (default 1)
+  --colors FG,BG     foreground and background of monochrome screens (default 66ccff,000000)";
+
 fn main() {
-    let x: u8 = 25;
-    print!("111 {}", (x / 10) as u8);
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("run") => cmd_run(&args[1..]),
+        Some("trace") => cmd_trace(&args[1..]),
+        Some("diff") => cmd_diff(&args[1..]),
+        Some("log") => cmd_log(&args[1..]),
+        Some("profile") => cmd_profile(&args[1..]),
+        Some("bench") => cmd_bench(&args[1..]),
+        Some("info") => cmd_info(&args[1..]),
+        Some("analyze") => cmd_analyze(&args[1..]),
+        Some("vip") => cmd_vip(&args[1..]),
+        _ => Err(USAGE.to_string()),
+    };
+
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(2);
+    }
+}
+
+/// 取出 `--name value` 形式的参数
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, String> {
+    match args.iter().position(|arg| arg == name) {
+        Some(i) if i + 1 < args.len() => {
+            let value = args.remove(i + 1);
+            args.remove(i);
+            Ok(Some(value))
+        }
+        Some(_) => Err(format!("missing value for {}", name)),
+        None => Ok(None),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(
+    name: &str,
+    value: Option<String>,
+    default: T,
+) -> Result<T, String> {
+    match value {
+        Some(value) => value
+            .parse()
+            .map_err(|_| format!("invalid value for {}: {}", name, value)),
+        None => Ok(default),
+    }
+}
+
+/// 取出不带值的 `--name` 开关
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    match args.iter().position(|arg| arg == name) {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    }
+}
+
+/// 解析 trace、log 与 profile 共用的参数，返回加载好 ROM 的 VM 与 ROM 的长度
+fn prepare_run(args: &mut Vec<String>) -> Result<(Chip8, TraceOptions, usize), String> {
+    let seed = parse_number("--seed", take_option(args, "--seed")?, 0u32)?;
+    let steps = parse_number("--steps", take_option(args, "--steps")?, 10_000u64)?;
+    let input = take_option(args, "--input")?;
+    let auto = take_flag(args, "--auto");
+    let platform = take_option(args, "--platform")?;
+    let machine = take_option(args, "--machine")?;
+    if platform.is_some() && machine.is_some() {
+        return Err("--platform and --machine cannot be used together".to_string());
+    }
+    let config = match machine.as_deref() {
+        None => MachineConfig::default(),
+        Some("vip") => MachineConfig::vip(),
+        Some("dream6800") => MachineConfig::dream6800(),
+        Some("eti660") => MachineConfig::eti660(),
+        Some("hires") => MachineConfig::hires(),
+        Some("xochip") => MachineConfig::xochip(),
+        Some(name) => return Err(format!("unknown machine: {}", name)),
+    };
+    let [rom] = args.as_slice() else {
+        return Err(USAGE.to_string());
+    };
+
+    let rom = fs::read(rom).map_err(|err| format!("{}: {}", rom, err))?;
+    let mut options = TraceOptions::new(steps);
+    if let Some(input) = input {
+        let script = fs::read_to_string(&input).map_err(|err| format!("{}: {}", input, err))?;
+        options.inputs = script
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .map(InputEvent::parse)
+            .collect::<Result<_, _>>()?;
+    }
+
+    let mut vm = match platform {
+        Some(id) => {
+            let platform =
+                Platform::from_id(&id).ok_or_else(|| format!("unknown platform: {}", id))?;
+            Chip8::with_platform(platform)
+        }
+        None => Chip8::with_config(config)?,
+    };
+    vm.seed_rng(seed);
+    vm.set_auto_configure(auto);
+    vm.load_rom(&rom)?;
+    Ok((vm, options, rom.len()))
+}
+
+/// `--screenshot-at-frame` 及其相关参数
+struct ScreenshotRequest {
+    frame: u64,
+    path: String,
+    format: ImageFormat,
+    scale: usize,
+    colors: Colors,
+}
+
+impl ScreenshotRequest {
+    /// 没有 `--screenshot-at-frame` 时返回 None，这时不允许出现其它截图参数
+    fn take(args: &mut Vec<String>) -> Result<Option<Self>, String> {
+        let frame = take_option(args, "--screenshot-at-frame")?;
+        let path = take_option(args, "--screenshot")?;
+        let scale = take_option(args, "--scale")?;
+        let colors = take_option(args, "--colors")?;
+        let Some(frame) = frame else {
+            if path.is_some() || scale.is_some() || colors.is_some() {
+                return Err("screenshot options require --screenshot-at-frame".to_string());
+            }
+            return Ok(None);
+        };
+
+        let path = path.unwrap_or_else(|| "screenshot.png".to_string());
+        let format = ImageFormat::from_path(&path)
+            .ok_or_else(|| format!("unknown screenshot format: {}", path))?;
+        let colors = match colors {
+            Some(text) => Colors::parse(&text)
+                .ok_or_else(|| format!("invalid value for --colors: {}", text))?,
+            None => Colors::default(),
+        };
+        Ok(Some(ScreenshotRequest {
+            frame: parse_number("--screenshot-at-frame", Some(frame), 0u64)?,
+            path,
+            format,
+            scale: parse_number("--scale", scale, 1usize)?,
+            colors,
+        }))
+    }
+
+    /// 保存当前画面，`frame` 为画面所在的帧
+    fn save(&self, vm: &Chip8, frame: u64) -> Result<(), String> {
+        let image = Screenshot::capture(vm, self.colors).scaled(self.scale);
+        fs::write(&self.path, image.encode(self.format))
+            .map_err(|err| format!("{}: {}", self.path, err))?;
+        eprintln!("saved screenshot of frame {} to {}", frame, self.path);
+        Ok(())
+    }
+}
+
+/// 运行 `frames` 帧，途中在第 `screenshot.frame` 帧结束时截图，第 0 帧为运行之前。
+/// 在这之前停机时保存停机时的画面，出错或中断时不截图
+fn run_frames<D: DisplaySink, A: AudioSink, I: InputSource, C: Clock>(
+    runner: &mut Runner<D, A, I, C>,
+    vm: &mut Chip8,
+    frames: u64,
+    screenshot: Option<&ScreenshotRequest>,
+) -> Result<StepOutcome, String> {
+    let Some(screenshot) = screenshot else {
+        return Ok(runner.run(vm, frames));
+    };
+    if screenshot.frame > frames {
+        return Err(format!(
+            "--screenshot-at-frame {} is after the last frame ({})",
+            screenshot.frame, frames
+        ));
+    }
+    let outcome = runner.run(vm, screenshot.frame);
+    if runner.frame() < screenshot.frame {
+        if outcome == StepOutcome::Halted {
+            eprintln!(
+                "halted at frame {}, before frame {}",
+                runner.frame(),
+                screenshot.frame
+            );
+            screenshot.save(vm, runner.frame())?;
+        } else {
+            eprintln!("stopped before frame {}, no screenshot", screenshot.frame);
+        }
+        return Ok(outcome);
+    }
+    screenshot.save(vm, screenshot.frame)?;
+    match outcome {
+        StepOutcome::Halted | StepOutcome::Fault(_) | StepOutcome::Break(_) => Ok(outcome),
+        _ => Ok(runner.run(vm, frames - screenshot.frame)),
+    }
+}
+
+/// 运行 `--frames` 帧。`--realtime` 时按 60Hz 在终端中刷新画面，否则不等待，最后输出一次画面
+fn cmd_run(args: &[String]) -> Result<(), String> {
+    let mut args = args.to_vec();
+    let frames = parse_number("--frames", take_option(&mut args, "--frames")?, 600u64)?;
+    let realtime = take_flag(&mut args, "--realtime");
+    let screenshot = ScreenshotRequest::take(&mut args)?;
+    let (mut vm, options, _) = prepare_run(&mut args)?;
+    let input = ScriptedInput::new(options.inputs);
+
+    let (outcome, ran) = if realtime {
+        let mut display = TextDisplay::new(io::stdout());
+        display.redraw = true;
+        print!("\x1b[2J");
+        let mut runner = Runner::new(display, BellAudio::stderr(), input, RealTimeClock::new());
+        let outcome = run_frames(&mut runner, &mut vm, frames, screenshot.as_ref())?;
+        (outcome, runner.frame())
+    } else {
+        let mut runner = Runner::new(NoDisplay, NoAudio, input, FixedClock);
+        let outcome = run_frames(&mut runner, &mut vm, frames, screenshot.as_ref())?;
+        print!("{}", runner::render_text(&vm.screen, vm.display_size()));
+        (outcome, runner.frame())
+    };
+
+    match outcome {
+        StepOutcome::Halted => match vm.exit_code() {
+            Some(code) => eprintln!("exited with {} after {} frames", code, ran),
+            None => eprintln!("halted after {} frames", ran),
+        },
+        StepOutcome::Fault(err) => {
+            return Err(format!("{} at {:#05x} after {} frames", err, vm.r_pc, ran))
+        }
+        _ => eprintln!("ran {} frames", ran),
+    }
+    Ok(())
+}
+
+/// 在模拟的 COSMAC VIP 上用原版解释器运行 `--frames` 帧，最后输出一次画面
+fn cmd_vip(args: &[String]) -> Result<(), String> {
+    let mut args = args.to_vec();
+    let frames = parse_number("--frames", take_option(&mut args, "--frames")?, 600u64)?;
+    let monitor = take_option(&mut args, "--monitor")?;
+    let [interpreter, rom] = args.as_slice() else {
+        return Err(USAGE.to_string());
+    };
+
+    let read = |path: &str| fs::read(path).map_err(|err| format!("{}: {}", path, err));
+    let mut vip = CosmacVip::new(&read(interpreter)?)?;
+    if let Some(monitor) = monitor {
+        vip.set_monitor(&read(&monitor)?)?;
+    }
+    vip.load_rom(&read(rom)?)?;
+    for _ in 0..frames {
+        vip.run_frame();
+    }
+    print!("{}", runner::render_text(&vip.screen, (64, 32)));
+    eprintln!("ran {} frames", frames);
+    Ok(())
+}
+
+/// 用 `backend` 运行 `frames` 帧，每帧 `cycles` 条指令，停机或出错时提前结束。返回耗时与运行的帧数
+fn bench_backend(
+    vm: &mut Chip8,
+    backend: &mut dyn Backend,
+    input: &mut ScriptedInput,
+    frames: u64,
+    cycles: u32,
+) -> (Duration, u64) {
+    let start = Instant::now();
+    for frame in 0..frames {
+        input.poll(frame, &mut vm.keyboard);
+        let outcome = backend.run(vm, cycles);
+        vm.ticker();
+        if matches!(outcome, StepOutcome::Halted | StepOutcome::Fault(_)) {
+            return (start.elapsed(), frame + 1);
+        }
+    }
+    (start.elapsed(), frames)
+}
+
+/// 分别用解释器和重编译器运行同一个 ROM，比较耗时，并检查两者的结果相同
+fn cmd_bench(args: &[String]) -> Result<(), String> {
+    let mut args = args.to_vec();
+    let frames = parse_number("--frames", take_option(&mut args, "--frames")?, 10_000u64)?;
+    let cycles = parse_number("--cycles", take_option(&mut args, "--cycles")?, 1000u32)?;
+    let (mut plain, options, _) = prepare_run(&mut args.clone())?;
+    let (mut compiled, _, _) = prepare_run(&mut args)?;
+
+    let (interpreted, ran) = bench_backend(
+        &mut plain,
+        &mut Interpreter,
+        &mut ScriptedInput::new(options.inputs.clone()),
+        frames,
+        cycles,
+    );
+    let (recompiled, _) = bench_backend(
+        &mut compiled,
+        &mut Recompiler::new(),
+        &mut ScriptedInput::new(options.inputs),
+        frames,
+        cycles,
+    );
+    if plain.r_pc != compiled.r_pc || plain.r_v != compiled.r_v || plain.memory != compiled.memory {
+        return Err("the recompiler diverged from the interpreter".to_string());
+    }
+
+    println!("{} frames, {} cycles per frame", ran, cycles);
+    println!(
+        "interpreter: {:>8.1} ms",
+        interpreted.as_secs_f64() * 1000.0
+    );
+    println!(
+        "recompiler:  {:>8.1} ms ({:.2}x)",
+        recompiled.as_secs_f64() * 1000.0,
+        interpreted.as_secs_f64() / recompiled.as_secs_f64()
+    );
+    Ok(())
+}
+
+fn cmd_trace(args: &[String]) -> Result<(), String> {
+    let mut args = args.to_vec();
+    let (mut vm, options, _) = prepare_run(&mut args)?;
+    trace::run(&mut vm, &options, |record| println!("{}", record));
+    Ok(())
+}
+
+fn cmd_info(args: &[String]) -> Result<(), String> {
+    let [path] = args else {
+        return Err(USAGE.to_string());
+    };
+    let rom = fs::read(path).map_err(|err| format!("{}: {}", path, err))?;
+    let sha1 = hash::sha1_hex(&rom);
+    println!("sha1: {}", sha1);
+    let Some(info) = romdb::lookup(&sha1) else {
+        println!("not in the ROM database");
+        return Ok(());
+    };
+
+    println!("title: {}", info.title);
+    if !info.authors.is_empty() {
+        println!("authors: {}", info.authors.join(", "));
+    }
+    println!(
+        "platform: {} ({})",
+        info.platform.name(),
+        info.platform.id()
+    );
+    println!("tick rate: {} instructions per frame", info.tick_rate());
+    println!("quirks: {:?}", info.quirks());
+    if let Some(keys) = info.keys {
+        println!("keymap: {}", String::from_utf8_lossy(keys));
+    }
+    Ok(())
+}
+
+fn cmd_analyze(args: &[String]) -> Result<(), String> {
+    let [path] = args else {
+        return Err(USAGE.to_string());
+    };
+    let rom = fs::read(path).map_err(|err| format!("{}: {}", path, err))?;
+    print!(
+        "{}",
+        analysis::analyze(&rom, MachineConfig::default().load_address).report()
+    );
+    Ok(())
+}
+
+fn cmd_profile(args: &[String]) -> Result<(), String> {
+    let mut args = args.to_vec();
+    let listing = take_flag(&mut args, "--listing");
+    let (mut vm, options, rom_len) = prepare_run(&mut args)?;
+
+    vm.set_profiler(Profiler::new(vm.code_space()));
+    trace::run(&mut vm, &options, |_| {});
+    let profiler = vm.take_profiler().unwrap();
+
+    let start = vm.config().load_address;
+    let rom = start..start + rom_len as u16;
+    print!("{}", profiler.report(rom.clone()));
+    println!("\nself-modifying code:");
+    for code in vm.modified_code() {
+        println!(
+            "  {:04X}  written by {:04X}  executed {}",
+            code.addr, code.writer, code.executions
+        );
+    }
+    if listing {
+        print!("\nlisting:\n{}", profiler.listing(&vm.memory, rom));
+    }
+    Ok(())
+}
+
+fn parse_address(value: &str) -> Result<u16, String> {
+    let digits = value.trim_start_matches("0x").trim_start_matches("0X");
+    u16::from_str_radix(digits, 16).map_err(|_| format!("invalid address: {}", value))
+}
+
+fn parse_filter(pc: Option<String>, classes: Option<String>) -> Result<TraceFilter, String> {
+    let mut filter = TraceFilter::default();
+    if let Some(pc) = pc {
+        let (start, end) = pc
+            .split_once('-')
+            .ok_or_else(|| format!("invalid value for --pc: {}", pc))?;
+        filter.pc_range = Some(parse_address(start)?..=parse_address(end)?);
+    }
+    if let Some(classes) = classes {
+        filter.classes = classes
+            .split(',')
+            .map(|name| {
+                OpcodeClass::from_name(name.trim())
+                    .ok_or_else(|| format!("unknown opcode class: {}", name))
+            })
+            .collect::<Result<_, _>>()?;
+    }
+    Ok(filter)
+}
+
+fn cmd_log(args: &[String]) -> Result<(), String> {
+    let mut args = args.to_vec();
+    let format = take_option(&mut args, "--format")?.unwrap_or_else(|| "text".to_string());
+    let filter = parse_filter(
+        take_option(&mut args, "--pc")?,
+        take_option(&mut args, "--class")?,
+    )?;
+    let out: Box<dyn Write> = match take_option(&mut args, "--output")? {
+        Some(path) => {
+            Box::new(fs::File::create(&path).map_err(|err| format!("{}: {}", path, err))?)
+        }
+        None => Box::new(io::stdout()),
+    };
+    let out = BufWriter::new(out);
+    let (mut vm, options, _) = prepare_run(&mut args)?;
+
+    let tracer = match format.as_str() {
+        "text" => Tracer::new(TextSink::new(out)),
+        "json" => Tracer::new(JsonLinesSink::new(out)),
+        "binary" => Tracer::new(BinarySink::new(out)),
+        _ => return Err(format!("unknown format: {}", format)),
+    };
+    vm.set_tracer(tracer.with_filter(filter));
+    trace::run(&mut vm, &options, |_| {});
+    match vm.take_tracer() {
+        Some(tracer) => tracer.finish().map_err(|err| err.to_string()),
+        None => Ok(()),
+    }
+}
+
+fn read_trace(path: &str) -> Result<Vec<TraceRecord>, String> {
+    fs::read_to_string(path)
+        .map_err(|err| format!("{}: {}", path, err))?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(TraceRecord::parse)
+        .collect()
+}
+
+fn cmd_diff(args: &[String]) -> Result<(), String> {
+    let [left, right] = args else {
+        return Err(USAGE.to_string());
+    };
+
+    match trace::first_divergence(&read_trace(left)?, &read_trace(right)?) {
+        Some(divergence) => {
+            println!("{}", divergence);
+            process::exit(1);
+        }
+        None => {
+            println!("traces match");
+            Ok(())
+        }
+    }
 }
@@ -0,0 +1,268 @@
+//! MegaChip-8：在 SUPER-CHIP 之上增加 256x192 的 256 色画面、可调大小的精灵与混合模式，以及数字采样播放。
+//!
+//! 彩色画面与 1 bit 的 [`Screen`](crate::hardware::Screen) 分开保存：每个像素一个调色板下标（用于碰撞检测）
+//! 和一个混合后的 RGBA 颜色。0011 进入 MegaChip 模式后，00E0、滚屏与 DXYN 作用在这里。
+//! MegaChip 的内存有 16 MiB，01NN NNNN 把 24 位地址载入 I；PC 仍是 16 位，代码只能位于前 64 KiB。
+
+pub const MEGA_WIDTH: usize = 256;
+pub const MEGA_HEIGHT: usize = 192;
+
+/// 采样数据前的头部：2 字节采样率、3 字节长度、1 字节保留
+const SAMPLE_HEADER: usize = 6;
+
+/// 080N 选择的精灵混合模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    /// 精灵占 25%
+    Alpha25,
+    Alpha50,
+    Alpha75,
+    Add,
+    Multiply,
+}
+
+impl BlendMode {
+    pub fn from_n(n: u8) -> Option<Self> {
+        Some(match n {
+            0 => BlendMode::Normal,
+            1 => BlendMode::Alpha25,
+            2 => BlendMode::Alpha50,
+            3 => BlendMode::Alpha75,
+            4 => BlendMode::Add,
+            5 => BlendMode::Multiply,
+            _ => return None,
+        })
+    }
+
+    /// 精灵颜色 `src` 画在 `dst` 上的结果，混合时 alpha 通道为不透明
+    pub fn blend(self, src: [u8; 4], dst: [u8; 4]) -> [u8; 4] {
+        if self == BlendMode::Normal {
+            return src;
+        }
+        let channel = |i: usize| {
+            let (s, d) = (src[i] as u16, dst[i] as u16);
+            let mix = |weight: u16| (s * weight + d * (4 - weight)) / 4;
+            (match self {
+                BlendMode::Normal => s,
+                BlendMode::Alpha25 => mix(1),
+                BlendMode::Alpha50 => mix(2),
+                BlendMode::Alpha75 => mix(3),
+                BlendMode::Add => (s + d).min(0xff),
+                BlendMode::Multiply => s * d / 0xff,
+            }) as u8
+        };
+        [channel(0), channel(1), channel(2), 0xff]
+    }
+}
+
+/// 256x192 的彩色画面
+#[derive(Debug, Clone)]
+pub struct MegaScreen {
+    /// 每个像素最后画上的调色板下标，0 表示背景
+    pub indices: [u8; MEGA_WIDTH * MEGA_HEIGHT],
+    /// 每个像素的 RGBA 颜色，按行排列
+    pub rgba: [[u8; 4]; MEGA_WIDTH * MEGA_HEIGHT],
+}
+
+impl MegaScreen {
+    pub fn new() -> Self {
+        MegaScreen {
+            indices: [0; MEGA_WIDTH * MEGA_HEIGHT],
+            rgba: [[0, 0, 0, 0xff]; MEGA_WIDTH * MEGA_HEIGHT],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.indices.fill(0);
+        self.rgba.fill([0, 0, 0, 0xff]);
+    }
+
+    pub fn get(&self, x: u8, y: u8) -> [u8; 4] {
+        self.rgba[y as usize * MEGA_WIDTH + x as usize]
+    }
+
+    /// 整个画面移动 (dx, dy) 个像素，移出的部分丢弃，空出的部分清为背景。
+    /// 按移动方向的反方向遍历，原地搬动不会覆盖还没读取的像素
+    pub fn scroll(&mut self, dx: isize, dy: isize) {
+        for i in 0..MEGA_HEIGHT {
+            let y = if dy > 0 { MEGA_HEIGHT - 1 - i } else { i };
+            for j in 0..MEGA_WIDTH {
+                let x = if dx > 0 { MEGA_WIDTH - 1 - j } else { j };
+                let target = y * MEGA_WIDTH + x;
+                match (x.checked_add_signed(-dx), y.checked_add_signed(-dy)) {
+                    (Some(src_x), Some(src_y)) if src_x < MEGA_WIDTH && src_y < MEGA_HEIGHT => {
+                        let source = src_y * MEGA_WIDTH + src_x;
+                        self.indices[target] = self.indices[source];
+                        self.rgba[target] = self.rgba[source];
+                    }
+                    _ => {
+                        self.indices[target] = 0;
+                        self.rgba[target] = [0, 0, 0, 0xff];
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 060N 开始播放的数字采样：8 bit 无符号单声道，按自己的采样率从内存中读取
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SamplePlayer {
+    /// 采样数据在内存中的起始地址
+    pub start: usize,
+    /// 采样个数
+    pub len: usize,
+    /// 采样率（Hz）
+    pub rate: u16,
+    pub looping: bool,
+    pub playing: bool,
+    /// 已经播放的采样数乘以输出采样率，避免浮点运算
+    position: u64,
+}
+
+impl SamplePlayer {
+    /// 按 I 处的头部开始播放，超出内存的部分被截掉
+    pub fn play(&mut self, memory: &[u8], addr: usize, looping: bool) {
+        let header = |i: usize| memory.get(addr + i).copied().unwrap_or(0) as usize;
+        let start = addr + SAMPLE_HEADER;
+        let len = header(2) << 16 | header(3) << 8 | header(4);
+        *self = SamplePlayer {
+            start,
+            len: len.min(memory.len().saturating_sub(start)),
+            rate: (header(0) << 8 | header(1)) as u16,
+            looping,
+            playing: true,
+            position: 0,
+        };
+    }
+
+    pub fn stop(&mut self) {
+        self.playing = false;
+    }
+
+    /// 以 `output_rate` 的采样率填满 `out`，没有声音时填入静音值 0x80
+    pub fn fill(&mut self, memory: &[u8], output_rate: u32, out: &mut [u8]) {
+        for sample in out.iter_mut() {
+            *sample = 0x80;
+            if !self.playing || self.len == 0 || output_rate == 0 {
+                continue;
+            }
+            let mut index = (self.position / output_rate as u64) as usize;
+            if index >= self.len {
+                if !self.looping {
+                    self.playing = false;
+                    continue;
+                }
+                self.position %= self.len as u64 * output_rate as u64;
+                index = (self.position / output_rate as u64) as usize;
+            }
+            *sample = memory[self.start + index];
+            self.position += self.rate as u64;
+        }
+    }
+}
+
+/// MegaChip 的全部额外状态
+#[derive(Debug, Clone)]
+pub struct MegaChip {
+    /// 0011 开启、0010 关闭的 MegaChip 模式
+    pub enabled: bool,
+    pub screen: MegaScreen,
+    /// 02NN 载入的调色板（RGBA），下标 0 为透明
+    pub palette: [[u8; 4]; 256],
+    /// 03NN / 04NN 设置的精灵宽高，0 表示 256
+    pub sprite_width: u16,
+    pub sprite_height: u16,
+    pub blend: BlendMode,
+    /// 09NN：精灵画到这个下标的像素上时 VF 置 1，背景（下标 0）不算碰撞
+    pub collision_color: u8,
+    /// 05NN 设置的画面透明度
+    pub alpha: u8,
+    pub sample: SamplePlayer,
+}
+
+impl MegaChip {
+    pub fn new() -> Self {
+        MegaChip {
+            enabled: false,
+            screen: MegaScreen::new(),
+            palette: [[0, 0, 0, 0xff]; 256],
+            sprite_width: 0,
+            sprite_height: 0,
+            blend: BlendMode::Normal,
+            collision_color: 0,
+            alpha: 0xff,
+            sample: SamplePlayer::default(),
+        }
+    }
+
+    /// 原地恢复初始状态，避免在栈上构造一份完整的画面
+    pub fn reset(&mut self) {
+        self.enabled = false;
+        self.screen.clear();
+        self.palette.fill([0, 0, 0, 0xff]);
+        self.sprite_width = 0;
+        self.sprite_height = 0;
+        self.blend = BlendMode::Normal;
+        self.collision_color = 0;
+        self.alpha = 0xff;
+        self.sample = SamplePlayer::default();
+    }
+
+    /// (x, y) 在屏幕上的 RGB 颜色：画面按 05NN 设置的透明度叠在黑色背景上
+    pub fn pixel_color(&self, x: u8, y: u8) -> [u8; 3] {
+        let [r, g, b, _] = self.screen.get(x, y);
+        [r, g, b].map(|c| (c as u16 * self.alpha as u16 / 0xff) as u8)
+    }
+
+    /// 02NN：从 `memory[addr..]` 读取 `count` 个 ARGB 颜色，存入调色板第 1 项起
+    pub fn load_palette(&mut self, memory: &[u8], addr: usize, count: u8) {
+        for i in 0..count as usize {
+            let byte = |j: usize| memory.get(addr + i * 4 + j).copied().unwrap_or(0);
+            self.palette[i + 1] = [byte(1), byte(2), byte(3), byte(0)];
+        }
+    }
+
+    /// DXYN：把 `memory[addr..]` 处 `sprite_width` x `sprite_height` 的精灵画在 (x, y)，
+    /// 每个字节是调色板下标，0 为透明。横向绕回，纵向超出画面的行被裁掉。返回是否发生碰撞
+    pub fn draw_sprite(&mut self, memory: &[u8], addr: usize, x: u8, y: u8) -> bool {
+        let width = if self.sprite_width == 0 {
+            256
+        } else {
+            self.sprite_width as usize
+        };
+        let height = if self.sprite_height == 0 {
+            256
+        } else {
+            self.sprite_height as usize
+        };
+        let mut collision = false;
+        for row in 0..height {
+            let target_y = y as usize + row;
+            if target_y >= MEGA_HEIGHT {
+                break;
+            }
+            for column in 0..width {
+                let index = memory
+                    .get(addr + row * width + column)
+                    .copied()
+                    .unwrap_or(0);
+                if index == 0 {
+                    continue;
+                }
+                let target = target_y * MEGA_WIDTH + (x as usize + column) % MEGA_WIDTH;
+                let under = self.screen.indices[target];
+                if under != 0 && under == self.collision_color {
+                    collision = true;
+                }
+                self.screen.indices[target] = index;
+                self.screen.rgba[target] = self
+                    .blend
+                    .blend(self.palette[index as usize], self.screen.rgba[target]);
+            }
+        }
+        collision
+    }
+}
@@ -0,0 +1,290 @@
+//! ROM 所针对的解释器平台。每个平台决定内存布局、字体、可用的指令、quirks、屏幕大小与运行速度，
+//! 用 [`Chip8::with_platform`](crate::vm::Chip8::with_platform) 创建对应的机器。
+
+use crate::config::MachineConfig;
+use crate::font::FontSet;
+use crate::hardware::MAX_MEMORY_SIZE;
+use crate::quirks::Quirks;
+use crate::vm::{Instruction, SCHIP_STACK_DEPTH, VIP_STACK_DEPTH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Platform {
+    /// COSMAC VIP 上最初的 CHIP-8 解释器
+    Vip,
+    /// 64x64 的 HIRES CHIP-8：ROM 开头是改写 VIP 解释器的引导代码，从 0x2C0 开始执行，0230 清屏
+    HiresChip8,
+    /// 128x64 的 CHIP-10
+    Chip10,
+    /// CHIP-8E：增加 5XY1 大于则跳过、5XY2/5XY3 保存与读取一段寄存器、BBNN/BFNN 相对跳转、
+    /// 00ED 停机、0151/FX4F 等待计时器，以及 FX03/FXE3/FXE7 端口 I/O
+    Chip8E,
+    /// 带 VP-590 彩色板与 VP-595 音调板的 CHIP-8X：02A0 切换背景色，BXYN 设置前景色，
+    /// FXF8/FXFB 读写 I/O 端口。BNNN 不再是跳转
+    Chip8X,
+    /// 现代模拟器上通用的 CHIP-8，使用本模拟器默认的 quirks。
+    /// 接受 CHIP-8 与 SUPER-CHIP 1.1 的所有指令，0NNN 被忽略，001N 退出
+    Chip8,
+    /// HP-48 上的 CHIP-48，没有 SUPER-CHIP 的扩展指令
+    Chip48,
+    /// SUPER-CHIP 1.0：128x64 高分辨率、16x16 精灵、大字体和 RPL 标志
+    SuperChip10,
+    /// HP-48 上的 SUPER-CHIP 1.1，增加了滚屏指令
+    SuperChip,
+    /// 现代模拟器上的 SUPER-CHIP：指令与 1.1 相同，不等待 vblank，FX75/FX85 可以使用全部 16 个标志
+    SuperChipModern,
+    /// Octo 的 XO-CHIP
+    XoChip,
+    /// MegaChip-8：SUPER-CHIP 加上 0011 切换的 256x192 彩色画面、调色板、精灵混合与数字采样
+    MegaChip,
+}
+
+/// 指令所属的指令集
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OpcodeGroup {
+    /// 所有平台都有的指令
+    Base,
+    /// 0NNN，调用机器码子程序
+    MachineCall,
+    /// 001N，chip8run 的退出指令
+    Exit,
+    SuperChip10,
+    SuperChip11,
+    XoChip,
+    Chip8X,
+    Chip8E,
+    /// 5XY2/5XY3，XO-CHIP 与 CHIP-8E 的编码相同，CHIP-8E 还会移动 I
+    RegisterRange,
+    /// MegaChip 的 0010、0011：在 MegaChip 上切换模式，在默认的 CHIP-8 上是 001N 退出，
+    /// 在 VIP 一族上是 0NNN
+    MegaChipMode,
+    /// MegaChip 的 00BN 与 01NN~09NN，在 VIP 一族上是 0NNN
+    MegaChip,
+    /// 任何平台上都不是指令
+    Invalid,
+}
+
+/// 按编码归类。SUPER-CHIP 与 XO-CHIP 的 00NN 指令不当作 0NNN 机器码调用，在没有这些扩展的平台上不存在
+fn opcode_group(ir: &Instruction) -> OpcodeGroup {
+    match ir.opcode {
+        0x0000 => match ir.ir_code {
+            0x00e0 | 0x00ee => OpcodeGroup::Base,
+            0x00fd..=0x00ff => OpcodeGroup::SuperChip10,
+            0x00fb | 0x00fc | 0x00c0..=0x00cf => OpcodeGroup::SuperChip11,
+            0x00d0..=0x00df => OpcodeGroup::XoChip,
+            0x0010 | 0x0011 => OpcodeGroup::MegaChipMode,
+            0x0012..=0x001f => OpcodeGroup::Exit,
+            0x00b0..=0x00bf
+            | 0x0100..=0x05ff
+            | 0x0600
+            | 0x0601
+            | 0x0700
+            | 0x0800..=0x0805
+            | 0x0900..=0x09ff => OpcodeGroup::MegaChip,
+            _ => OpcodeGroup::MachineCall,
+        },
+        0x5000 => match ir.n {
+            0x0 => OpcodeGroup::Base,
+            0x1 => OpcodeGroup::Chip8E,
+            0x2 | 0x3 => OpcodeGroup::RegisterRange,
+            _ => OpcodeGroup::Invalid,
+        },
+        0x8000 => match ir.n {
+            0x0..=0x7 | 0xe => OpcodeGroup::Base,
+            _ => OpcodeGroup::Invalid,
+        },
+        0x9000 if ir.n != 0 => OpcodeGroup::Invalid,
+        0xe000 => match ir.kk {
+            0x9e | 0xa1 => OpcodeGroup::Base,
+            _ => OpcodeGroup::Invalid,
+        },
+        0xf000 => match ir.kk {
+            0x07 | 0x0a | 0x15 | 0x18 | 0x1e | 0x29 | 0x33 | 0x55 | 0x65 => OpcodeGroup::Base,
+            0x30 | 0x75 | 0x85 => OpcodeGroup::SuperChip10,
+            0xf8 | 0xfb => OpcodeGroup::Chip8X,
+            0x03 | 0x1b | 0x4f | 0xe3 | 0xe7 => OpcodeGroup::Chip8E,
+            0x00 | 0x02 if ir.x == 0 => OpcodeGroup::XoChip,
+            0x01 | 0x3a => OpcodeGroup::XoChip,
+            _ => OpcodeGroup::Invalid,
+        },
+        _ => OpcodeGroup::Base,
+    }
+}
+
+impl Platform {
+    pub const ALL: [Platform; 12] = [
+        Platform::Vip,
+        Platform::HiresChip8,
+        Platform::Chip10,
+        Platform::Chip8E,
+        Platform::Chip8X,
+        Platform::Chip8,
+        Platform::Chip48,
+        Platform::SuperChip10,
+        Platform::SuperChip,
+        Platform::SuperChipModern,
+        Platform::XoChip,
+        Platform::MegaChip,
+    ];
+
+    /// 平台 id，chip-8-database 收录的平台使用它的 id
+    pub fn id(&self) -> &'static str {
+        match self {
+            Platform::Vip => "originalChip8",
+            Platform::HiresChip8 => "hiresChip8",
+            Platform::Chip10 => "chip10",
+            Platform::Chip8E => "chip8e",
+            Platform::Chip8X => "chip8x",
+            Platform::Chip8 => "modernChip8",
+            Platform::Chip48 => "chip48",
+            Platform::SuperChip10 => "superchip1",
+            Platform::SuperChip => "superchip",
+            Platform::SuperChipModern => "superchipModern",
+            Platform::XoChip => "xochip",
+            Platform::MegaChip => "megachip8",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|platform| platform.id() == id)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Platform::Vip => "CHIP-8 (COSMAC VIP)",
+            Platform::HiresChip8 => "HIRES CHIP-8",
+            Platform::Chip10 => "CHIP-10",
+            Platform::Chip8E => "CHIP-8E",
+            Platform::Chip8X => "CHIP-8X",
+            Platform::Chip8 => "CHIP-8",
+            Platform::Chip48 => "CHIP-48",
+            Platform::SuperChip10 => "SUPER-CHIP 1.0",
+            Platform::SuperChip => "SUPER-CHIP 1.1",
+            Platform::SuperChipModern => "SUPER-CHIP (modern)",
+            Platform::XoChip => "XO-CHIP",
+            Platform::MegaChip => "MegaChip-8",
+        }
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        match self {
+            Platform::Vip
+            | Platform::HiresChip8
+            | Platform::Chip10
+            | Platform::Chip8E
+            | Platform::Chip8X => Quirks::vip(),
+            Platform::Chip8 => Quirks::default(),
+            Platform::Chip48 => Quirks {
+                rpl_limit: false,
+                ..Quirks::schip()
+            },
+            Platform::SuperChip10 | Platform::SuperChip => Quirks::schip(),
+            Platform::SuperChipModern | Platform::MegaChip => Quirks {
+                rpl_limit: false,
+                ..Quirks::schip()
+            },
+            Platform::XoChip => Quirks::xochip(),
+        }
+    }
+
+    /// 内存大小、加载地址与字体
+    pub fn config(&self) -> MachineConfig {
+        match self {
+            Platform::Vip | Platform::Chip10 | Platform::Chip8E => MachineConfig::vip(),
+            Platform::HiresChip8 => MachineConfig {
+                font_set: FontSet::Vip,
+                ..MachineConfig::hires()
+            },
+            Platform::Chip8X => MachineConfig::chip8x(),
+            Platform::XoChip => MachineConfig::xochip(),
+            Platform::MegaChip => MachineConfig {
+                memory_size: MAX_MEMORY_SIZE,
+                ..MachineConfig::default()
+            },
+            _ => MachineConfig::default(),
+        }
+    }
+
+    pub fn stack_depth(&self) -> u8 {
+        if self.is_vip_family() {
+            VIP_STACK_DEPTH
+        } else {
+            SCHIP_STACK_DEPTH
+        }
+    }
+
+    /// 默认每帧（1/60 秒）执行的指令数
+    pub fn tick_rate(&self) -> u16 {
+        match self {
+            Platform::Chip8 => 8,
+            _ if self.is_vip_family() => 8,
+            Platform::XoChip => 100,
+            Platform::MegaChip => 1000,
+            _ => 30,
+        }
+    }
+
+    /// 每秒执行的指令数。默认的 CHIP-8 进入高分辨率后提速到 700，与之前的行为一致
+    pub fn rate(&self, high_res: bool) -> u16 {
+        match self {
+            Platform::Chip8 if high_res => 700,
+            _ => self.tick_rate() * 60,
+        }
+    }
+
+    /// 可见区域的大小，`high_res` 为 00FF 切换的扩展模式
+    pub fn display_size(&self, high_res: bool) -> (u8, u8) {
+        match self {
+            Platform::HiresChip8 => (64, 64),
+            Platform::Chip10 => (128, 64),
+            _ if high_res => (128, 64),
+            _ => (64, 32),
+        }
+    }
+
+    /// 运行在 COSMAC VIP 上的解释器：VIP quirks、12 层栈、每帧 8 条指令
+    fn is_vip_family(&self) -> bool {
+        matches!(
+            self,
+            Platform::Vip
+                | Platform::HiresChip8
+                | Platform::Chip10
+                | Platform::Chip8E
+                | Platform::Chip8X
+        )
+    }
+
+    /// 0NNN 是否为机器码调用：VIP 一族与默认的 CHIP-8
+    fn runs_machine_code(&self) -> bool {
+        self.is_vip_family() || *self == Platform::Chip8
+    }
+
+    /// 这条指令在本平台上是否存在
+    pub fn supports(&self, ir: &Instruction) -> bool {
+        use Platform::*;
+
+        match opcode_group(ir) {
+            OpcodeGroup::Base => true,
+            OpcodeGroup::MachineCall | OpcodeGroup::Exit => self.runs_machine_code(),
+            OpcodeGroup::SuperChip10 => {
+                matches!(
+                    self,
+                    Chip8 | SuperChip10 | SuperChip | SuperChipModern | XoChip | MegaChip
+                )
+            }
+            OpcodeGroup::SuperChip11 => {
+                matches!(
+                    self,
+                    Chip8 | SuperChip | SuperChipModern | XoChip | MegaChip
+                )
+            }
+            OpcodeGroup::XoChip => *self == XoChip,
+            OpcodeGroup::Chip8X => *self == Chip8X,
+            OpcodeGroup::Chip8E => *self == Chip8E,
+            OpcodeGroup::RegisterRange => matches!(self, XoChip | Chip8E),
+            OpcodeGroup::MegaChipMode | OpcodeGroup::MegaChip => {
+                *self == MegaChip || self.runs_machine_code()
+            }
+            OpcodeGroup::Invalid => false,
+        }
+    }
+}
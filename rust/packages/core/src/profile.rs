@@ -0,0 +1,252 @@
+//! 指令级 profiler：统计每个地址的执行次数、各类 opcode 的执行次数，以及作为数据被读写的内存。
+//!
+//! 挂到 VM 上（[`Chip8::set_profiler`](crate::vm::Chip8::set_profiler)）之后，每执行一条指令都会计数；
+//! 跑完一段输入录像后，可以据此找出热点循环、从未执行的代码，并输出带覆盖率的反汇编清单。
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use core::ops::Range;
+
+use crate::disasm;
+use crate::vm::Instruction;
+
+/// 按最高 4 位划分的 opcode 家族名称
+pub const FAMILIES: [&str; 16] = [
+    "0NNN", "1NNN", "2NNN", "3XNN", "4XNN", "5XY0", "6XNN", "7XNN", "8XYN", "9XY0", "ANNN", "BNNN",
+    "CXNN", "DXYN", "EXNN", "FXNN",
+];
+
+/// 由向后的 1NNN 跳转围成的循环
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HotLoop {
+    /// 跳转目标，即循环体的第一条指令
+    pub start: u16,
+    /// 跳转指令所在地址
+    pub end: u16,
+    /// 跳转执行的次数
+    pub iterations: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Profiler {
+    /// 每个地址作为指令起始被执行的次数
+    exec: Vec<u64>,
+    /// 每个字节作为指令的一部分（opcode 的两个字节）被执行过
+    covered: Vec<bool>,
+    families: [u64; 16],
+    /// 每个字节被 DXYN、FX65 等指令当作数据读取的次数
+    reads: Vec<u64>,
+    /// 每个字节被 FX33、FX55 写入的次数
+    writes: Vec<u64>,
+    /// (跳转地址, 目标地址) -> 次数
+    back_edges: BTreeMap<(u16, u16), u64>,
+    instructions: u64,
+}
+
+impl Profiler {
+    /// `memory_size` 为 VM 存放代码的内存大小，即 [`Chip8::code_space`](crate::vm::Chip8::code_space)
+    pub fn new(memory_size: usize) -> Self {
+        Profiler {
+            exec: vec![0; memory_size],
+            covered: vec![false; memory_size],
+            families: [0; 16],
+            reads: vec![0; memory_size],
+            writes: vec![0; memory_size],
+            back_edges: BTreeMap::new(),
+            instructions: 0,
+        }
+    }
+
+    pub(crate) fn record_exec(&mut self, pc: u16, ir: &Instruction) {
+        let pc_idx = pc as usize;
+        self.exec[pc_idx] += 1;
+        self.covered[pc_idx] = true;
+        if let Some(low) = self.covered.get_mut(pc_idx + 1) {
+            *low = true;
+        }
+        self.families[(ir.ir_code >> 12) as usize] += 1;
+        if ir.opcode == 0x1000 && ir.nnn <= pc {
+            *self.back_edges.entry((pc, ir.nnn)).or_insert(0) += 1;
+        }
+        self.instructions += 1;
+    }
+
+    pub(crate) fn record_read(&mut self, addr: u16) {
+        self.reads[addr as usize] += 1;
+    }
+
+    pub(crate) fn record_write(&mut self, addr: u16) {
+        self.writes[addr as usize] += 1;
+    }
+
+    /// 执行过的指令总数
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    pub fn exec_count(&self, addr: u16) -> u64 {
+        self.exec[addr as usize]
+    }
+
+    pub fn read_count(&self, addr: u16) -> u64 {
+        self.reads[addr as usize]
+    }
+
+    pub fn write_count(&self, addr: u16) -> u64 {
+        self.writes[addr as usize]
+    }
+
+    /// 按 [`FAMILIES`] 顺序排列的执行次数
+    pub fn family_counts(&self) -> &[u64; 16] {
+        &self.families
+    }
+
+    /// 执行次数最多的 `limit` 个地址，按次数从高到低
+    pub fn hot_spots(&self, limit: usize) -> Vec<(u16, u64)> {
+        let mut spots: Vec<(u16, u64)> = self
+            .exec
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(addr, &count)| (addr as u16, count))
+            .collect();
+        spots.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        spots.truncate(limit);
+        spots
+    }
+
+    /// 所有执行过的循环，按迭代次数从高到低
+    pub fn hot_loops(&self) -> Vec<HotLoop> {
+        let mut loops: Vec<HotLoop> = self
+            .back_edges
+            .iter()
+            .map(|(&(end, start), &iterations)| HotLoop {
+                start,
+                end,
+                iterations,
+            })
+            .collect();
+        loops.sort_by(|a, b| b.iterations.cmp(&a.iterations).then(a.start.cmp(&b.start)));
+        loops
+    }
+
+    /// `range` 内既没有执行过、也没有被当作数据读取的连续字节段
+    pub fn unexecuted(&self, range: Range<u16>) -> Vec<Range<u16>> {
+        let mut ranges = vec![];
+        let mut start = None;
+        for addr in range.clone() {
+            let idx = addr as usize;
+            let untouched = !self.covered[idx] && self.reads[idx] == 0;
+            match (untouched, start) {
+                (true, None) => start = Some(addr),
+                (false, Some(from)) => {
+                    ranges.push(from..addr);
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(from) = start {
+            ranges.push(from..range.end);
+        }
+        ranges
+    }
+
+    /// 执行过的字节占 `range` 的比例
+    pub fn coverage(&self, range: Range<u16>) -> f64 {
+        if range.is_empty() {
+            return 0.0;
+        }
+        let covered = range
+            .clone()
+            .filter(|&addr| self.covered[addr as usize])
+            .count();
+        covered as f64 / range.len() as f64
+    }
+
+    /// `range` 的反汇编清单，每行前面是执行次数。
+    ///
+    /// 执行过的地址按指令输出；被当作数据读取的字节输出为 `DB`；
+    /// 两者都不是的按指令输出（奇数地址上的输出为 `DB`），次数列为 `-`。
+    ///
+    /// ```text
+    ///      1  0200  6E05  LD VE, 0x05
+    ///   2712  020A  DAB1  DRW VA, VB, 1
+    ///      -  0230  1234  JP 0x234
+    ///   data  030C  80    DB 0x80  ; read 2712
+    /// ```
+    pub fn listing(&self, memory: &[u8], range: Range<u16>) -> String {
+        let mut out = String::new();
+        let mut addr = range.start;
+        while addr < range.end {
+            let idx = addr as usize;
+            let executed = self.exec[idx] > 0;
+            // 未执行的代码按 CHIP-8 的惯例只从偶数地址开始反汇编
+            if !executed && (self.reads[idx] > 0 || addr % 2 == 1 || addr + 1 >= range.end) {
+                let label = if self.reads[idx] > 0 { "data" } else { "-" };
+                let _ = writeln!(
+                    out,
+                    "{:>6}  {:04X}  {:02X}    DB 0x{:02X}  ; read {}",
+                    label, addr, memory[idx], memory[idx], self.reads[idx]
+                );
+                addr += 1;
+                continue;
+            }
+
+            let ir = Instruction::new((memory[idx] as u16) << 8 | memory[idx + 1] as u16);
+            let count = if executed {
+                self.exec[idx].to_string()
+            } else {
+                "-".to_string()
+            };
+            let _ = writeln!(
+                out,
+                "{:>6}  {:04X}  {:04X}  {}",
+                count,
+                addr,
+                ir.ir_code,
+                disasm::disassemble(&ir)
+            );
+            addr += 2;
+        }
+        out
+    }
+
+    /// 汇总报告：指令总数、覆盖率、opcode 家族、热点循环和未执行的代码段
+    pub fn report(&self, rom: Range<u16>) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "instructions: {}", self.instructions);
+        let _ = writeln!(
+            out,
+            "coverage: {:.1}% of {:04X}..{:04X}",
+            self.coverage(rom.clone()) * 100.0,
+            rom.start,
+            rom.end
+        );
+
+        let _ = writeln!(out, "\nopcode families:");
+        for (name, count) in FAMILIES.iter().zip(self.families.iter()) {
+            if *count > 0 {
+                let _ = writeln!(out, "  {}  {}", name, count);
+            }
+        }
+
+        let _ = writeln!(out, "\nhot loops:");
+        for hot in self.hot_loops().iter().take(10) {
+            let _ = writeln!(
+                out,
+                "  {:04X}..{:04X}  {}",
+                hot.start, hot.end, hot.iterations
+            );
+        }
+
+        let _ = writeln!(out, "\nnever executed:");
+        for range in self.unexecuted(rom) {
+            let _ = writeln!(out, "  {:04X}..{:04X}", range.start, range.end);
+        }
+        out
+    }
+}
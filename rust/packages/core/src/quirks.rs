@@ -0,0 +1,80 @@
+/// 不同解释器之间的行为差异（quirks）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    /// FX0A 在按键松开时才完成（COSMAC VIP 的行为），为 false 时按下即完成
+    pub key_wait_release: bool,
+    /// 8XY1、8XY2、8XY3 执行后将 VF 清零
+    pub vf_reset: bool,
+    /// FX55、FX65 执行后 I 增加 X + 1
+    pub memory_increment: bool,
+    /// DXYN 绘制后等待下一次 vblank（即下一次 `ticker`）才继续执行
+    pub display_wait: bool,
+    /// 超出屏幕的精灵像素被裁剪，为 false 时绕回另一侧
+    pub clipping: bool,
+    /// 8XY6、8XYE 先将 VY 复制到 VX 再移位
+    pub shift_vy: bool,
+    /// BNNN 变为 BXNN，使用 VX 而不是 V0 作为偏移
+    pub jump_vx: bool,
+    /// FX75、FX85 只允许 X <= 7（HP-48 只有 8 个用户标志），超出时报错
+    pub rpl_limit: bool,
+}
+
+impl Quirks {
+    /// COSMAC VIP 上原始解释器的行为
+    pub fn vip() -> Self {
+        Quirks {
+            key_wait_release: true,
+            vf_reset: true,
+            memory_increment: true,
+            display_wait: true,
+            clipping: true,
+            shift_vy: true,
+            jump_vx: false,
+            rpl_limit: false,
+        }
+    }
+
+    /// HP-48 上 SUPER-CHIP 1.1 的行为
+    pub fn schip() -> Self {
+        Quirks {
+            key_wait_release: true,
+            vf_reset: false,
+            memory_increment: false,
+            display_wait: false,
+            clipping: true,
+            shift_vy: false,
+            jump_vx: true,
+            rpl_limit: true,
+        }
+    }
+
+    /// Octo 中 XO-CHIP 的行为
+    pub fn xochip() -> Self {
+        Quirks {
+            key_wait_release: true,
+            vf_reset: false,
+            memory_increment: true,
+            display_wait: false,
+            clipping: false,
+            shift_vy: true,
+            jump_vx: false,
+            rpl_limit: false,
+        }
+    }
+}
+
+impl Default for Quirks {
+    /// 本模拟器一直以来的行为：介于 VIP 与 SUPER-CHIP 之间
+    fn default() -> Self {
+        Quirks {
+            key_wait_release: true,
+            vf_reset: false,
+            memory_increment: false,
+            display_wait: false,
+            clipping: true,
+            shift_vy: false,
+            jump_vx: false,
+            rpl_limit: false,
+        }
+    }
+}
@@ -0,0 +1,337 @@
+//! 基本块重编译：把一段不含跳转的指令翻译成优化过的中间表示，之后整块执行。
+//!
+//! 基本块从某个地址开始，到第一条会改变控制流、绘制、等待、写内存或可能出错的指令（块尾）为止。
+//! 块尾指令在编译时译码，执行完块内指令后直接调用，不再经过 [`Chip8::cycle`] 的取指与译码；
+//! 被程序改写过的块尾仍交给 `cycle` 执行。块内按顺序执行的寄存器赋值会做常量折叠，
+//! 被后面的赋值覆盖的赋值直接删除。写入块内地址（包括块尾）的指令会让这个块失效，下次执行时重新编译。
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::platform::Platform;
+use crate::vm::{self, Backend, Chip8, Instruction, Op, StepOutcome};
+
+/// 一个基本块最多包含的指令数
+const MAX_BLOCK_LEN: u16 = 64;
+
+/// `Recompiler::index` 中表示这个地址没有编译过块
+const NO_BLOCK: u32 = u32::MAX;
+
+/// 基本块的中间表示
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IrOp {
+    /// VX = value
+    SetReg { x: u8, value: u8 },
+    /// VX += value，不影响 VF
+    AddReg { x: u8, value: u8 },
+    /// I = value
+    SetI(u16),
+    /// 其余指令调用解释器的执行函数
+    Call(u16),
+}
+
+impl IrOp {
+    /// 写入的目标：寄存器编号，I 记为 16
+    fn target(&self) -> Option<usize> {
+        match self {
+            IrOp::SetReg { x, .. } | IrOp::AddReg { x, .. } => Some(*x as usize),
+            IrOp::SetI(_) => Some(16),
+            IrOp::Call(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Block {
+    start: u16,
+    /// 块内的指令数，不含块尾
+    len: u16,
+    ir: Vec<IrOp>,
+    /// `IrOp::Call` 对应的执行函数，按出现顺序排列
+    calls: Vec<(Op, Instruction)>,
+    /// 译码好的块尾，被程序改写过或超出内存时为 None
+    tail: Option<(Op, Instruction)>,
+}
+
+impl Block {
+    pub fn start(&self) -> u16 {
+        self.start
+    }
+
+    /// 块内的指令数，不含块尾
+    pub fn len(&self) -> u16 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 优化后的中间表示
+    pub fn ir(&self) -> &[IrOp] {
+        &self.ir
+    }
+
+    /// 块内指令与译码好的块尾占用的字节
+    fn bytes(&self) -> core::ops::Range<usize> {
+        let len = self.len as usize + self.tail.is_some() as usize;
+        self.start as usize..self.start as usize + len * 2
+    }
+
+    fn execute(&self, vm: &mut Chip8) {
+        let mut calls = self.calls.iter();
+        for op in &self.ir {
+            match *op {
+                IrOp::SetReg { x, value } => vm.r_v[x as usize] = value,
+                IrOp::AddReg { x, value } => {
+                    vm.r_v[x as usize] = vm.r_v[x as usize].wrapping_add(value)
+                }
+                IrOp::SetI(value) => vm.r_i = value.into(),
+                IrOp::Call(_) => {
+                    let (op, ir) = calls.next().unwrap();
+                    op(vm, ir);
+                }
+            }
+        }
+        vm.r_pc = self.start + self.len * 2;
+    }
+}
+
+/// 可以放进基本块的指令：只读写寄存器、I、计时器，或读取内存，不会出错。
+/// 当前平台上不存在的指令会出错，同样不放进基本块
+fn is_straight_line(ir: &Instruction, platform: Platform) -> bool {
+    if !platform.supports(ir) {
+        return false;
+    }
+    match ir.opcode {
+        0x6000 | 0x7000 | 0x8000 | 0xa000 | 0xc000 => true,
+        0xf000 => matches!(ir.kk, 0x07 | 0x15 | 0x18 | 0x1e | 0x29 | 0x30 | 0x65),
+        _ => false,
+    }
+}
+
+/// 编译 `start` 开始的基本块。被程序改写过的指令不编译，交给 `cycle` 执行以便记录自修改代码
+fn compile(vm: &Chip8, start: u16) -> Block {
+    let mut ir = vec![];
+    let mut calls = vec![];
+    // 块内已知的寄存器值
+    let mut known: [Option<u8>; 16] = [None; 16];
+    let mut len = 0;
+    let mut pc = start;
+
+    while len < MAX_BLOCK_LEN && (pc as usize) + 1 < vm.code_space() {
+        if vm.code_writes().writer(pc).is_some() {
+            break;
+        }
+        let ins = Instruction::new(vm.read_opcode(pc));
+        if !is_straight_line(&ins, vm.platform()) {
+            break;
+        }
+        let (x, y) = (ins.x as usize, ins.y as usize);
+        match (ins.opcode, ins.n) {
+            (0x6000, _) => {
+                known[x] = Some(ins.kk);
+                ir.push(IrOp::SetReg {
+                    x: ins.x,
+                    value: ins.kk,
+                });
+            }
+            (0x7000, _) => match known[x] {
+                Some(value) => {
+                    let value = value.wrapping_add(ins.kk);
+                    known[x] = Some(value);
+                    ir.push(IrOp::SetReg { x: ins.x, value });
+                }
+                None => ir.push(IrOp::AddReg {
+                    x: ins.x,
+                    value: ins.kk,
+                }),
+            },
+            (0x8000, 0x0) if known[y].is_some() => {
+                known[x] = known[y];
+                ir.push(IrOp::SetReg {
+                    x: ins.x,
+                    value: known[y].unwrap(),
+                });
+            }
+            (0xa000, _) => ir.push(IrOp::SetI(ins.nnn)),
+            _ => {
+                // 执行函数可能改写任何寄存器
+                known = [None; 16];
+                ir.push(IrOp::Call(ins.ir_code));
+                calls.push((vm::decode(&ins, vm.platform()), ins));
+            }
+        }
+        len += 1;
+        pc += 2;
+    }
+
+    let tail =
+        ((pc as usize) + 1 < vm.code_space() && vm.code_writes().writer(pc).is_none()).then(|| {
+            let ins = Instruction::new(vm.read_opcode(pc));
+            (vm::decode(&ins, vm.platform()), ins)
+        });
+    Block {
+        start,
+        len,
+        ir: eliminate_dead_stores(ir),
+        calls,
+        tail,
+    }
+}
+
+/// 删除在被读取前就被后面的 `SetReg`、`SetI` 覆盖的赋值。`Call` 可能读取任何寄存器
+fn eliminate_dead_stores(ir: Vec<IrOp>) -> Vec<IrOp> {
+    let mut overwritten = [false; 17];
+    let mut live: Vec<IrOp> = ir
+        .into_iter()
+        .rev()
+        .filter(|op| match (op, op.target()) {
+            (IrOp::Call(_), _) => {
+                overwritten = [false; 17];
+                true
+            }
+            (_, Some(target)) if overwritten[target] => false,
+            (IrOp::AddReg { .. }, _) => true,
+            (_, Some(target)) => {
+                overwritten[target] = true;
+                true
+            }
+            (_, None) => true,
+        })
+        .collect();
+    live.reverse();
+    live
+}
+
+/// 基本块重编译后端
+#[derive(Debug, Default)]
+pub struct Recompiler {
+    blocks: Vec<Block>,
+    /// 每个地址开始的块在 `blocks` 中的下标，没有时为 `NO_BLOCK`
+    index: Vec<u32>,
+    /// 每个字节被多少个已编译的块覆盖
+    coverage: Vec<u16>,
+    /// 从 VM 取出的写入地址，留着复用
+    writes: Vec<u16>,
+    generation: u64,
+    compiled: u64,
+}
+
+impl Recompiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `start` 处已编译的块
+    pub fn block(&self, start: u16) -> Option<&Block> {
+        let slot = *self.index.get(start as usize)?;
+        self.blocks.get(slot as usize)
+    }
+
+    /// 目前有效的块数
+    pub fn block_count(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// 累计编译的次数，包括失效后的重新编译
+    pub fn compiled(&self) -> u64 {
+        self.compiled
+    }
+
+    /// 丢弃内存被整体替换前编译的块，以及被写入过的块。没有新的写入时直接返回
+    fn sync(&mut self, vm: &mut Chip8) {
+        if self.generation == vm.code_generation()
+            && self.index.len() == vm.code_space()
+            && !vm.has_logged_writes()
+        {
+            return;
+        }
+        if self.generation != vm.code_generation() || self.index.len() != vm.code_space() {
+            self.blocks.clear();
+            self.index = vec![NO_BLOCK; vm.code_space()];
+            self.coverage = vec![0; vm.code_space()];
+            self.generation = vm.code_generation();
+        }
+        vm.swap_write_log(&mut self.writes);
+        for i in 0..self.writes.len() {
+            let addr = self.writes[i] as usize;
+            if self.coverage[addr] > 0 {
+                self.remove_blocks_at(addr);
+            }
+        }
+    }
+
+    /// 删除覆盖 `addr` 的块
+    fn remove_blocks_at(&mut self, addr: usize) {
+        let mut slot = 0;
+        while slot < self.blocks.len() {
+            if !self.blocks[slot].bytes().contains(&addr) {
+                slot += 1;
+                continue;
+            }
+            let block = self.blocks.swap_remove(slot);
+            self.index[block.start as usize] = NO_BLOCK;
+            for byte in block.bytes() {
+                self.coverage[byte] -= 1;
+            }
+            if let Some(moved) = self.blocks.get(slot) {
+                self.index[moved.start as usize] = slot as u32;
+            }
+        }
+    }
+
+    fn block_at(&mut self, vm: &Chip8, pc: u16) -> &Block {
+        let mut slot = self.index[pc as usize];
+        if slot == NO_BLOCK {
+            let block = compile(vm, pc);
+            for byte in block.bytes() {
+                self.coverage[byte] += 1;
+            }
+            self.compiled += 1;
+            slot = self.blocks.len() as u32;
+            self.index[pc as usize] = slot;
+            self.blocks.push(block);
+        }
+        &self.blocks[slot as usize]
+    }
+}
+
+impl Backend for Recompiler {
+    fn run(&mut self, vm: &mut Chip8, cycles: u32) -> StepOutcome {
+        self.sync(vm);
+        let mut done = 0;
+        while done < cycles {
+            if vm.can_run_compiled() {
+                // 与 cycle 取指时一样，超出内存的 PC 先绕回内存开头再查找块
+                vm.r_pc = vm.wrap_addr(vm.r_pc);
+                let block = self.block_at(vm, vm.r_pc);
+                let len = block.len as u32;
+                if done + len <= cycles {
+                    block.execute(vm);
+                    done += len;
+                    if done == cycles {
+                        break;
+                    }
+                    if let Some((op, ir)) = block.tail {
+                        let outcome = vm.execute_decoded(vm.r_pc, op, &ir);
+                        done += 1;
+                        self.sync(vm);
+                        if outcome != StepOutcome::Ran {
+                            return outcome;
+                        }
+                        continue;
+                    }
+                }
+            }
+
+            let outcome = vm.cycle();
+            done += 1;
+            self.sync(vm);
+            if outcome != StepOutcome::Ran {
+                return outcome;
+            }
+        }
+        StepOutcome::Ran
+    }
+}
@@ -0,0 +1,56 @@
+use core::fmt;
+
+/// CXNN 使用的随机数来源，可以换成硬件随机数发生器等外部实现
+pub trait RandomSource: fmt::Debug {
+    fn next_u8(&mut self) -> u8;
+}
+
+/// 默认的种子：有 `std` 时取自系统熵源，否则为固定值
+pub(crate) fn default_seed() -> u32 {
+    #[cfg(feature = "std")]
+    {
+        rand::random()
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        0
+    }
+}
+
+/// xorshift32 随机数发生器。
+///
+/// 算法足够简单，TypeScript 版本（`js/src/Random.ts`）用同样的种子能得到同样的序列，
+/// 两个实现可以逐条指令对比执行结果。
+#[derive(Debug, Clone)]
+pub struct Xorshift32 {
+    state: u32,
+}
+
+impl Xorshift32 {
+    /// 种子为 0 时 xorshift 会一直输出 0，这里换成一个固定的非零值
+    pub fn new(seed: u32) -> Self {
+        Xorshift32 {
+            state: if seed == 0 { 0x2545_F491 } else { seed },
+        }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    /// 0 ~ 255，取高 8 位
+    pub fn next_u8(&mut self) -> u8 {
+        (self.next_u32() >> 24) as u8
+    }
+}
+
+impl RandomSource for Xorshift32 {
+    fn next_u8(&mut self) -> u8 {
+        Xorshift32::next_u8(self)
+    }
+}
@@ -0,0 +1,630 @@
+//! 内置的 ROM 数据库：按 SHA-1 记录 `assets/roms` 中每个 ROM 的标题、作者、平台和按键映射。
+//!
+//! 字段参照社区的 chip-8-database；作者只收录随 ROM 附带的文档与源码中写明的。
+//! 按键映射取自同名的 `.c8k` 文件，平台按 ROM 所在目录（`chip8/`、`schip8/`）确定。
+//! 随 ROM 附带的 `chip8.txt`、`schip8.txt` 只有玩法说明，没有速度和配色，这两项不收录，使用平台的默认值。
+
+use crate::platform::Platform;
+use crate::quirks::Quirks;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RomInfo {
+    /// ROM 内容的 SHA-1（小写十六进制）
+    pub sha1: &'static str,
+    pub title: &'static str,
+    pub authors: &'static [&'static str],
+    pub platform: Platform,
+    /// `.c8k` 格式的按键映射：第 N 个字符是触发 CHIP-8 按键 N 的键
+    pub keys: Option<&'static [u8; 16]>,
+}
+
+impl RomInfo {
+    /// 平台推荐的 quirks
+    pub fn quirks(&self) -> Quirks {
+        self.platform.quirks()
+    }
+
+    /// 每帧执行的指令数，即平台的默认值
+    pub fn tick_rate(&self) -> u16 {
+        self.platform.tick_rate()
+    }
+
+    /// 解析后的按键映射，见 [`Keyboard::set_keymap`](crate::hardware::Keyboard::set_keymap)
+    pub fn keymap(&self) -> Option<[u8; 16]> {
+        let keys = self.keys?;
+        let mut keymap = [0; 16];
+        for (slot, &key) in keymap.iter_mut().zip(keys.iter()) {
+            *slot = (key as char).to_digit(16)? as u8;
+        }
+        Some(keymap)
+    }
+}
+
+/// 按 SHA-1（大小写均可）查找 ROM
+pub fn lookup(sha1: &str) -> Option<&'static RomInfo> {
+    ROMS.iter().find(|rom| rom.sha1.eq_ignore_ascii_case(sha1))
+}
+
+/// 数据库中的所有 ROM
+pub fn all() -> &'static [RomInfo] {
+    ROMS
+}
+
+static ROMS: &[RomInfo] = &[
+    // chip8/15PUZZLE.ch8
+    RomInfo {
+        sha1: "cf3a8c546038c63cd4cc1de8d171b9bf0d57c0ee",
+        title: "15 Puzzle",
+        authors: &["Roger Ivie"],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/AIRPLANE.ch8
+    RomInfo {
+        sha1: "fca71182a8838b686573e69b22aff945d79fe1d0",
+        title: "Airplane",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/BLINKY.ch8
+    RomInfo {
+        sha1: "d40abc54374e4343639f993e897e00904ddf85d9",
+        title: "Blinky",
+        authors: &["Christian Egeberg"],
+        platform: Platform::Chip8,
+        keys: Some(b"0122458469ABCDE5"),
+    },
+    // chip8/BLITZ.ch8
+    RomInfo {
+        sha1: "6f6509f38220e057a7e32ebb22dd353c1078e3e7",
+        title: "Blitz",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/BREAKOUT.ch8
+    RomInfo {
+        sha1: "237756a4014fb3aa82a29246a7cdd534f8dc2dbb",
+        title: "Breakout",
+        authors: &["Paul Vervalin"],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/BRIX.ch8
+    RomInfo {
+        sha1: "f13766c14aeb02ad8d4d103cb5eadd282d20cddc",
+        title: "Brix",
+        authors: &["Paul Vervalin"],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/CAVE.ch8
+    RomInfo {
+        sha1: "5c82520906073287a3ef781746c67207ca084d93",
+        title: "Cave",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: Some(b"0123456789ABCDE5"),
+    },
+    // chip8/CONNECT4.ch8
+    RomInfo {
+        sha1: "2d10c07b532f4fa7c07a07324ba26ca39fe484fd",
+        title: "Connect 4",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/FIGURES.ch8
+    RomInfo {
+        sha1: "3b2bf5dc7ffb5f3fbe168e802079f79730535ca8",
+        title: "Figures",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/FILTER.ch8
+    RomInfo {
+        sha1: "ae71a7b081a947f1760cdc147759803aea45e751",
+        title: "Filter",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/GUESS.ch8
+    RomInfo {
+        sha1: "137cb8397456f53fcab216124458238bc18c0965",
+        title: "Guess",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/HIDDEN.ch8
+    RomInfo {
+        sha1: "050f07a54371da79f924dd0227b89d07b4f2aed0",
+        title: "Hidden",
+        authors: &["David Winter"],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/INVADERS.ch8
+    RomInfo {
+        sha1: "5c28a5f85289c9d859f95fd5eadbdcb1c30bb08b",
+        title: "Space Invaders",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/KALEID.ch8
+    RomInfo {
+        sha1: "d6fa9dc9005dc0496f39ba52fef56f9fd0a5a158",
+        title: "Kaleidoscope",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: Some(b"5123456789ABCDEF"),
+    },
+    // chip8/LANDING.ch8
+    RomInfo {
+        sha1: "72fb3e0a4572bdb81f484df7948a8bc736fe78d0",
+        title: "Landing",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: Some(b"0123456785ABCDEF"),
+    },
+    // chip8/MAZE.ch8
+    RomInfo {
+        sha1: "8b70080adbac44513ec60005734a816372b845ec",
+        title: "Maze",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/MERLIN.ch8
+    RomInfo {
+        sha1: "d979858bb9ffd07b48f52f92a8bcac0199f3623e",
+        title: "Merlin",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: Some(b"0123426869ABCDEF"),
+    },
+    // chip8/MISSILE.ch8
+    RomInfo {
+        sha1: "0d0cc129dad3c45ba672f85fec71a668232212cc",
+        title: "Missile",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/PADDLES.ch8
+    RomInfo {
+        sha1: "a18f1e3897416180b32e47ddc82cba9aca2c8d52",
+        title: "Paddles",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/PONG(1P).ch8
+    RomInfo {
+        sha1: "607c4f7f4e4dce9f99d96b3182bfe7e88bb090ee",
+        title: "Pong (1 player)",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/PONG.ch8
+    RomInfo {
+        sha1: "b232ef880bd6060fb45fa6effed7edf0ae95670e",
+        title: "Pong",
+        authors: &["Paul Vervalin"],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/PONG2.ch8
+    RomInfo {
+        sha1: "1830eb401ba8789a477dfcf294873a5479ebcfe8",
+        title: "Pong 2",
+        authors: &["Paul Vervalin"],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/PUZZLE.ch8
+    RomInfo {
+        sha1: "1293db0ccccbe7dd3fc5a09a2abc5d7b175e18e0",
+        title: "Puzzle",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: Some(b"0183456729ABCDEF"),
+    },
+    // chip8/ROCKET.ch8
+    RomInfo {
+        sha1: "5e70f91ca08e9b9e9de61670492e3db2d7f7d57a",
+        title: "Rocket",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: Some(b"0123456789A5CDEF"),
+    },
+    // chip8/SOCCER.ch8
+    RomInfo {
+        sha1: "6df358d77961a0bf21e98876f9f616791cba31e3",
+        title: "Soccer",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/SPACEF.ch8
+    RomInfo {
+        sha1: "aa4f1a282bd64a2364102abf5737a4205365a2b4",
+        title: "Space Flight",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: Some(b"0123456789ABCD65"),
+    },
+    // chip8/SQUASH.ch8
+    RomInfo {
+        sha1: "a58ec7cc63707f9e7274026de27c15ec1d9945bd",
+        title: "Squash",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: Some(b"0223656789ABCDEF"),
+    },
+    // chip8/SYZYGY.ch8
+    RomInfo {
+        sha1: "1bdb4ddaa7049266fa3226851f28855a365cfd12",
+        title: "Syzygy",
+        authors: &["Roy Trevino"],
+        platform: Platform::Chip8,
+        keys: Some(b"0122458469A5CD28"),
+    },
+    // chip8/TANK.ch8
+    RomInfo {
+        sha1: "18b9d15f4c159e1f0ed58c2d8ec1d89325d3a3b6",
+        title: "Tank",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: Some(b"0183456729ABCDEF"),
+    },
+    // chip8/TETRIS.ch8
+    RomInfo {
+        sha1: "5f518084744bf3cb8733f6e5454dfd1634320563",
+        title: "Tetris",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: Some(b"0823546789ABCDEF"),
+    },
+    // chip8/TICTAC.ch8
+    RomInfo {
+        sha1: "429d455a4bc53167942bf6fd934d72b0f648dce3",
+        title: "Tic-Tac-Toe",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/TRON.ch8
+    RomInfo {
+        sha1: "a6a6cb2351c20b8f904da07c0ce91bd8161e9317",
+        title: "Tron",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/UFO.ch8
+    RomInfo {
+        sha1: "bdb92475acfe11bc7814a2f5eade13fcd09b756a",
+        title: "UFO",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/VBRIX.ch8
+    RomInfo {
+        sha1: "da710f631f8e35534d0b9170bcf892a60f49c43d",
+        title: "Vertical Brix",
+        authors: &["Paul Robson"],
+        platform: Platform::Chip8,
+        keys: Some(b"0223656589ABCDEF"),
+    },
+    // chip8/VERS.ch8
+    RomInfo {
+        sha1: "ade839585ddeb0e3633177df03c1d91589e629eb",
+        title: "Vers",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/WALL.ch8
+    RomInfo {
+        sha1: "09ce01c54ddddda42ca5cd171f1ffcfd47355d12",
+        title: "Wall",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: Some(b"0223656789ABCDEF"),
+    },
+    // chip8/WIPEOFF.ch8
+    RomInfo {
+        sha1: "d666688a8fce468a7d88b536bc1ef5f35ba12031",
+        title: "Wipe Off",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/TEST/C8PIC.ch8
+    RomInfo {
+        sha1: "a82ca5c53e1dcedfab4f65efef02229145771b7d",
+        title: "CHIP8 Picture",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/TEST/IBM.ch8
+    RomInfo {
+        sha1: "1ba58656810b67fd131eb9af3e3987863bf26c90",
+        title: "IBM Logo",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/TEST/Rocket2.ch8
+    RomInfo {
+        sha1: "e2005db6391f589534dd2d63a95b429338bd667c",
+        title: "Rocket 2",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: Some(b"0123456789ABCDE5"),
+    },
+    // chip8/TEST/TAPEWORM.ch8
+    RomInfo {
+        sha1: "775e82a36c93f1b41b42eca94b55acbc4a48cebe",
+        title: "Tapeworm",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: Some(b"0123456789ABCDE5"),
+    },
+    // chip8/TEST/TIMEBOMB.ch8
+    RomInfo {
+        sha1: "67996195539c0ddcd98533a01dffeec6a53a6da1",
+        title: "Time Bomb",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/TEST/X-MIRROR.ch8
+    RomInfo {
+        sha1: "bc158d819890f16f105b8a316eeeefe4a0bad875",
+        title: "X-Mirror",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/TEST/test_bc.ch8
+    RomInfo {
+        sha1: "9df1689015a0d1d95144f141903296f9f1c35fc5",
+        title: "BC Test",
+        authors: &["BestCoder"],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // chip8/TEST/test_opcode.ch8
+    RomInfo {
+        sha1: "f1cfcffe1937ed6dd6eeed1a7f85dfc777bda700",
+        title: "Opcode Test",
+        authors: &[],
+        platform: Platform::Chip8,
+        keys: None,
+    },
+    // schip8/ALIEN.ch8
+    RomInfo {
+        sha1: "bc5faf54f04da3f4dbde50d3b31ccfc2bf8b9e06",
+        title: "Alien",
+        authors: &[],
+        platform: Platform::SuperChip,
+        keys: Some(b"01244567895B6DEF"),
+    },
+    // schip8/ANT.ch8
+    RomInfo {
+        sha1: "a56c09537df0f32e2d49fb68cb2ba8216b38f632",
+        title: "Ant",
+        authors: &[],
+        platform: Platform::SuperChip,
+        keys: Some(b"01244567895B6DEF"),
+    },
+    // schip8/BLINKY.ch8
+    RomInfo {
+        sha1: "5b733a60e7208f6aa0d15c99390ce4f670b2b886",
+        title: "Blinky",
+        authors: &["Christian Egeberg"],
+        platform: Platform::SuperChip,
+        keys: Some(b"0122458469ABCDE5"),
+    },
+    // schip8/CAR.ch8
+    RomInfo {
+        sha1: "2cd26a9a84ed2be6aaa6916d49b2e5c503196400",
+        title: "Car",
+        authors: &[],
+        platform: Platform::SuperChip,
+        keys: Some(b"0123456469ABCDEF"),
+    },
+    // schip8/DRAGON1.ch8
+    RomInfo {
+        sha1: "d6cbd3af85b4c55b83c4e01f3a17c66fcebe9ccc",
+        title: "Single Dragon (stage 1)",
+        authors: &[],
+        platform: Platform::SuperChip,
+        keys: None,
+    },
+    // schip8/DRAGON2.ch8
+    RomInfo {
+        sha1: "6b6502b03183e492f8170172308df9876c29d1d9",
+        title: "Single Dragon (stage 2)",
+        authors: &[],
+        platform: Platform::SuperChip,
+        keys: None,
+    },
+    // schip8/FIELD.ch8
+    RomInfo {
+        sha1: "31fe380556d65600ef293d99aabd3b6bb119aa01",
+        title: "Field!",
+        authors: &["Al Roland"],
+        platform: Platform::SuperChip,
+        keys: None,
+    },
+    // schip8/HPIPER.ch8
+    RomInfo {
+        sha1: "01ffe488efbe14ca63de1c23053806533e329f3f",
+        title: "H. Piper",
+        authors: &["Paul Raines"],
+        platform: Platform::SuperChip,
+        keys: Some(b"0122558469ABCDEF"),
+    },
+    // schip8/JOUST23.ch8
+    RomInfo {
+        sha1: "6d677bb44500a5ee4754b3a75516cfd9e73947fc",
+        title: "Joust",
+        authors: &["Erin S. Catto"],
+        platform: Platform::SuperChip,
+        keys: Some(b"01244567895B6DEF"),
+    },
+    // schip8/LASER.ch8
+    RomInfo {
+        sha1: "f31a8912ffb8a2920eb7ad5d645aa65a413b6ae9",
+        title: "Laser",
+        authors: &[],
+        platform: Platform::SuperChip,
+        keys: None,
+    },
+    // schip8/MATCHES.ch8
+    RomInfo {
+        sha1: "24fd50a95b84e3a42e336a06567a9752f17b9979",
+        title: "Matches",
+        authors: &[],
+        platform: Platform::SuperChip,
+        keys: None,
+    },
+    // schip8/MAZE.ch8
+    RomInfo {
+        sha1: "e6d910b7c9f9680df462662ce16336ebcb0eab1e",
+        title: "Maze",
+        authors: &[],
+        platform: Platform::SuperChip,
+        keys: None,
+    },
+    // schip8/MINES.ch8
+    RomInfo {
+        sha1: "6d4514ae3a43c307763648b0bdd485fb77bcf20d",
+        title: "Mines",
+        authors: &["David Winter"],
+        platform: Platform::SuperChip,
+        keys: None,
+    },
+    // schip8/RACE.ch8
+    RomInfo {
+        sha1: "e6d4a8598999b3d95047babf67b529d83eaa9554",
+        title: "Race",
+        authors: &["Erik Bryntse"],
+        platform: Platform::SuperChip,
+        keys: Some(b"0123456469ABCDEF"),
+    },
+    // schip8/SPACEFIG.ch8
+    RomInfo {
+        sha1: "a05844df3305738e4030512f0063db2fe4f3bd11",
+        title: "SpaceFight 2091",
+        authors: &[],
+        platform: Platform::SuperChip,
+        keys: Some(b"01244567895B6DEF"),
+    },
+    // schip8/SQUARE.ch8
+    RomInfo {
+        sha1: "e4ef6fff9813c43bd7ad2ecaf02d1a3135d68418",
+        title: "Square",
+        authors: &["David Winter"],
+        platform: Platform::SuperChip,
+        keys: None,
+    },
+    // schip8/UBOAT.ch8
+    RomInfo {
+        sha1: "7321e1bbe885a749b2ca875d1f49fb6c01f54f91",
+        title: "U-Boat",
+        authors: &["Michael D. Kemper"],
+        platform: Platform::SuperChip,
+        keys: Some(b"0123456456AB2D8F"),
+    },
+    // schip8/WORM3.ch8
+    RomInfo {
+        sha1: "f8008875a4b35dc7188eeca2a05535116371eaf0",
+        title: "Worm 3",
+        authors: &[],
+        platform: Platform::SuperChip,
+        keys: Some(b"0143456786ABCDEF"),
+    },
+    // schip8/TEST/BMPviewer.ch8
+    RomInfo {
+        sha1: "eba3b6ac5539452d1dd0c7c045d69e6096c457dd",
+        title: "BMP Viewer",
+        authors: &[],
+        platform: Platform::SuperChip,
+        keys: None,
+    },
+    // schip8/TEST/Emutest.ch8
+    RomInfo {
+        sha1: "e8477fad78863714c508c046d2419248c5f89690",
+        title: "Emutest",
+        authors: &[],
+        platform: Platform::SuperChip,
+        keys: None,
+    },
+    // schip8/TEST/HEX_MIXT.ch8
+    RomInfo {
+        sha1: "d9389d564baced03192503a58ad930110bb0fe03",
+        title: "Hex Mix",
+        authors: &[],
+        platform: Platform::SuperChip,
+        keys: None,
+    },
+    // schip8/TEST/LINEDEMO.ch8
+    RomInfo {
+        sha1: "a9bf29597674c39b4e11d964b352b1e52c4ebb2f",
+        title: "Line Demo",
+        authors: &[],
+        platform: Platform::SuperChip,
+        keys: None,
+    },
+    // schip8/TEST/ROBOT.ch8
+    RomInfo {
+        sha1: "dd6ef80cadef1e7b42f71ad99573b1af2299e27d",
+        title: "Robot",
+        authors: &[],
+        platform: Platform::SuperChip,
+        keys: None,
+    },
+    // schip8/TEST/SCR_TEST.ch8
+    RomInfo {
+        sha1: "9b7faac49c44c1194a3283c2ef89eabfca76fe38",
+        title: "Scroll Test",
+        authors: &[],
+        platform: Platform::SuperChip,
+        keys: None,
+    },
+    // schip8/TEST/TEST.ch8
+    RomInfo {
+        sha1: "a1ec824285a593cd1ca84dc6c732c61b0fe96330",
+        title: "SCHIP Test",
+        authors: &[],
+        platform: Platform::SuperChip,
+        keys: None,
+    },
+    // schip8/TEST/TEST_128.ch8
+    RomInfo {
+        sha1: "c1b605040e29cce2a6fc52334fb09b0985340314",
+        title: "SCHIP Test 128",
+        authors: &[],
+        platform: Platform::SuperChip,
+        keys: None,
+    },
+    // schip8/TEST/WORMS.ch8
+    RomInfo {
+        sha1: "709328365147967f434d1bf78430e9ec160cc24f",
+        title: "Worms",
+        authors: &[],
+        platform: Platform::SuperChip,
+        keys: None,
+    },
+];
@@ -0,0 +1,86 @@
+//! HP-48 的 RPL 用户标志（FX75/FX85）在关机后仍然保留，一些游戏用它保存最高分。
+//!
+//! 挂上 [`RplStorage`]（[`Chip8::set_rpl_storage`](crate::vm::Chip8::set_rpl_storage)）后，
+//! `load_rom` 按 ROM 的 SHA-1 读取之前保存的标志，每次 FX75 写入后立即保存。
+
+use alloc::collections::BTreeMap;
+#[cfg(feature = "std")]
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+
+/// RPL 标志的存储，`key` 为 ROM 的 SHA-1（小写十六进制）
+pub trait RplStorage: fmt::Debug {
+    /// 读取保存过的标志，没有时返回 None
+    fn load(&mut self, key: &str) -> Option<Vec<u8>>;
+
+    fn store(&mut self, key: &str, flags: &[u8]) -> Result<(), String>;
+}
+
+/// 保存在内存中，clone 出的副本共享同一份数据，可以模拟多次启动
+#[derive(Debug, Clone, Default)]
+pub struct MemoryRplStorage {
+    flags: Rc<RefCell<BTreeMap<String, Vec<u8>>>>,
+}
+
+impl MemoryRplStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<Vec<u8>> {
+        self.flags.borrow().get(key).cloned()
+    }
+}
+
+impl RplStorage for MemoryRplStorage {
+    fn load(&mut self, key: &str) -> Option<Vec<u8>> {
+        self.get(key)
+    }
+
+    fn store(&mut self, key: &str, flags: &[u8]) -> Result<(), String> {
+        self.flags
+            .borrow_mut()
+            .insert(key.to_string(), flags.to_vec());
+        Ok(())
+    }
+}
+
+/// 每个 ROM 一个文件：`<dir>/<sha1>.rpl`，内容为标志的原始字节
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct FileRplStorage {
+    dir: PathBuf,
+}
+
+#[cfg(feature = "std")]
+impl FileRplStorage {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FileRplStorage { dir: dir.into() }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.rpl", key))
+    }
+}
+
+#[cfg(feature = "std")]
+impl RplStorage for FileRplStorage {
+    fn load(&mut self, key: &str) -> Option<Vec<u8>> {
+        fs::read(self.path(key)).ok()
+    }
+
+    fn store(&mut self, key: &str, flags: &[u8]) -> Result<(), String> {
+        let path = self.path(key);
+        fs::create_dir_all(&self.dir)
+            .and_then(|_| fs::write(&path, flags))
+            .map_err(|err| format!("{}: {}", path.display(), err))
+    }
+}
@@ -0,0 +1,288 @@
+//! 帧循环：按 60Hz 的帧驱动 [`Chip8`]，把画面、声音、输入和计时交给外部的实现。
+//!
+//! 每一帧依次：从 [`InputSource`] 读取按键，执行 `rate / 60` 条指令，调用 `ticker`，
+//! 画面有变化时交给 [`DisplaySink`]，蜂鸣器开关变化时通知 [`AudioSink`]。
+//! [`Clock`] 决定什么时候该跑下一帧：测试与批量运行不等待，交互运行按真实时间等待。
+//! 按真实时间等待、输出到终端和回放输入录像的实现需要 `std`。
+
+use alloc::boxed::Box;
+use alloc::string::String;
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+#[cfg(feature = "std")]
+use std::thread;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use crate::hardware::{Keyboard, Screen};
+#[cfg(feature = "std")]
+use crate::trace::InputEvent;
+use crate::vm::{Backend, Chip8, Interpreter, StepOutcome};
+
+/// 一帧的时长
+pub const FRAME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// [`RealTimeClock`] 一次最多补的帧数
+pub const MAX_CATCH_UP: u32 = 4;
+
+pub trait DisplaySink {
+    /// 本帧画面有变化，`size` 为当前平台与模式下可见区域的大小
+    fn present(&mut self, screen: &Screen, size: (u8, u8));
+}
+
+pub trait AudioSink {
+    /// 蜂鸣器开始或停止发声，sound 计时器不为 0 时发声
+    fn set_tone(&mut self, on: bool);
+}
+
+pub trait InputSource {
+    /// 每帧开始时调用，更新 `keyboard`
+    fn poll(&mut self, frame: u64, keyboard: &mut Keyboard);
+}
+
+pub trait Clock {
+    /// 等到至少一帧到期，返回到期的帧数
+    fn wait(&mut self) -> u32;
+}
+
+/// 不显示画面
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoDisplay;
+
+impl DisplaySink for NoDisplay {
+    fn present(&mut self, _screen: &Screen, _size: (u8, u8)) {}
+}
+
+/// 不发声
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoAudio;
+
+impl AudioSink for NoAudio {
+    fn set_tone(&mut self, _on: bool) {}
+}
+
+/// 没有输入
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoInput;
+
+impl InputSource for NoInput {
+    fn poll(&mut self, _frame: u64, _keyboard: &mut Keyboard) {}
+}
+
+/// 按帧号回放 [`InputEvent`]，与 `trace` 的输入录像格式相同
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedInput {
+    events: Vec<InputEvent>,
+}
+
+#[cfg(feature = "std")]
+impl ScriptedInput {
+    pub fn new(events: Vec<InputEvent>) -> Self {
+        ScriptedInput { events }
+    }
+}
+
+#[cfg(feature = "std")]
+impl InputSource for ScriptedInput {
+    fn poll(&mut self, frame: u64, keyboard: &mut Keyboard) {
+        for event in self.events.iter().filter(|event| event.frame == frame) {
+            if event.down {
+                keyboard.key_down(event.key);
+            } else {
+                keyboard.key_up(event.key);
+            }
+        }
+    }
+}
+
+/// 不等待，每次一帧，用于测试和批量运行
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FixedClock;
+
+impl Clock for FixedClock {
+    fn wait(&mut self) -> u32 {
+        1
+    }
+}
+
+/// 按真实时间每 1/60 秒一帧。落后时一次补上多帧，最多补 [`MAX_CATCH_UP`] 帧
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct RealTimeClock {
+    next: Instant,
+}
+
+#[cfg(feature = "std")]
+impl RealTimeClock {
+    pub fn new() -> Self {
+        RealTimeClock {
+            next: Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for RealTimeClock {
+    fn wait(&mut self) -> u32 {
+        let now = Instant::now();
+        if now < self.next {
+            thread::sleep(self.next - now);
+        }
+        let behind = Instant::now().saturating_duration_since(self.next);
+        let due = (behind.as_nanos() / FRAME.as_nanos()) as u32 + 1;
+        if due > MAX_CATCH_UP {
+            // 落后太多（例如进程被挂起过），放弃补帧，从现在重新计时
+            self.next = Instant::now() + FRAME;
+            return MAX_CATCH_UP;
+        }
+        self.next += FRAME * due;
+        due
+    }
+}
+
+/// 画面的文本形式，每个字符是上下两个像素。只输出左上角 `size` 大小的可见区域
+pub fn render_text(screen: &Screen, size: (u8, u8)) -> String {
+    let (columns, rows) = size;
+    let mut text = String::new();
+    for y in (0..rows).step_by(2) {
+        for x in 0..columns {
+            text.push(match (screen.get_pixel(x, y), screen.get_pixel(x, y + 1)) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' ',
+            });
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// 用 [`render_text`] 把画面写到终端或文件
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct TextDisplay<W> {
+    out: W,
+    /// 每次输出前先把光标移回左上角，在终端中原地刷新
+    pub redraw: bool,
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> TextDisplay<W> {
+    pub fn new(out: W) -> Self {
+        TextDisplay { out, redraw: false }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> DisplaySink for TextDisplay<W> {
+    fn present(&mut self, screen: &Screen, size: (u8, u8)) {
+        let prefix = if self.redraw { "\x1b[H" } else { "" };
+        let _ = write!(self.out, "{}{}", prefix, render_text(screen, size));
+        let _ = self.out.flush();
+    }
+}
+
+/// 把蜂鸣器的开关写成 BEL 字符，终端会响一声
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct BellAudio<W>(pub W);
+
+#[cfg(feature = "std")]
+impl<W: Write> AudioSink for BellAudio<W> {
+    fn set_tone(&mut self, on: bool) {
+        if on {
+            let _ = self.0.write_all(b"\x07");
+            let _ = self.0.flush();
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl BellAudio<io::Stderr> {
+    pub fn stderr() -> Self {
+        BellAudio(io::stderr())
+    }
+}
+
+/// 帧循环
+#[derive(Debug)]
+pub struct Runner<D, A, I, C> {
+    pub display: D,
+    pub audio: A,
+    pub input: I,
+    pub clock: C,
+    backend: Box<dyn Backend>,
+    frame: u64,
+    tone: bool,
+}
+
+impl<D: DisplaySink, A: AudioSink, I: InputSource, C: Clock> Runner<D, A, I, C> {
+    /// 默认使用 [`Interpreter`] 执行指令
+    pub fn new(display: D, audio: A, input: I, clock: C) -> Self {
+        Runner {
+            display,
+            audio,
+            input,
+            clock,
+            backend: Box::new(Interpreter),
+            frame: 0,
+            tone: false,
+        }
+    }
+
+    pub fn set_backend(&mut self, backend: impl Backend + 'static) {
+        self.backend = Box::new(backend);
+    }
+
+    /// 已经运行的帧数
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// 运行一帧，返回执行指令的结果。停机或出错时本帧剩余的指令不再执行
+    pub fn run_frame(&mut self, vm: &mut Chip8) -> StepOutcome {
+        self.input.poll(self.frame, &mut vm.keyboard);
+        let outcome = self.backend.run(vm, (vm.rate / 60) as u32);
+        vm.ticker();
+
+        if vm.draw_flag {
+            self.display.present(&vm.screen, vm.display_size());
+            vm.draw_flag = false;
+        }
+        let tone = vm.s_timer > 0;
+        if tone != self.tone {
+            self.audio.set_tone(tone);
+            self.tone = tone;
+        }
+        self.frame += 1;
+        outcome
+    }
+
+    /// 按 `clock` 运行，直到停机、出错、遇到断点或运行了 `max_frames` 帧，返回最后一帧的结果
+    pub fn run(&mut self, vm: &mut Chip8, max_frames: u64) -> StepOutcome {
+        let end = self.frame + max_frames;
+        let mut outcome = StepOutcome::Ran;
+        while self.frame < end {
+            for _ in 0..self.clock.wait() {
+                outcome = self.run_frame(vm);
+                let stopped = matches!(
+                    outcome,
+                    StepOutcome::Halted | StepOutcome::Fault(_) | StepOutcome::Break(_)
+                );
+                if stopped || self.frame >= end {
+                    return outcome;
+                }
+            }
+        }
+        outcome
+    }
+}
@@ -0,0 +1,272 @@
+//! 截图：把当前画面导出为 PNG、PBM 或 PGM。
+//!
+//! 三种格式都不依赖外部库。PNG 为 8 bit RGB，zlib 数据只使用不压缩的 stored 块，文件较大但任何解码器都能读取；
+//! PBM（P4）与 PGM（P5）按亮度转换，PBM 中亮度低于一半的像素为黑色。
+
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::hardware::Screen;
+use crate::platform::Platform;
+use crate::vm::{Chip8, MONO_OFF, MONO_ON};
+
+/// 单色画面使用的颜色
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Colors {
+    pub foreground: [u8; 3],
+    pub background: [u8; 3],
+}
+
+impl Default for Colors {
+    /// 与网页前端相同
+    fn default() -> Self {
+        Colors {
+            foreground: MONO_ON,
+            background: MONO_OFF,
+        }
+    }
+}
+
+impl Colors {
+    /// 解析 `RRGGBB,RRGGBB`（前景色,背景色）
+    pub fn parse(text: &str) -> Option<Self> {
+        let (foreground, background) = text.split_once(',')?;
+        Some(Colors {
+            foreground: parse_rgb(foreground.trim())?,
+            background: parse_rgb(background.trim())?,
+        })
+    }
+}
+
+fn parse_rgb(text: &str) -> Option<[u8; 3]> {
+    let text = text.trim_start_matches('#');
+    if text.len() != 6 {
+        return None;
+    }
+    let value = u32::from_str_radix(text, 16).ok()?;
+    let [_, r, g, b] = value.to_be_bytes();
+    Some([r, g, b])
+}
+
+/// 导出的文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Pbm,
+    Pgm,
+}
+
+impl ImageFormat {
+    /// 按文件扩展名选择格式
+    pub fn from_path(path: &str) -> Option<Self> {
+        let (_, extension) = path.rsplit_once('.')?;
+        match extension.to_ascii_lowercase().as_str() {
+            "png" => Some(ImageFormat::Png),
+            "pbm" => Some(ImageFormat::Pbm),
+            "pgm" => Some(ImageFormat::Pgm),
+            _ => None,
+        }
+    }
+}
+
+/// 一帧画面的 RGB 像素，按行排列
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Screenshot {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<[u8; 3]>,
+}
+
+impl Screenshot {
+    /// 单色画面左上角 `size` 大小的可见区域
+    pub fn from_screen(screen: &Screen, size: (u8, u8), colors: Colors) -> Self {
+        let (width, height) = (size.0 as usize, size.1 as usize);
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..size.1 {
+            for x in 0..size.0 {
+                pixels.push(if screen.get_pixel(x, y) {
+                    colors.foreground
+                } else {
+                    colors.background
+                });
+            }
+        }
+        Screenshot {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// VM 当前的画面。CHIP-8X 与 MegaChip 模式使用画面本身的颜色，其它平台使用 `colors`
+    pub fn capture(vm: &Chip8, colors: Colors) -> Self {
+        let colored = vm.platform() == Platform::Chip8X
+            || vm.megachip().is_some_and(|megachip| megachip.enabled);
+        if !colored {
+            return Self::from_screen(&vm.screen, vm.display_size(), colors);
+        }
+        let (width, height) = vm.framebuffer_size();
+        let mut pixels = Vec::with_capacity(width as usize * height as usize);
+        for y in 0..height {
+            for x in 0..width {
+                pixels.push(vm.pixel_color(x as u8, y as u8));
+            }
+        }
+        Screenshot {
+            width: width as usize,
+            height: height as usize,
+            pixels,
+        }
+    }
+
+    /// 每个像素放大为 `factor` x `factor`，`factor` 为 0 时按 1 处理
+    pub fn scaled(&self, factor: usize) -> Self {
+        let factor = factor.max(1);
+        let width = self.width * factor;
+        let mut pixels = Vec::with_capacity(width * self.height * factor);
+        for row in self.pixels.chunks(self.width.max(1)) {
+            let start = pixels.len();
+            for pixel in row {
+                pixels.extend(core::iter::repeat_n(*pixel, factor));
+            }
+            for _ in 1..factor {
+                pixels.extend_from_within(start..start + width);
+            }
+        }
+        Screenshot {
+            width,
+            height: self.height * factor,
+            pixels,
+        }
+    }
+
+    pub fn encode(&self, format: ImageFormat) -> Vec<u8> {
+        match format {
+            ImageFormat::Png => self.to_png(),
+            ImageFormat::Pbm => self.to_pbm(),
+            ImageFormat::Pgm => self.to_pgm(),
+        }
+    }
+
+    /// 二进制 PBM（P4），每行按字节补齐，1 为黑色
+    pub fn to_pbm(&self) -> Vec<u8> {
+        let mut out = format!("P4\n{} {}\n", self.width, self.height).into_bytes();
+        let row_bytes = self.width.div_ceil(8);
+        for row in self.pixels.chunks(self.width.max(1)) {
+            let mut packed = vec![0u8; row_bytes];
+            for (x, pixel) in row.iter().enumerate() {
+                if luma(*pixel) < 0x80 {
+                    packed[x / 8] |= 0x80 >> (x % 8);
+                }
+            }
+            out.extend_from_slice(&packed);
+        }
+        out
+    }
+
+    /// 二进制 PGM（P5），8 bit 灰度
+    pub fn to_pgm(&self) -> Vec<u8> {
+        let mut out = format!("P5\n{} {}\n255\n", self.width, self.height).into_bytes();
+        out.extend(self.pixels.iter().map(|pixel| luma(*pixel)));
+        out
+    }
+
+    /// 8 bit RGB 的 PNG
+    pub fn to_png(&self) -> Vec<u8> {
+        // 每行前面是过滤类型 0
+        let mut raw = Vec::with_capacity((self.width * 3 + 1) * self.height);
+        for row in self.pixels.chunks(self.width.max(1)) {
+            raw.push(0);
+            raw.extend(row.iter().flatten());
+        }
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        // 位深 8，颜色类型 2（RGB），默认压缩与过滤，不交错
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+        let mut out = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+        write_chunk(&mut out, b"IHDR", &header);
+        write_chunk(&mut out, b"IDAT", &zlib_stored(&raw));
+        write_chunk(&mut out, b"IEND", &[]);
+        out
+    }
+}
+
+/// ITU-R BT.601 亮度
+fn luma([r, g, b]: [u8; 3]) -> u8 {
+    ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000) as u8
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// 只由 stored 块组成的 zlib 数据流
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const BLOCK: usize = 0xffff;
+    let mut out = Vec::with_capacity(data.len() + data.len() / BLOCK * 5 + 11);
+    // CM = 8（deflate），窗口 32K，FCHECK 使前两个字节是 31 的倍数
+    out.extend_from_slice(&[0x78, 0x01]);
+    let mut blocks = data.chunks(BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        out.push(blocks.peek().is_none() as u8);
+        let len = block.len() as u16;
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 是 b 不会溢出 u32 的最大块长
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    b << 16 | a
+}
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// PNG 块使用的 CRC-32
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &byte| {
+        CRC_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
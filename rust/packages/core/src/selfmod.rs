@@ -0,0 +1,106 @@
+//! 自修改代码检测：记录程序写入过的内存，并找出之后被当作指令执行的地址。
+//!
+//! PUZZLE 等 ROM 会用 FX33、FX55 改写自己的指令。缓存译码结果的执行方式也要靠这些记录来失效。
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// `written_by` 中表示没有被写入过。0xFFFF 处放不下一条完整的指令，不会是写入者
+const NOT_WRITTEN: u16 = u16::MAX;
+/// 最多记录这么多条被改写后执行过的指令，空间在创建时分配好，`cycle` 中记录时不再分配
+pub const MODIFIED_CODE_CAPACITY: usize = 256;
+
+/// 一条执行前被程序改写过的指令
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModifiedCode {
+    /// 指令地址
+    pub addr: u16,
+    /// 最近一次改写这条指令的指令地址
+    pub writer: u16,
+    /// 改写后被执行的次数
+    pub executions: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct CodeWrites {
+    /// 每个地址最近一次被哪条指令写入，创建时按内存大小分配好，记录写入时不再分配
+    written_by: Vec<u16>,
+    /// 按地址排序，不超过 [`MODIFIED_CODE_CAPACITY`] 项
+    executed: Vec<ModifiedCode>,
+    /// 记录已满后没能记下的指令条数
+    dropped: usize,
+}
+
+impl CodeWrites {
+    /// 记录 `memory_size` 字节内存的写入
+    pub fn new(memory_size: usize) -> Self {
+        CodeWrites {
+            written_by: vec![NOT_WRITTEN; memory_size],
+            executed: Vec::with_capacity(MODIFIED_CODE_CAPACITY),
+            dropped: 0,
+        }
+    }
+
+    pub(crate) fn record_write(&mut self, addr: u16, writer: u16) {
+        if let Some(slot) = self.written_by.get_mut(addr as usize) {
+            *slot = writer;
+        }
+    }
+
+    fn written_by(&self, addr: u16) -> Option<u16> {
+        self.written_by
+            .get(addr as usize)
+            .copied()
+            .filter(|&writer| writer != NOT_WRITTEN)
+    }
+
+    /// 改写过 `addr` 处指令（两个字节中任意一个）的指令地址
+    pub fn writer(&self, addr: u16) -> Option<u16> {
+        self.written_by(addr)
+            .or_else(|| self.written_by(addr.wrapping_add(1)))
+    }
+
+    /// 执行 `addr` 处的指令前调用，指令被改写过时记录下来并返回 true。记录已满时只计数
+    pub(crate) fn record_exec(&mut self, addr: u16) -> bool {
+        let Some(writer) = self.writer(addr) else {
+            return false;
+        };
+        match self.executed.binary_search_by_key(&addr, |code| code.addr) {
+            Ok(index) => {
+                let entry = &mut self.executed[index];
+                entry.writer = writer;
+                entry.executions += 1;
+            }
+            Err(_) if self.executed.len() == MODIFIED_CODE_CAPACITY => self.dropped += 1,
+            Err(index) => self.executed.insert(
+                index,
+                ModifiedCode {
+                    addr,
+                    writer,
+                    executions: 1,
+                },
+            ),
+        }
+        true
+    }
+
+    pub fn is_written(&self, addr: u16) -> bool {
+        self.written_by(addr).is_some()
+    }
+
+    /// 被改写后又执行过的指令，按地址排序
+    pub fn modified_code(&self) -> Vec<ModifiedCode> {
+        self.executed.clone()
+    }
+
+    /// 记录已满后执行的、没有记下的被改写指令条数（重复执行的也计入）
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    pub fn clear(&mut self) {
+        self.written_by.fill(NOT_WRITTEN);
+        self.executed.clear();
+        self.dropped = 0;
+    }
+}
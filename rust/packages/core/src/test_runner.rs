@@ -0,0 +1,176 @@
+//! 运行 Timendus chip8-test-suite 风格的测试 ROM。
+//!
+//! 这类 ROM 会读取 0x1FF 处的字节来选择要测试的平台，跑完后停在一个跳转到自身的 `1NNN` 上（[`StepOutcome::Halted`]），
+//! 并把每一项检查的结果画在屏幕上或写入内存的固定位置。
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::platform::Platform;
+use crate::quirks::Quirks;
+use crate::runner::{FixedClock, NoAudio, NoDisplay, NoInput, Runner};
+use crate::vm::{Chip8, StepOutcome, VmError};
+
+/// 测试 ROM 读取平台选择的地址
+pub const PLATFORM_SELECT: u16 = 0x1FF;
+
+/// 写入 [`PLATFORM_SELECT`] 的平台编号
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestPlatform {
+    Chip8 = 1,
+    Schip = 2,
+    XoChip = 3,
+}
+
+impl TestPlatform {
+    /// 运行该平台测试的模拟平台：COSMAC VIP 上的 CHIP-8、HP-48 上的 SUPER-CHIP 1.1 与 XO-CHIP
+    pub fn platform(&self) -> Platform {
+        match self {
+            TestPlatform::Chip8 => Platform::Vip,
+            TestPlatform::Schip => Platform::SuperChip,
+            TestPlatform::XoChip => Platform::XoChip,
+        }
+    }
+
+    /// 该平台对应的 quirks 配置
+    pub fn quirks(&self) -> Quirks {
+        self.platform().quirks()
+    }
+}
+
+/// 检查项的结果放在哪里
+#[derive(Debug, Clone, Copy)]
+pub enum Probe {
+    /// 内存中的一个字节，等于 `pass` 时通过
+    Memory { address: u16, pass: u8 },
+    /// 屏幕 (x, y) 处 8 像素宽的图案，逐行与 `pass` 相同时通过
+    Screen { x: u8, y: u8, pass: &'static [u8] },
+}
+
+/// 一项检查
+#[derive(Debug, Clone, Copy)]
+pub struct Check {
+    pub name: &'static str,
+    pub probe: Probe,
+}
+
+/// 单项检查的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub passed: bool,
+    /// 实际读到的字节，方便定位失败原因
+    pub actual: Vec<u8>,
+}
+
+/// 一次测试运行的报告
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestReport {
+    /// 是否在帧数上限内进入了停机循环
+    pub halted: bool,
+    /// 实际运行的帧数
+    pub frames: usize,
+    /// 因指令出错而提前结束
+    pub fault: Option<VmError>,
+    pub checks: Vec<CheckResult>,
+}
+
+impl TestReport {
+    /// 停机且所有检查都通过
+    pub fn passed(&self) -> bool {
+        self.halted && self.fault.is_none() && self.checks.iter().all(|check| check.passed)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &CheckResult> {
+        self.checks.iter().filter(|check| !check.passed)
+    }
+}
+
+pub struct TestRunner {
+    pub vm: Chip8,
+    /// 最多运行的帧数，超过后视为没有停机
+    pub max_frames: usize,
+}
+
+impl TestRunner {
+    /// 在默认的 CHIP-8 平台上按 `quirks` 运行
+    pub fn new(quirks: Quirks) -> Self {
+        let mut vm = Chip8::create();
+        vm.quirks = quirks;
+        vm.seed_rng(0);
+        TestRunner {
+            vm,
+            max_frames: 60 * 60,
+        }
+    }
+
+    /// 在对应的模拟平台上运行，指令集、内存、quirks 与栈深度都取自该平台
+    pub fn for_platform(platform: TestPlatform) -> Self {
+        let mut vm = Chip8::with_platform(platform.platform());
+        vm.seed_rng(0);
+        TestRunner {
+            vm,
+            max_frames: 60 * 60,
+        }
+    }
+
+    /// 加载 ROM，`platform` 不为空时预先写入 0x1FF，跳过 ROM 的选择菜单
+    pub fn load(&mut self, rom: &[u8], platform: Option<TestPlatform>) -> Result<(), String> {
+        let quirks = self.vm.quirks;
+        self.vm.reset();
+        self.vm.quirks = quirks;
+        self.vm.load_rom(rom)?;
+        if let Some(platform) = platform {
+            self.vm.memory[PLATFORM_SELECT as usize] = platform as u8;
+        }
+        Ok(())
+    }
+
+    /// 逐帧运行直到停机或达到帧数上限，然后读取各检查项
+    pub fn run(&mut self, checks: &[Check]) -> TestReport {
+        let mut runner = Runner::new(NoDisplay, NoAudio, NoInput, FixedClock);
+        let outcome = runner.run(&mut self.vm, self.max_frames as u64);
+
+        TestReport {
+            halted: outcome == StepOutcome::Halted,
+            frames: runner.frame() as usize,
+            fault: match outcome {
+                StepOutcome::Fault(err) => Some(err),
+                _ => None,
+            },
+            checks: checks.iter().map(|check| self.probe(check)).collect(),
+        }
+    }
+
+    fn probe(&self, check: &Check) -> CheckResult {
+        let (passed, actual) = match check.probe {
+            Probe::Memory { address, pass } => {
+                let byte = self.vm.memory[address as usize];
+                (byte == pass, vec![byte])
+            }
+            Probe::Screen { x, y, pass } => {
+                let rows = (0..pass.len() as u8)
+                    .map(|row| self.screen_byte(x, y.wrapping_add(row)))
+                    .collect::<Vec<u8>>();
+                (rows == pass, rows)
+            }
+        };
+
+        CheckResult {
+            name: check.name,
+            passed,
+            actual,
+        }
+    }
+
+    /// 屏幕 (x, y) 起向右 8 个像素组成的字节，超出屏幕的部分为 0
+    fn screen_byte(&self, x: u8, y: u8) -> u8 {
+        let screen = &self.vm.screen;
+        (0..8u8).fold(0, |byte, bit| {
+            let px = x as u16 + bit as u16;
+            let on = px < screen.columns as u16 && y < screen.rows && screen.get_pixel(px as u8, y);
+            byte << 1 | on as u8
+        })
+    }
+}
@@ -0,0 +1,574 @@
+//! 执行轨迹：每执行一步记录一次 CPU 状态，用来逐条对比两个实现（`js/src/CPU.ts` 与本 crate）的行为。
+//!
+//! 文本格式每行一条记录，step 为十进制，其余均为大写十六进制：
+//!
+//! ```text
+//! <step> <PC> <opcode> V:<V0..VF> I:<I> DT:<DT> ST:<ST>
+//! 00000000 0200 00E0 V:00000000000000000000000000000000 I:0000 DT:00 ST:00
+//! ```
+//!
+//! PC 与 opcode 是这一步开始时的值，寄存器与计时器是这一步执行完之后的值。
+//!
+//! 另一部分是挂在 VM 上的 [`Tracer`]：每执行一条指令产生一条 [`TraceEntry`]，包含反汇编、
+//! 寄存器的变化和内存写入，按 [`TraceFilter`] 过滤后交给 [`TraceSink`] 输出。
+
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+
+use crate::disasm::{self, OpcodeClass};
+use crate::vm::{Chip8, Instruction};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceRecord {
+    pub step: u64,
+    pub pc: u16,
+    pub ir_code: u16,
+    pub v: [u8; 16],
+    pub i: u32,
+    pub d_timer: u8,
+    pub s_timer: u8,
+}
+
+impl TraceRecord {
+    /// 记录 `pc` 处的 `ir_code` 执行完之后 VM 的状态
+    pub fn capture(vm: &Chip8, step: u64, pc: u16, ir_code: u16) -> Self {
+        let mut v = [0; 16];
+        v.copy_from_slice(&vm.r_v);
+        TraceRecord {
+            step,
+            pc,
+            ir_code,
+            v,
+            i: vm.r_i,
+            d_timer: vm.d_timer,
+            s_timer: vm.s_timer,
+        }
+    }
+
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let err = || format!("invalid trace record: {}", line);
+        let hex = |field: &str, prefix: &str| {
+            field
+                .strip_prefix(prefix)
+                .and_then(|value| u16::from_str_radix(value, 16).ok())
+                .ok_or_else(err)
+        };
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 7 {
+            return Err(err());
+        }
+
+        let regs = fields[3].strip_prefix("V:").ok_or_else(err)?;
+        if regs.len() != 32 {
+            return Err(err());
+        }
+        let mut v = [0; 16];
+        for (i, reg) in v.iter_mut().enumerate() {
+            *reg = u8::from_str_radix(&regs[i * 2..i * 2 + 2], 16).map_err(|_| err())?;
+        }
+
+        Ok(TraceRecord {
+            step: fields[0].parse().map_err(|_| err())?,
+            pc: hex(fields[1], "")?,
+            ir_code: hex(fields[2], "")?,
+            v,
+            i: fields[4]
+                .strip_prefix("I:")
+                .and_then(|value| u32::from_str_radix(value, 16).ok())
+                .ok_or_else(err)?,
+            d_timer: hex(fields[5], "DT:")? as u8,
+            s_timer: hex(fields[6], "ST:")? as u8,
+        })
+    }
+
+    /// 与另一条记录不同的字段名
+    pub fn diff(&self, other: &TraceRecord) -> Vec<String> {
+        let mut fields = vec![];
+        if self.step != other.step {
+            fields.push("step".to_string());
+        }
+        if self.pc != other.pc {
+            fields.push("PC".to_string());
+        }
+        if self.ir_code != other.ir_code {
+            fields.push("opcode".to_string());
+        }
+        for (i, (a, b)) in self.v.iter().zip(other.v.iter()).enumerate() {
+            if a != b {
+                fields.push(format!("V{:X}", i));
+            }
+        }
+        if self.i != other.i {
+            fields.push("I".to_string());
+        }
+        if self.d_timer != other.d_timer {
+            fields.push("DT".to_string());
+        }
+        if self.s_timer != other.s_timer {
+            fields.push("ST".to_string());
+        }
+        fields
+    }
+}
+
+impl fmt::Display for TraceRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:08} {:04X} {:04X} V:",
+            self.step, self.pc, self.ir_code
+        )?;
+        for reg in self.v.iter() {
+            write!(f, "{:02X}", reg)?;
+        }
+        write!(
+            f,
+            " I:{:04X} DT:{:02X} ST:{:02X}",
+            self.i, self.d_timer, self.s_timer
+        )
+    }
+}
+
+/// 在某一帧开始时按下或松开一个键
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputEvent {
+    pub frame: u64,
+    /// 键盘上的按键，与 `Keyboard::key_down` 的映射一致
+    pub key: char,
+    pub down: bool,
+}
+
+impl InputEvent {
+    /// 解析 `<frame> <key> down|up`，如 `30 w down`
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let err = || format!("invalid input event: {}", line);
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 3 {
+            return Err(err());
+        }
+
+        let mut key = fields[1].chars();
+        Ok(InputEvent {
+            frame: fields[0].parse().map_err(|_| err())?,
+            key: match (key.next(), key.next()) {
+                (Some(key), None) => key,
+                _ => return Err(err()),
+            },
+            down: match fields[2] {
+                "down" => true,
+                "up" => false,
+                _ => return Err(err()),
+            },
+        })
+    }
+}
+
+pub struct TraceOptions {
+    pub steps: u64,
+    /// 每帧执行的指令数，每帧结束时调用一次 `ticker`
+    pub cycles_per_frame: u64,
+    pub inputs: Vec<InputEvent>,
+}
+
+impl TraceOptions {
+    pub fn new(steps: u64) -> Self {
+        TraceOptions {
+            steps,
+            cycles_per_frame: 8,
+            inputs: vec![],
+        }
+    }
+}
+
+/// 按 `options` 运行 VM，每一步产生一条记录。
+///
+/// TypeScript 版本的 `runTrace` 用同样的节奏推进帧和输入，改动这里时两边要保持一致。
+pub fn run(vm: &mut Chip8, options: &TraceOptions, mut emit: impl FnMut(&TraceRecord)) {
+    for step in 0..options.steps {
+        if step % options.cycles_per_frame == 0 {
+            let frame = step / options.cycles_per_frame;
+            for event in options.inputs.iter().filter(|event| event.frame == frame) {
+                if event.down {
+                    vm.keyboard.key_down(event.key);
+                } else {
+                    vm.keyboard.key_up(event.key);
+                }
+            }
+        }
+
+        let pc = vm.r_pc;
+        let ir_code = vm.read_opcode(pc);
+        vm.cycle();
+        if (step + 1) % options.cycles_per_frame == 0 {
+            vm.ticker();
+        }
+
+        emit(&TraceRecord::capture(vm, step, pc, ir_code));
+    }
+}
+
+/// 两份轨迹第一次出现差异的位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// 记录的下标
+    pub index: usize,
+    /// 某一份提前结束时为 None
+    pub left: Option<TraceRecord>,
+    pub right: Option<TraceRecord>,
+    pub fields: Vec<String>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "first divergence at record {}", self.index)?;
+        match &self.left {
+            Some(record) => writeln!(f, "  left:  {}", record)?,
+            None => writeln!(f, "  left:  <end of trace>")?,
+        }
+        match &self.right {
+            Some(record) => writeln!(f, "  right: {}", record)?,
+            None => writeln!(f, "  right: <end of trace>")?,
+        }
+        write!(f, "  fields: {}", self.fields.join(", "))
+    }
+}
+
+pub fn first_divergence(left: &[TraceRecord], right: &[TraceRecord]) -> Option<Divergence> {
+    for index in 0..left.len().max(right.len()) {
+        let (a, b) = (left.get(index), right.get(index));
+        let fields = match (a, b) {
+            (Some(a), Some(b)) => a.diff(b),
+            _ => vec!["length".to_string()],
+        };
+        if !fields.is_empty() {
+            return Some(Divergence {
+                index,
+                left: a.cloned(),
+                right: b.cloned(),
+                fields,
+            });
+        }
+    }
+    None
+}
+
+/// 一个寄存器在一条指令前后的值
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterDelta {
+    pub reg: u8,
+    pub old: u8,
+    pub new: u8,
+}
+
+/// 一条指令写入内存的一个字节
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryWrite {
+    pub addr: u32,
+    pub value: u8,
+}
+
+/// [`Tracer`] 为每条执行的指令产生的记录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    /// 从挂上 tracer 起执行的第几条指令，被过滤掉的指令也计数
+    pub cycle: u64,
+    pub pc: u16,
+    pub ir_code: u16,
+    pub disasm: String,
+    /// 值发生变化的 V 寄存器
+    pub deltas: Vec<RegisterDelta>,
+    /// 执行后的 I
+    pub i: u32,
+    pub writes: Vec<MemoryWrite>,
+}
+
+/// 只记录满足条件的指令
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TraceFilter {
+    /// 指令地址范围，None 表示不限
+    pub pc_range: Option<RangeInclusive<u16>>,
+    /// 指令类别，为空表示不限
+    pub classes: Vec<OpcodeClass>,
+}
+
+impl TraceFilter {
+    pub fn matches(&self, pc: u16, ir: &Instruction) -> bool {
+        self.pc_range
+            .as_ref()
+            .is_none_or(|range| range.contains(&pc))
+            && (self.classes.is_empty() || self.classes.contains(&OpcodeClass::of(ir)))
+    }
+}
+
+/// 执行记录的输出目标
+pub trait TraceSink {
+    fn write(&mut self, entry: &TraceEntry) -> io::Result<()>;
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// 收集到内存中，便于测试和前端读取
+impl TraceSink for Rc<RefCell<Vec<TraceEntry>>> {
+    fn write(&mut self, entry: &TraceEntry) -> io::Result<()> {
+        self.borrow_mut().push(entry.clone());
+        Ok(())
+    }
+}
+
+/// 每条一行的文本：
+///
+/// ```text
+/// 00000012 0204 600C LD V0, 0x0C       V0:00->0C I:022A
+/// 00000031 0226 F333 LD B, V3          I:0300 [0300]=01 [0301]=02 [0302]=08
+/// ```
+pub struct TextSink<W: Write> {
+    out: W,
+}
+
+impl<W: Write> TextSink<W> {
+    pub fn new(out: W) -> Self {
+        TextSink { out }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> TraceSink for TextSink<W> {
+    fn write(&mut self, entry: &TraceEntry) -> io::Result<()> {
+        write!(
+            self.out,
+            "{:08} {:04X} {:04X} {:<18}",
+            entry.cycle, entry.pc, entry.ir_code, entry.disasm
+        )?;
+        for delta in entry.deltas.iter() {
+            write!(
+                self.out,
+                "V{:X}:{:02X}->{:02X} ",
+                delta.reg, delta.old, delta.new
+            )?;
+        }
+        write!(self.out, "I:{:04X}", entry.i)?;
+        for write in entry.writes.iter() {
+            write!(self.out, " [{:04X}]={:02X}", write.addr, write.value)?;
+        }
+        writeln!(self.out)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// 紧凑的二进制格式，所有整数均为小端序，反汇编可由 opcode 还原故不写入：
+///
+/// ```text
+/// cycle: u64, pc: u16, opcode: u16, I: u32,
+/// 变化的寄存器个数: u8, 每个 { 寄存器编号: u8, 新值: u8 },
+/// 内存写入个数: u8, 每个 { 地址: u32, 值: u8 }
+/// ```
+pub struct BinarySink<W: Write> {
+    out: W,
+}
+
+impl<W: Write> BinarySink<W> {
+    pub fn new(out: W) -> Self {
+        BinarySink { out }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> TraceSink for BinarySink<W> {
+    fn write(&mut self, entry: &TraceEntry) -> io::Result<()> {
+        let mut buf = Vec::with_capacity(18 + entry.deltas.len() * 2 + entry.writes.len() * 5);
+        buf.extend_from_slice(&entry.cycle.to_le_bytes());
+        buf.extend_from_slice(&entry.pc.to_le_bytes());
+        buf.extend_from_slice(&entry.ir_code.to_le_bytes());
+        buf.extend_from_slice(&entry.i.to_le_bytes());
+        buf.push(entry.deltas.len() as u8);
+        for delta in entry.deltas.iter() {
+            buf.extend_from_slice(&[delta.reg, delta.new]);
+        }
+        buf.push(entry.writes.len() as u8);
+        for write in entry.writes.iter() {
+            buf.extend_from_slice(&write.addr.to_le_bytes());
+            buf.push(write.value);
+        }
+        self.out.write_all(&buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// 每行一个 JSON 对象：
+///
+/// ```text
+/// {"cycle":12,"pc":516,"opcode":24588,"asm":"LD V0, 0x0C","regs":[{"reg":0,"old":0,"new":12}],"i":554,"writes":[]}
+/// ```
+pub struct JsonLinesSink<W: Write> {
+    out: W,
+}
+
+impl<W: Write> JsonLinesSink<W> {
+    pub fn new(out: W) -> Self {
+        JsonLinesSink { out }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> TraceSink for JsonLinesSink<W> {
+    fn write(&mut self, entry: &TraceEntry) -> io::Result<()> {
+        let regs = entry
+            .deltas
+            .iter()
+            .map(|d| format!(r#"{{"reg":{},"old":{},"new":{}}}"#, d.reg, d.old, d.new))
+            .collect::<Vec<_>>()
+            .join(",");
+        let writes = entry
+            .writes
+            .iter()
+            .map(|w| format!(r#"{{"addr":{},"value":{}}}"#, w.addr, w.value))
+            .collect::<Vec<_>>()
+            .join(",");
+        writeln!(
+            self.out,
+            r#"{{"cycle":{},"pc":{},"opcode":{},"asm":"{}","regs":[{}],"i":{},"writes":[{}]}}"#,
+            entry.cycle,
+            entry.pc,
+            entry.ir_code,
+            json_escape(&entry.disasm),
+            regs,
+            entry.i,
+            writes
+        )
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// 转义 JSON 字符串中的引号、反斜杠与控制字符
+fn json_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// 挂在 VM 上（[`Chip8::set_tracer`]），在每条指令执行前后采样
+pub struct Tracer {
+    sink: Box<dyn TraceSink>,
+    pub filter: TraceFilter,
+    cycle: u64,
+    /// 指令执行前的 V 寄存器
+    before: [u8; 16],
+    writes: Vec<MemoryWrite>,
+    /// sink 第一次出错后不再写入，错误留给 `finish` 返回
+    error: Option<io::Error>,
+}
+
+impl fmt::Debug for Tracer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tracer")
+            .field("filter", &self.filter)
+            .field("cycle", &self.cycle)
+            .finish()
+    }
+}
+
+impl Tracer {
+    pub fn new(sink: impl TraceSink + 'static) -> Self {
+        Tracer {
+            sink: Box::new(sink),
+            filter: TraceFilter::default(),
+            cycle: 0,
+            before: [0; 16],
+            writes: vec![],
+            error: None,
+        }
+    }
+
+    pub fn with_filter(mut self, filter: TraceFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// 已执行的指令数
+    pub fn cycles(&self) -> u64 {
+        self.cycle
+    }
+
+    /// 刷新 sink，返回写入过程中遇到的第一个错误
+    pub fn finish(mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => self.sink.flush(),
+        }
+    }
+
+    pub(crate) fn begin(&mut self, r_v: &[u8]) {
+        self.before.copy_from_slice(r_v);
+        self.writes.clear();
+    }
+
+    pub(crate) fn record_write(&mut self, addr: u32, value: u8) {
+        self.writes.push(MemoryWrite { addr, value });
+    }
+
+    pub(crate) fn end(&mut self, vm: &Chip8, pc: u16, ir: &Instruction) {
+        let cycle = self.cycle;
+        self.cycle += 1;
+        if self.error.is_some() || !self.filter.matches(pc, ir) {
+            return;
+        }
+
+        let entry = TraceEntry {
+            cycle,
+            pc,
+            ir_code: ir.ir_code,
+            disasm: disasm::disassemble(ir),
+            deltas: self
+                .before
+                .iter()
+                .zip(vm.r_v.iter())
+                .enumerate()
+                .filter(|(_, (old, new))| old != new)
+                .map(|(reg, (&old, &new))| RegisterDelta {
+                    reg: reg as u8,
+                    old,
+                    new,
+                })
+                .collect(),
+            i: vm.r_i,
+            writes: std::mem::take(&mut self.writes),
+        };
+        if let Err(err) = self.sink.write(&entry) {
+            self.error = Some(err);
+        }
+    }
+}
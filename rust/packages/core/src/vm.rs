@@ -1,64 +1,71 @@
-use rand::{prelude::ThreadRng, thread_rng};
-
-use crate::hardware::{Keyboard, Screen};
-
-const START_ADDRESS: u16 = 0x200;
-const FONT_BASE: u16 = 0x50;
-const LARGE_FONT_BASE: u16 = FONT_BASE + 0x50;
-
-static FONTS: [u8; 80] = [
-    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
-    0x20, 0x60, 0x20, 0x20, 0x70, // 1
-    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
-    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
-    0x90, 0x90, 0xF0, 0x10, 0x10, // 4
-    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
-    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
-    0xF0, 0x10, 0x20, 0x40, 0x40, // 7
-    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
-    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
-    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
-    0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
-    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
-    0xE0, 0x90, 0x90, 0x90, 0xE0, // D
-    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
-    0xF0, 0x80, 0xF0, 0x80, 0x80, // F
-];
-
-static LARGE_FONTS: [u8; 100] = [
-    0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // 0
-    0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // 1
-    0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // 2
-    0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // 3
-    0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // 4
-    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // 5
-    0x3E, 0x7C, 0xC0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // 6
-    0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // 7
-    0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // 8
-    0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C, // 9
-];
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use crate::config::MachineConfig;
+use crate::hardware::{Keyboard, Memory, Screen, CODE_SPACE_SIZE};
+use crate::hash;
+use crate::megachip::{BlendMode, MegaChip, MEGA_HEIGHT, MEGA_WIDTH};
+use crate::platform::Platform;
+use crate::profile::Profiler;
+use crate::quirks::Quirks;
+use crate::rng::{self, RandomSource, Xorshift32};
+use crate::romdb::{self, RomInfo};
+use crate::rpl::RplStorage;
+use crate::selfmod::{CodeWrites, ModifiedCode};
+#[cfg(feature = "std")]
+use crate::trace::Tracer;
+
+/// 向后跳转不超过这么多字节时，才检查是否为空转循环
+const IDLE_LOOP_WINDOW: u16 = 0x10;
+/// COSMAC VIP 解释器的栈深度
+pub const VIP_STACK_DEPTH: u8 = 12;
+/// SUPER-CHIP 的栈深度，也是默认值
+pub const SCHIP_STACK_DEPTH: u8 = 16;
+/// 栈深度的上限，等于 VIP 布局下内存中的栈能放下的项数
+pub const MAX_STACK_DEPTH: u8 = VIP_STACK_SLOTS;
+/// VIP 布局下栈所在的内存区域，栈从高地址向低地址增长，每项两个字节，最多 24 项
+pub const VIP_STACK: Range<u16> = 0xEA0..0xED0;
+const VIP_STACK_SLOTS: u8 = ((VIP_STACK.end - VIP_STACK.start) / 2) as u8;
+/// VIP 布局下 64x32 显示缓冲区的起始地址，每行 8 个字节，高位在左
+pub const VIP_DISPLAY: u16 = 0xF00;
+/// 写入日志最多积累这么多项，超出后丢弃日志并让所有已编译的代码失效
+const WRITE_LOG_LIMIT: usize = 0x1000;
+/// 单色画面的颜色，与网页前端一致
+pub const MONO_ON: [u8; 3] = [0x66, 0xcc, 0xff];
+pub const MONO_OFF: [u8; 3] = [0x00, 0x00, 0x00];
+/// XO-CHIP 音高寄存器的初始值
+const DEFAULT_PITCH: u8 = 64;
 
 #[derive(Debug)]
 pub struct Chip8 {
     // CPU 频率
     pub rate: u16,
-    /// 4KB size of the RAM
-    pub memory: Vec<u8>,
-    /// 栈模拟
-    pub stack: Vec<u16>,
+    /// RAM，大小由 `MachineConfig::memory_size` 决定，默认 4KB
+    pub memory: Memory,
+    /// 栈模拟，只使用前 `stack_depth()` 项；VIP 布局下栈在内存中，不使用这里
+    pub stack: [u16; MAX_STACK_DEPTH as usize],
+    /// 栈指针，即栈中的项数
+    pub r_sp: u8,
+    /// 栈溢出、下溢时的处理方式
+    pub stack_policy: StackPolicy,
     /// 16 个 8bit 的通用寄存器
-    pub r_v: Vec<u8>,
-    /// 计数器，实际上最大值是 12bit，默认值 0x200
+    pub r_v: [u8; 16],
+    /// 计数器，初始值为 `MachineConfig::entry_point`，默认 0x200
     pub r_pc: u16,
-    ///
-    pub r_rpl: Vec<u8>,
-    /// 地址索引寄存器
-    pub r_i: u16,
+    /// HP-48 RPL 用户标志
+    pub r_rpl: [u8; 16],
+    /// 地址索引寄存器，16 位；MegaChip 的 01NN NNNN 可以载入 24 位地址
+    pub r_i: u32,
     /// delay 计时器
     pub d_timer: u8,
     /// sound 计时器
     pub s_timer: u8,
-    ///
+    /// 是否运行中
     pub running: bool,
     /// schip8 模式
     pub high_res: bool,
@@ -66,19 +73,102 @@ pub struct Chip8 {
 
     pub screen: Screen,
     pub keyboard: Keyboard,
+    pub quirks: Quirks,
+    /// 栈与显示缓冲区放在哪里，修改后需要 `reset`
+    pub layout: MemoryLayout,
+
+    /// 栈深度，由 `set_stack_depth` 设置
+    stack_depth: u8,
+    /// 可用的指令、屏幕大小与速度，由 `set_platform` 设置
+    platform: Platform,
+    /// XO-CHIP FN01 选中的位平面。只实现了第一个平面，没有选中它时 DXYN 与 00E0 不起作用
+    planes: u8,
+    /// XO-CHIP F002 载入的 1 bit 音频样本
+    audio_pattern: [u8; 16],
+    /// XO-CHIP FX3A 设置的音高，64 对应 4000Hz
+    pitch: u8,
+    /// CHIP-8X FXF8 / CHIP-8E FX03 最后输出到 I/O 端口的值，VP-595 用它作为音调
+    io_output: u8,
+    /// CHIP-8X FXFB / CHIP-8E FXE3、FXE7 从 I/O 端口读到的值，由 `set_io_input` 设置
+    io_input: u8,
+    /// MegaChip 平台的彩色画面与采样播放，只在该平台上分配
+    megachip: Option<Box<MegaChip>>,
+    /// FX0A 等待按键的状态
+    key_wait: Option<KeyWait>,
+    /// 开启 display_wait 时，绘制后等待 vblank
+    vblank_wait: bool,
+    /// CHIP-8E 0151/FX4F 等待 delay 计时器归零
+    timer_wait: bool,
+    config: MachineConfig,
+    /// 执行中的指令产生的错误，由 `cycle` 返回
+    fault: Option<VmError>,
+    /// 001N 退出时的返回值
+    exit_code: Option<u8>,
+    rng: Box<dyn RandomSource>,
+    #[cfg(feature = "std")]
+    tracer: Option<Tracer>,
+    profiler: Option<Box<Profiler>>,
+    rpl_storage: Option<Box<dyn RplStorage>>,
+    /// 已加载 ROM 的 SHA-1
+    rom_sha1: Option<String>,
+    /// 已加载 ROM 在数据库中的信息
+    rom_info: Option<&'static RomInfo>,
+    /// 加载数据库中已知的 ROM 时自动应用其设置
+    auto_configure: bool,
+    /// 数据库指定的速度，切换分辨率与 `reset` 时代替平台的默认速度
+    rate_override: Option<u16>,
+    /// `auto_configure` 第一次按数据库配置 ROM 之前的平台与内存配置，加载未收录的 ROM 时恢复
+    before_rom_info: Option<(Platform, MachineConfig)>,
+    /// 正在执行的指令地址，写内存时记录写入者
+    exec_pc: u16,
+    code_writes: CodeWrites,
+    break_on_modified_code: bool,
+    /// 上一次 `cycle` 在这里中断，下一次直接执行
+    resume_at: Option<u16>,
+    /// 按地址缓存的译码结果，为空时不使用缓存
+    decode_cache: Option<Vec<Option<Decoded>>>,
+    /// 内存被整体替换（reset、load_rom 等）时递增，编译执行的后端据此丢弃全部代码
+    code_generation: u64,
+    /// 指令写入过的地址，后端第一次取走后才开始记录
+    write_log: Option<Vec<u16>>,
+}
 
-    rng: ThreadRng,
+/// 栈与显示缓冲区的存放方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MemoryLayout {
+    /// 栈与屏幕都在 `memory` 之外
+    #[default]
+    Flat,
+    /// 按 COSMAC VIP 的布局放在内存中：栈在 [`VIP_STACK`]，显示缓冲区在 [`VIP_DISPLAY`]..0x1000。
+    ///
+    /// 2NNN/00EE、DXYN 与 00E0 直接读写这两块内存，`screen` 只是显示缓冲区的一个视图；
+    /// 程序可以直接读写它们，绕过指令直接改写 `memory` 后调用 `sync_screen` 刷新视图。
+    /// 这一布局只有 64x32 的显示缓冲区，高分辨率下超出的部分只存在于 `screen` 中。
+    Vip,
+}
+
+/// FX0A 执行后，VM 暂停取指，直到观察到一次完整的按键
+#[derive(Debug, Clone, Copy)]
+struct KeyWait {
+    /// 结果写入的寄存器编号
+    x: u8,
+    /// 上一次轮询时按下的键，只有新的按下才算数
+    held: u16,
+    /// 已按下、等待松开的键
+    pressed: Option<u8>,
 }
 
 impl Chip8 {
     fn new() -> Self {
         Chip8 {
             rate: 480,
-            memory: vec![0; 4 * 1024],
-            stack: vec![0; 16],
-            r_v: vec![0; 16],
-            r_pc: START_ADDRESS,
-            r_rpl: vec![0; 16],
+            memory: Memory::new(MachineConfig::default().memory_size),
+            stack: [0; MAX_STACK_DEPTH as usize],
+            r_sp: 0,
+            stack_policy: StackPolicy::Error,
+            r_v: [0; 16],
+            r_pc: MachineConfig::default().entry_point,
+            r_rpl: [0; 16],
             r_i: 0,
             d_timer: 0,
             s_timer: 0,
@@ -88,8 +178,40 @@ impl Chip8 {
 
             screen: Screen::new(),
             keyboard: Keyboard::new(),
-
-            rng: thread_rng(),
+            quirks: Quirks::default(),
+            layout: MemoryLayout::Flat,
+
+            stack_depth: SCHIP_STACK_DEPTH,
+            platform: Platform::Chip8,
+            planes: 1,
+            audio_pattern: [0; 16],
+            pitch: DEFAULT_PITCH,
+            io_output: 0,
+            io_input: 0,
+            megachip: None,
+            key_wait: None,
+            vblank_wait: false,
+            timer_wait: false,
+            config: MachineConfig::default(),
+            fault: None,
+            exit_code: None,
+            rng: Box::new(Xorshift32::new(rng::default_seed())),
+            #[cfg(feature = "std")]
+            tracer: None,
+            profiler: None,
+            rpl_storage: None,
+            rom_sha1: None,
+            rom_info: None,
+            auto_configure: false,
+            rate_override: None,
+            before_rom_info: None,
+            exec_pc: 0,
+            code_writes: CodeWrites::new(MachineConfig::default().memory_size.min(CODE_SPACE_SIZE)),
+            break_on_modified_code: false,
+            resume_at: None,
+            decode_cache: None,
+            code_generation: 0,
+            write_log: None,
         }
     }
 
@@ -100,37 +222,180 @@ impl Chip8 {
         c8
     }
 
+    /// 按 `config` 创建，内存大小与加载地址不合法时返回错误
+    pub fn with_config(config: MachineConfig) -> Result<Self, String> {
+        let mut c8 = Chip8::new();
+        c8.configure(config)?;
+        c8.change_mode(false);
+        Ok(c8)
+    }
+
+    /// 按平台的内存布局、字体、quirks、栈深度与速度创建
+    pub fn with_platform(platform: Platform) -> Self {
+        let mut c8 = Chip8::new();
+        c8.set_platform(platform);
+        c8
+    }
+
+    /// 更换内存配置并 `reset`
+    pub fn configure(&mut self, config: MachineConfig) -> Result<(), String> {
+        config.validate()?;
+        self.apply_config(config);
+        Ok(())
+    }
+
+    fn apply_config(&mut self, config: MachineConfig) {
+        self.config = config;
+        self.before_rom_info = None;
+        self.memory = Memory::new(config.memory_size);
+        self.code_writes = CodeWrites::new(config.memory_size.min(CODE_SPACE_SIZE));
+        self.reset();
+    }
+
+    pub fn platform(&self) -> Platform {
+        self.platform
+    }
+
+    /// 切换平台：换成平台的内存配置、quirks 与栈深度并 `reset`。之后不属于该平台的指令执行时报
+    /// [`VmError::UnknownOpcode`]
+    pub fn set_platform(&mut self, platform: Platform) {
+        self.platform = platform;
+        self.quirks = platform.quirks();
+        self.rate_override = None;
+        self.set_stack_depth(platform.stack_depth());
+        self.megachip = (platform == Platform::MegaChip).then(|| Box::new(MegaChip::new()));
+        self.apply_config(platform.config());
+    }
+
+    pub fn config(&self) -> &MachineConfig {
+        &self.config
+    }
+
+    /// 把 ROM 写入 `MachineConfig::load_address`，ROM 超出可用空间时不写入任何内容并返回错误。
+    ///
+    /// 开启 `auto_configure` 时，数据库中的 ROM 先切换到它的平台再写入；未收录的 ROM 撤销上一个
+    /// ROM 按数据库做的设置，回到原来的平台、内存配置与平台默认的 quirks、速度和按键映射
     pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), String> {
-        for (i, byte) in rom.iter().enumerate() {
-            let m_idx = i + START_ADDRESS as usize;
-            if m_idx < self.memory.len() {
-                self.memory[m_idx] = *byte;
-            } else {
-                return Err("ROM is too large to load into memory".to_string());
+        let key = hash::sha1_hex(rom);
+        let info = romdb::lookup(&key);
+        if self.auto_configure {
+            match info {
+                Some(info) => {
+                    let before = self.before_rom_info.unwrap_or((self.platform, self.config));
+                    if info.platform != self.platform {
+                        self.set_platform(info.platform);
+                    }
+                    self.before_rom_info = Some(before);
+                }
+                None => {
+                    if let Some((platform, config)) = self.before_rom_info.take() {
+                        self.set_platform(platform);
+                        self.apply_config(config);
+                    }
+                }
             }
         }
+
+        let start = self.config.load_address as usize;
+        let end = match self.layout {
+            MemoryLayout::Flat => self.memory.len(),
+            MemoryLayout::Vip => VIP_STACK.start as usize,
+        };
+        if start + rom.len() > end {
+            return Err(format!(
+                "ROM is {} bytes, but only {} bytes are available at 0x{:03X}{}",
+                rom.len(),
+                end.saturating_sub(start),
+                start,
+                match self.layout {
+                    MemoryLayout::Flat => "",
+                    MemoryLayout::Vip => " below the VIP stack",
+                }
+            ));
+        }
+        self.memory[start..start + rom.len()].copy_from_slice(rom);
+        self.code_writes.clear();
+        self.invalidate_decode_cache();
+
+        if let Some(flags) = self.rpl_storage.as_mut().and_then(|s| s.load(&key)) {
+            let len = flags.len().min(self.r_rpl.len());
+            self.r_rpl[..len].copy_from_slice(&flags[..len]);
+        }
+        self.rom_info = info;
+        self.rom_sha1 = Some(key);
+        if self.auto_configure {
+            self.apply_rom_info();
+        }
         Ok(())
     }
 
+    /// 已加载 ROM 的 SHA-1（小写十六进制）
+    pub fn rom_sha1(&self) -> Option<&str> {
+        self.rom_sha1.as_deref()
+    }
+
+    /// 已加载 ROM 在内置数据库中的标题、平台等信息，未收录时为空
+    pub fn rom_info(&self) -> Option<&'static RomInfo> {
+        self.rom_info
+    }
+
+    /// 为 true 时，`load_rom` 加载数据库中已知的 ROM 后自动调用 `apply_rom_info`
+    pub fn set_auto_configure(&mut self, enabled: bool) {
+        self.auto_configure = enabled;
+    }
+
+    /// 按数据库应用已加载 ROM 推荐的 quirks、速度和按键映射，ROM 未收录时不做任何修改。
+    /// 切换平台会清空内存，只能在写入 ROM 之前进行，由开启 `auto_configure` 的 `load_rom` 完成
+    pub fn apply_rom_info(&mut self) -> Option<&'static RomInfo> {
+        let info = self.rom_info?;
+        self.quirks = info.quirks();
+        self.rate = info.tick_rate() * 60;
+        self.rate_override = Some(self.rate);
+        self.keyboard.set_keymap(info.keymap());
+        Some(info)
+    }
+
+    /// 持久化 RPL 标志，在 `load_rom` 之前设置
+    pub fn set_rpl_storage(&mut self, storage: impl RplStorage + 'static) {
+        self.rpl_storage = Some(Box::new(storage));
+    }
+
+    /// FX75 写入后保存 RPL 标志。保存失败不影响执行
+    fn flush_rpl(&mut self) {
+        if let (Some(storage), Some(key)) = (self.rpl_storage.as_mut(), self.rom_sha1.as_ref()) {
+            let _ = storage.store(key, &self.r_rpl);
+        }
+    }
+
     pub fn reset(&mut self) {
         // reset memory
         self.memory.fill(0);
 
         // load fonts
-        for (i, byte) in FONTS.iter().enumerate() {
-            self.memory[i] = *byte;
-        }
-        for (i, byte) in LARGE_FONTS.iter().enumerate() {
-            self.memory[LARGE_FONT_BASE as usize + i] = *byte;
+        let font = self.config.font_set;
+        for (base, glyphs) in [
+            (self.config.small_font_base, font.small_glyphs()),
+            (self.config.large_font_base, font.large_glyphs()),
+        ] {
+            let base = base as usize;
+            self.memory[base..base + glyphs.len()].copy_from_slice(glyphs);
         }
 
         // reset stack
-        self.stack = vec![0; 16];
+        self.stack.fill(0);
+        self.r_sp = 0;
+        self.fault = None;
+        self.exit_code = None;
 
         // reset registers
         self.r_v.fill(0);
-        self.r_pc = START_ADDRESS;
+        self.r_pc = self.config.entry_point;
         self.r_rpl.fill(0);
+        self.rom_sha1 = None;
+        self.rom_info = None;
+        self.code_writes.clear();
+        self.resume_at = None;
+        self.invalidate_decode_cache();
         self.r_i = 0;
 
         // reset timers
@@ -140,106 +405,568 @@ impl Chip8 {
         self.running = true;
         self.draw_flag = false;
         self.high_res = false;
+        self.key_wait = None;
+        self.vblank_wait = false;
+        self.timer_wait = false;
+        self.planes = 1;
+        self.audio_pattern = [0; 16];
+        self.pitch = DEFAULT_PITCH;
+        self.io_output = 0;
 
         self.change_mode(false);
 
         self.screen.clear();
+        self.screen.colors.reset();
+        if let Some(megachip) = self.megachip.as_deref_mut() {
+            megachip.reset();
+        }
         self.keyboard.reset();
+        self.keyboard.set_keymap(None);
     }
 
     fn fetch(&mut self) -> u16 {
-        let high = self.memory[self.r_pc as usize] as u16;
-        let low = self.memory[self.r_pc as usize + 1] as u16;
-        self.r_pc += 2;
+        let ir_code = self.read_opcode(self.r_pc);
+        self.r_pc = self.r_pc.wrapping_add(2);
+
+        ir_code
+    }
+
+    /// 读取 addr 处的两个字节组成的指令，不移动 PC。地址超出内存时绕回开头
+    pub fn read_opcode(&self, addr: u16) -> u16 {
+        let high = self.memory[self.wrap_addr(addr) as usize] as u16;
+        let low = self.memory[self.wrap_addr(addr.wrapping_add(1)) as usize] as u16;
 
         (high << 8) | low
     }
 
+    /// 把地址折回内存范围内。PC 可以超出内存大小（如 4 KiB 内存的 0xFFF 之后），
+    /// 取指时超出的部分绕回内存开头
+    pub(crate) fn wrap_addr(&self, addr: u16) -> u16 {
+        (addr as usize % self.memory.len()) as u16
+    }
+
     fn execute(&mut self, ir: &Instruction) {
-        match ir.opcode {
-            0x0000 => match ir.y {
-                0x1 => ops::exit_n(self, ir),
-                0xc => ops::s8_scd_n(self, ir),
-                _ => match ir.kk {
-                    0x00e0 => ops::cls(self),
-                    0x00ee => ops::ret(self),
-                    0x00fb => ops::s8_scr(self),
-                    0x00fc => ops::s8_scl(self),
-                    0x00fd => ops::s8_exit(self),
-                    0x00fe => ops::s8_low(self),
-                    0x00ff => ops::s8_high(self),
-                    _ => ops::call_nnn(self),
-                },
-            },
-            0x1000 => ops::jmp_nnn(self, ir),
-            0x2000 => ops::call_nnn_2(self, ir),
-            0x3000 => ops::se_vx_nn(self, ir),
-            0x4000 => ops::sne_vx_nn(self, ir),
-            0x5000 => ops::se_vx_vy(self, ir),
-            0x6000 => ops::ld_vx_nn(self, ir),
-            0x7000 => ops::add_vx_nn(self, ir),
-            0x8000 => match ir.n {
-                0x0 => ops::ld_vx_vy(self, ir),
-                0x1 => ops::or_vx_vy(self, ir),
-                0x2 => ops::and_vx_vy(self, ir),
-                0x3 => ops::xor_vx_vy(self, ir),
-                0x4 => ops::add_vx_vy(self, ir),
-                0x5 => ops::sub_vx_vy(self, ir),
-                0x6 => ops::shr_vx_vy(self, ir),
-                0x7 => ops::subn_vy_vx(self, ir),
-                0xe => ops::shl_vx_vy(self, ir),
-                _ => {}
-            },
-            0x9000 => ops::sne_vx_vy(self, ir),
-            0xa000 => ops::ld_i_nnn(self, ir),
-            0xb000 => ops::jp_v0_nnn(self, ir),
-            0xc000 => ops::rnd_vx_nn(self, ir),
-            0xd000 => {
-                if self.high_res && ir.n == 0 {
-                    ops::s8_drw_vx_vy_0(self, ir);
-                } else {
-                    ops::drw_vx_vy_n(self, ir)
+        decode(ir, self.platform)(self, ir);
+    }
+
+    /// PC 能访问到的内存大小：内存的前 64 KiB，内存更小时为整个内存
+    pub fn code_space(&self) -> usize {
+        self.memory.len().min(CODE_SPACE_SIZE)
+    }
+
+    /// 开启后按地址缓存译码结果，执行时跳过取指和 `decode` 中的逐级匹配，结果与不开启时相同。
+    ///
+    /// 指令写入内存时对应的缓存会失效；绕过指令直接修改 `memory` 后需要调用 `invalidate_decode_cache`
+    pub fn set_decode_cache(&mut self, enabled: bool) {
+        self.decode_cache = enabled.then(|| vec![None; self.code_space()]);
+    }
+
+    /// 丢弃所有缓存的译码结果，以及后端（如 [`Recompiler`](crate::recompiler::Recompiler)）编译的代码
+    pub fn invalidate_decode_cache(&mut self) {
+        self.code_generation += 1;
+        if let Some(log) = self.write_log.as_mut() {
+            log.clear();
+        }
+        let len = self.code_space();
+        if let Some(cache) = self.decode_cache.as_mut() {
+            cache.clear();
+            cache.resize(len, None);
+        }
+    }
+
+    pub(crate) fn code_generation(&self) -> u64 {
+        self.code_generation
+    }
+
+    /// 把上次调用以来指令写入过的地址换到 `writes` 中，`writes` 原有的内容丢弃。第一次调用后才开始记录
+    pub(crate) fn swap_write_log(&mut self, writes: &mut Vec<u16>) {
+        writes.clear();
+        core::mem::swap(self.write_log.get_or_insert_with(Vec::new), writes);
+    }
+
+    /// 上次 `swap_write_log` 之后是否有指令写过内存
+    pub(crate) fn has_logged_writes(&self) -> bool {
+        self.write_log.as_ref().is_some_and(|log| !log.is_empty())
+    }
+
+    /// 下一条指令是否可以跳过 `cycle` 直接执行：没有在等待、没有挂载 tracer 和 profiler，也不需要中断
+    pub(crate) fn can_run_compiled(&self) -> bool {
+        self.running
+            && self.key_wait.is_none()
+            && !self.vblank_wait
+            && !self.timer_wait
+            && self.tracing_off()
+            && self.profiler.is_none()
+            && !self.break_on_modified_code
+    }
+
+    /// 从译码缓存中取出 `pc` 处的指令，缓存中没有时译码并存入；未开启缓存时为空
+    fn fetch_decoded(&mut self, pc: u16) -> Option<Decoded> {
+        let pc = self.wrap_addr(pc);
+        let cached = self.decode_cache.as_ref()?[pc as usize];
+        if cached.is_some() {
+            return cached;
+        }
+        let ir = Instruction::new(self.read_opcode(pc));
+        let decoded = Decoded {
+            op: decode(&ir, self.platform),
+            ir,
+        };
+        self.decode_cache.as_mut()?[pc as usize] = Some(decoded);
+        Some(decoded)
+    }
+
+    /// fetch -> decode -> execute
+    pub fn cycle(&mut self) -> StepOutcome {
+        if !self.running {
+            return StepOutcome::Halted;
+        }
+        if self.key_wait.is_some() {
+            #[cfg(feature = "std")]
+            if let Some(tracer) = self.tracer.as_mut() {
+                tracer.begin(&self.r_v);
+            }
+            self.poll_key_wait();
+            if self.key_wait.is_some() {
+                return StepOutcome::Idle;
+            }
+            #[cfg(feature = "std")]
+            self.trace_key_wait_done();
+            return StepOutcome::Ran;
+        }
+        if self.vblank_wait {
+            return StepOutcome::Idle;
+        }
+        if self.timer_wait {
+            if self.d_timer > 0 {
+                return StepOutcome::Idle;
+            }
+            self.timer_wait = false;
+        }
+
+        let pc = self.wrap_addr(self.r_pc);
+        self.r_pc = pc;
+        if self.break_on_modified_code
+            && self.code_writes.writer(pc).is_some()
+            && self.resume_at.take() != Some(pc)
+        {
+            self.resume_at = Some(pc);
+            return StepOutcome::Break(pc);
+        }
+        self.code_writes.record_exec(pc);
+        self.exec_pc = pc;
+
+        // fetch + decode，开启译码缓存时直接使用缓存的结果
+        let decoded = self.fetch_decoded(pc);
+        let ir = match decoded {
+            Some(decoded) => {
+                self.r_pc = pc.wrapping_add(2);
+                decoded.ir
+            }
+            None => Instruction::new(self.fetch()),
+        };
+
+        // execute
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.record_exec(pc, &ir);
+        }
+        #[cfg(feature = "std")]
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.begin(&self.r_v);
+        }
+        match decoded {
+            Some(decoded) => (decoded.op)(self, &ir),
+            None => self.execute(&ir),
+        }
+        #[cfg(feature = "std")]
+        if let Some(mut tracer) = self.tracer.take() {
+            tracer.end(self, pc, &ir);
+            self.tracer = Some(tracer);
+        }
+
+        self.step_outcome(pc, &ir)
+    }
+
+    /// 执行后端预先译码好的 `pc` 处指令，跳过 `cycle` 中的等待检查、取指与译码。
+    /// 只在 `can_run_compiled` 时调用，且 `pc` 处的指令没有被程序改写过
+    pub(crate) fn execute_decoded(&mut self, pc: u16, op: Op, ir: &Instruction) -> StepOutcome {
+        self.exec_pc = pc;
+        self.r_pc = pc.wrapping_add(2);
+        op(self, ir);
+        self.step_outcome(pc, ir)
+    }
+
+    /// 执行完 `pc` 处的指令之后的结果
+    fn step_outcome(&mut self, pc: u16, ir: &Instruction) -> StepOutcome {
+        if let Some(err) = self.fault.take() {
+            // 停在出错的指令上，便于调试器查看
+            self.r_pc = pc;
+            self.running = false;
+            StepOutcome::Fault(err)
+        } else if !self.running {
+            StepOutcome::Halted
+        } else if ir.opcode == 0x1000 {
+            self.loop_outcome(pc, ir.nnn)
+        } else {
+            StepOutcome::Ran
+        }
+    }
+
+    /// FX0A 等到按键时再记一条 FX0A，写入 VX 的按键记在这一条上
+    #[cfg(feature = "std")]
+    fn trace_key_wait_done(&mut self) {
+        if let Some(mut tracer) = self.tracer.take() {
+            let pc = self.r_pc.wrapping_sub(2);
+            tracer.end(self, pc, &Instruction::new(self.read_opcode(pc)));
+            self.tracer = Some(tracer);
+        }
+    }
+
+    /// 判断 `pc` 处跳转到 `target` 的 1NNN 是否构成停机或空转循环。
+    ///
+    /// 跳转到自身即停机。短距离的向后跳转，如果循环体内只有读取计时器、按键和条件跳过这类
+    /// 没有副作用的指令，那么循环能否退出只取决于 delay 计时器和按键：
+    /// 读取按键，或条件跳过检查了 FX07 读出的寄存器时为空转，否则永远不会退出，视为停机。
+    /// 这里不看计时器当前的值：FX07 与 1NNN 之间可能刚好经过一次 `ticker`，
+    /// 此时计时器已归零，但寄存器里还是上一帧读到的值，下一轮循环才会退出。
+    fn loop_outcome(&self, pc: u16, target: u16) -> StepOutcome {
+        if target == pc {
+            return StepOutcome::Halted;
+        }
+        if target > pc || pc - target > IDLE_LOOP_WINDOW {
+            return StepOutcome::Ran;
+        }
+
+        // 存放计时器值的寄存器，以及条件跳过检查过的寄存器
+        let mut timer_regs = 0u16;
+        let mut tested_regs = 0u16;
+        let mut reads_keys = false;
+        for addr in (target..pc).step_by(2) {
+            let ir = Instruction::new(self.read_opcode(addr));
+            match (ir.opcode, ir.kk) {
+                (0xf000, 0x07) => timer_regs |= 1 << ir.x,
+                (0xe000, 0x9e) | (0xe000, 0xa1) => reads_keys = true,
+                (0x3000, _) | (0x4000, _) => tested_regs |= 1 << ir.x,
+                (0x5000, _) | (0x9000, _) if ir.n == 0 => {
+                    tested_regs |= 1 << ir.x | 1 << ir.y;
                 }
+                _ => return StepOutcome::Ran,
             }
-            0xe000 => match ir.kk {
-                0x009e => ops::skp_vx(self, ir),
-                0x00a1 => ops::sknp_vx(self, ir),
-                _ => {}
-            },
-            0xf000 => match ir.kk {
-                0x0007 => ops::ld_vx_dt(self, ir),
-                0x000a => ops::ld_vx_key(self, ir),
-                0x0015 => ops::ld_dt_vx(self, ir),
-                0x0018 => ops::ld_st_vx(self, ir),
-                0x001e => ops::add_i_vx(self, ir),
-                0x0029 => ops::ld_i_font_vx(self, ir),
-                0x0030 => ops::s8_ld_i_font_vx(self, ir),
-                0x0033 => ops::bcd_vx(self, ir),
-                0x0055 => ops::ld_i_vx(self, ir),
-                0x0065 => ops::ld_vx_i(self, ir),
-                0x0075 => ops::ld_r_vx(self, ir),
-                0x0085 => ops::ld_vx_r(self, ir),
-                _ => {}
-            },
-            _ => {
-                // let x = self.c8.as_ptr();
+        }
+
+        if reads_keys || timer_regs & tested_regs != 0 {
+            StepOutcome::Idle
+        } else {
+            StepOutcome::Halted
+        }
+    }
+
+    /// 固定随机数种子，使 CXNN 的结果可以复现
+    pub fn seed_rng(&mut self, seed: u32) {
+        self.set_rng(Xorshift32::new(seed));
+    }
+
+    /// 更换 CXNN 的随机数来源
+    pub fn set_rng(&mut self, rng: impl RandomSource + 'static) {
+        self.rng = Box::new(rng);
+    }
+
+    /// 记录之后执行的每一条指令，替换已有的 tracer
+    #[cfg(feature = "std")]
+    pub fn set_tracer(&mut self, tracer: Tracer) {
+        self.tracer = Some(tracer);
+    }
+
+    /// 取下 tracer，调用 `Tracer::finish` 刷新输出
+    #[cfg(feature = "std")]
+    pub fn take_tracer(&mut self) -> Option<Tracer> {
+        self.tracer.take()
+    }
+
+    #[cfg(feature = "std")]
+    fn tracing_off(&self) -> bool {
+        self.tracer.is_none()
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn tracing_off(&self) -> bool {
+        true
+    }
+
+    /// 开始统计执行次数和内存访问，替换已有的 profiler
+    pub fn set_profiler(&mut self, profiler: Profiler) {
+        self.profiler = Some(Box::new(profiler));
+    }
+
+    pub fn profiler(&self) -> Option<&Profiler> {
+        self.profiler.as_deref()
+    }
+
+    pub fn take_profiler(&mut self) -> Option<Profiler> {
+        self.profiler.take().map(|profiler| *profiler)
+    }
+
+    /// 栈深度。VIP 布局下不超过 [`VIP_STACK`] 能放下的项数，设置深度后再切换布局也是如此
+    pub fn stack_depth(&self) -> u8 {
+        self.stack_depth.min(self.stack_capacity())
+    }
+
+    /// 当前布局下栈最多能放下的项数
+    fn stack_capacity(&self) -> u8 {
+        match self.layout {
+            MemoryLayout::Flat => MAX_STACK_DEPTH,
+            MemoryLayout::Vip => VIP_STACK_SLOTS,
+        }
+    }
+
+    /// 设置栈深度并清空栈，深度最多为当前布局下栈能放下的项数
+    pub fn set_stack_depth(&mut self, depth: u8) {
+        self.stack_depth = depth.min(self.stack_capacity());
+        self.stack.fill(0);
+        self.r_sp = 0;
+    }
+
+    /// VIP 布局下第 `slot` 项在内存中的地址
+    fn vip_stack_addr(slot: u8) -> u16 {
+        VIP_STACK.end.wrapping_sub(2 * (slot as u16 + 1)) & 0xFFF
+    }
+
+    /// 按 `layout` 压栈，栈满时按 `stack_policy` 报错或绕回栈底
+    fn push_stack(&mut self, addr: u16) {
+        if self.r_sp >= self.stack_depth() {
+            match self.stack_policy {
+                StackPolicy::Error => {
+                    self.fault = Some(VmError::StackOverflow);
+                    return;
+                }
+                StackPolicy::Wrap => self.r_sp = 0,
+            }
+        }
+        match self.layout {
+            MemoryLayout::Flat => self.stack[self.r_sp as usize] = addr,
+            MemoryLayout::Vip => {
+                let slot = Self::vip_stack_addr(self.r_sp);
+                let [high, low] = addr.to_be_bytes();
+                self.write_byte(slot.into(), high);
+                self.write_byte(slot as u32 + 1, low);
             }
         }
+        self.r_sp += 1;
     }
 
-    /// fetch -> decode -> execute
-    pub fn cycle(&mut self) {
-        // fetch
-        let ir_code = self.fetch();
+    /// 按 `layout` 出栈，栈空时按 `stack_policy` 报错（返回 None）或绕回栈顶
+    fn pop_stack(&mut self) -> Option<u16> {
+        if self.r_sp == 0 {
+            match self.stack_policy {
+                StackPolicy::Error => {
+                    self.fault = Some(VmError::StackUnderflow);
+                    return None;
+                }
+                StackPolicy::Wrap => self.r_sp = self.stack_depth(),
+            }
+        }
+        self.r_sp -= 1;
+        match self.layout {
+            MemoryLayout::Flat => Some(self.stack[self.r_sp as usize]),
+            MemoryLayout::Vip => {
+                let slot = Self::vip_stack_addr(self.r_sp);
+                Some(u16::from_be_bytes([
+                    self.read_byte(slot.into()),
+                    self.read_byte(slot as u32 + 1),
+                ]))
+            }
+        }
+    }
 
-        // decode
-        let ir = Instruction::new(ir_code);
+    /// 显示缓冲区中 (x, y) 所在的字节地址与位掩码，不在 VIP 布局的缓冲区内时为 None
+    fn display_bit(&self, x: u8, y: u8) -> Option<(usize, u8)> {
+        if self.layout != MemoryLayout::Vip || x >= 64 || y >= 32 {
+            return None;
+        }
+        let addr = VIP_DISPLAY as usize + y as usize * 8 + x as usize / 8;
+        Some((addr, 0x80 >> (x % 8)))
+    }
 
-        // execute
-        self.execute(&ir);
+    /// 读取像素，VIP 布局下读取显示缓冲区
+    fn get_pixel(&self, x: u8, y: u8) -> bool {
+        match self.display_bit(x, y) {
+            Some((addr, mask)) => self.memory[addr] & mask != 0,
+            None => self.screen.get_pixel(x, y),
+        }
+    }
+
+    /// 写入像素，VIP 布局下同时写入显示缓冲区
+    fn set_pixel(&mut self, x: u8, y: u8, on: bool) {
+        if let Some((addr, mask)) = self.display_bit(x, y) {
+            if on {
+                self.memory[addr] |= mask;
+            } else {
+                self.memory[addr] &= !mask;
+            }
+        }
+        self.screen.set_pixel(x, y, on);
+    }
+
+    /// 清屏，VIP 布局下同时清空显示缓冲区
+    fn clear_display(&mut self) {
+        self.screen.clear();
+        if self.layout == MemoryLayout::Vip {
+            self.memory[VIP_DISPLAY as usize..VIP_DISPLAY as usize + 0x100].fill(0);
+        }
+    }
+
+    /// VIP 布局下，按显示缓冲区重建 `screen` 的 64x32 区域
+    pub fn sync_screen(&mut self) {
+        if self.layout != MemoryLayout::Vip {
+            return;
+        }
+        for y in 0..32 {
+            for x in 0..64 {
+                let on = self.get_pixel(x, y);
+                self.screen.set_pixel(x, y, on);
+            }
+        }
+    }
+
+    /// VIP 布局下，把 `screen` 的 64x32 区域写回显示缓冲区，用于直接修改 `screen` 的滚屏指令
+    fn store_display(&mut self) {
+        if self.layout != MemoryLayout::Vip {
+            return;
+        }
+        for y in 0..32 {
+            for x in 0..64 {
+                let on = self.screen.get_pixel(x, y);
+                self.set_pixel(x, y, on);
+            }
+        }
+    }
+
+    /// I 加上 `offset`，按 I 的宽度绕回：16 位，内存超过 64 KiB（MegaChip）时为 24 位
+    fn i_plus(&self, offset: u32) -> u32 {
+        let mask = if self.memory.len() > CODE_SPACE_SIZE {
+            0xFF_FFFF
+        } else {
+            0xFFFF
+        };
+        self.r_i.wrapping_add(offset) & mask
+    }
+
+    /// 指令把内存当作数据读取时都经过这里，以便 profiler 统计。地址超出内存时绕回开头
+    fn read_byte(&mut self, addr: u32) -> u8 {
+        let addr = addr as usize % self.memory.len();
+        if let (Some(profiler), Ok(addr)) = (self.profiler.as_mut(), u16::try_from(addr)) {
+            profiler.record_read(addr);
+        }
+        self.memory[addr]
+    }
+
+    /// 指令写内存都经过这里，以便 tracer 与 profiler 记录写入，并检测自修改代码
+    fn write_byte(&mut self, addr: u32, value: u8) {
+        let addr = addr as usize % self.memory.len();
+        self.memory[addr] = value;
+        #[cfg(feature = "std")]
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.record_write(addr as u32, value);
+        }
+        // 64 KiB 之后的内存不会被当作指令执行，下面只记录代码区的写入
+        let Ok(addr) = u16::try_from(addr) else {
+            return;
+        };
+        self.code_writes.record_write(addr, self.exec_pc);
+        if let Some(log) = self.write_log.as_mut() {
+            if log.len() < WRITE_LOG_LIMIT {
+                log.push(addr);
+            } else {
+                log.clear();
+                self.code_generation += 1;
+            }
+        }
+        if let Some(cache) = self.decode_cache.as_mut() {
+            // 这个字节可能是某条指令的高字节或低字节
+            cache[addr as usize] = None;
+            if let Some(prev) = (addr as usize).checked_sub(1) {
+                cache[prev] = None;
+            }
+        }
+        if self.layout == MemoryLayout::Vip && (VIP_DISPLAY..0x1000).contains(&addr) {
+            // 程序直接写显示缓冲区，刷新 screen 上对应的 8 个像素
+            let offset = addr - VIP_DISPLAY;
+            let (x, y) = ((offset % 8 * 8) as u8, (offset / 8) as u8);
+            for bit in 0..8 {
+                self.screen
+                    .set_pixel(x + bit, y, value & (0x80 >> bit) != 0);
+            }
+        }
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.record_write(addr);
+        }
+    }
+
+    /// 程序写入过的内存，以及写入后被执行的指令
+    pub fn code_writes(&self) -> &CodeWrites {
+        &self.code_writes
+    }
+
+    /// 被程序改写后又执行过的指令，按地址排序
+    pub fn modified_code(&self) -> Vec<ModifiedCode> {
+        self.code_writes.modified_code()
+    }
+
+    /// 为 true 时，即将执行被改写过的指令前 `cycle` 返回 [`StepOutcome::Break`]
+    pub fn set_break_on_modified_code(&mut self, enabled: bool) {
+        self.break_on_modified_code = enabled;
+    }
+
+    /// 是否阻塞在 FX0A 上，前端可以据此降低调用 `cycle` 的频率
+    pub fn is_waiting_for_key(&self) -> bool {
+        self.key_wait.is_some()
+    }
+
+    /// 检查 FX0A 等待期间的按键边沿，等到按键后写入 VX 并恢复执行
+    fn poll_key_wait(&mut self) {
+        let Some(mut wait) = self.key_wait else {
+            return;
+        };
+        let keys = self.keyboard.mask();
+
+        let key = match wait.pressed {
+            Some(key) => {
+                if keys & (1 << key) == 0 {
+                    Some(key)
+                } else {
+                    None
+                }
+            }
+            None => {
+                let new_presses = keys & !wait.held;
+                if new_presses == 0 {
+                    None
+                } else {
+                    let key = new_presses.trailing_zeros() as u8;
+                    if self.quirks.key_wait_release {
+                        wait.pressed = Some(key);
+                        None
+                    } else {
+                        Some(key)
+                    }
+                }
+            }
+        };
+
+        match key {
+            Some(key) => {
+                self.r_v[wait.x as usize] = key;
+                self.key_wait = None;
+            }
+            None => {
+                wait.held = keys;
+                self.key_wait = Some(wait);
+            }
+        }
     }
 
     pub fn ticker(&mut self) {
+        self.vblank_wait = false;
         if self.d_timer > 0 {
             self.d_timer -= 1;
         }
@@ -248,18 +975,335 @@ impl Chip8 {
         }
     }
 
-    pub fn change_mode(&mut self, is_high_res: bool) {
-        self.high_res = is_high_res;
-        self.rate = if is_high_res { 700 } else { 480 };
-    }
+    /// 当前平台与模式下可见区域的大小，例如低分辨率 64x32，高分辨率 128x64
+    pub fn display_size(&self) -> (u8, u8) {
+        self.platform.display_size(self.high_res)
+    }
+
+    /// 切换分辨率，速度恢复为平台在该模式下的默认值（应用过数据库设置时为数据库中的速度）
+    pub fn change_mode(&mut self, is_high_res: bool) {
+        self.high_res = is_high_res;
+        self.rate = self
+            .rate_override
+            .unwrap_or_else(|| self.platform.rate(is_high_res));
+    }
+
+    /// XO-CHIP 的音频样本（F002）与音高（FX3A）
+    pub fn audio_pattern(&self) -> (&[u8; 16], u8) {
+        (&self.audio_pattern, self.pitch)
+    }
+
+    /// 程序执行 001N 退出时的返回值 N，之后 `cycle` 返回 [`StepOutcome::Halted`]
+    pub fn exit_code(&self) -> Option<u8> {
+        self.exit_code
+    }
+
+    /// CHIP-8X FXF8 最后输出到 I/O 端口的值
+    pub fn io_output(&self) -> u8 {
+        self.io_output
+    }
+
+    /// 设置 CHIP-8X FXFB 读到的值
+    pub fn set_io_input(&mut self, value: u8) {
+        self.io_input = value;
+    }
+
+    /// 画面的大小：MegaChip 模式下为 256x192，否则与 `display_size` 相同
+    pub fn framebuffer_size(&self) -> (u16, u16) {
+        match self.megachip.as_deref() {
+            Some(megachip) if megachip.enabled => (MEGA_WIDTH as u16, MEGA_HEIGHT as u16),
+            _ => {
+                let (width, height) = self.display_size();
+                (width as u16, height as u16)
+            }
+        }
+    }
+
+    /// MegaChip 平台的额外状态，其它平台为 None
+    pub fn megachip(&self) -> Option<&MegaChip> {
+        self.megachip.as_deref()
+    }
+
+    /// 以 `output_rate` 的采样率取出 MegaChip 正在播放的数字采样，没有采样可播时返回 false
+    pub fn fill_sample_audio(&mut self, output_rate: u32, out: &mut [u8]) -> bool {
+        match self.megachip.as_deref_mut() {
+            Some(megachip) if megachip.sample.playing => {
+                megachip.sample.fill(&self.memory, output_rate, out);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 像素 (x, y) 的 RGB 颜色：MegaChip 模式下取彩色画面，CHIP-8X 按颜色层着色，其它平台为单色
+    pub fn pixel_color(&self, x: u8, y: u8) -> [u8; 3] {
+        if let Some(megachip) = self.megachip.as_deref().filter(|megachip| megachip.enabled) {
+            return megachip.pixel_color(x, y);
+        }
+        let on = self.screen.get_pixel(x, y);
+        match self.platform {
+            Platform::Chip8X => self.screen.colors.rgb(x, y, on),
+            _ if on => MONO_ON,
+            _ => MONO_OFF,
+        }
+    }
+
+    /// XO-CHIP 只实现了第一个位平面，其它平台始终为 true
+    fn first_plane(&self) -> bool {
+        self.planes & 1 != 0
+    }
+
+    /// 跳过下一条指令。下一条是 4 字节的 XO-CHIP F000 NNNN 或 MegaChip 01NN NNNN 时跳过 4 个字节
+    fn skip_next(&mut self) {
+        let next = self.read_opcode(self.r_pc);
+        let long = match self.platform {
+            Platform::XoChip => next == 0xf000,
+            Platform::MegaChip => next & 0xff00 == 0x0100,
+            _ => false,
+        };
+        self.r_pc = self.r_pc.wrapping_add(if long { 4 } else { 2 });
+    }
+}
+
+impl Default for Chip8 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 栈溢出、下溢时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StackPolicy {
+    /// `cycle` 返回 [`StepOutcome::Fault`] 并停机
+    #[default]
+    Error,
+    /// 栈指针绕回另一端，覆盖或读取那里的旧值
+    Wrap,
+}
+
+/// 指令执行失败的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VmError {
+    /// 栈满时执行了 2NNN
+    StackOverflow,
+    /// 栈空时执行了 00EE
+    StackUnderflow,
+    /// 开启 `rpl_limit` 时 FX75/FX85 的 X 大于 7
+    RplOutOfRange(u8),
+    /// 当前平台上没有这条指令
+    UnknownOpcode(u16),
+    /// MegaChip 01NN NNNN 的地址超出了内存
+    AddressOutOfRange(u32),
+}
+
+impl fmt::Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VmError::StackOverflow => write!(f, "stack overflow"),
+            VmError::StackUnderflow => write!(f, "stack underflow"),
+            VmError::RplOutOfRange(x) => write!(f, "RPL flag V{:X} out of range", x),
+            VmError::UnknownOpcode(code) => write!(f, "unknown opcode {:04X}", code),
+            VmError::AddressOutOfRange(addr) => write!(f, "address {:06X} out of range", addr),
+        }
+    }
+}
+
+impl core::error::Error for VmError {}
+
+/// 执行一次 `cycle` 的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepOutcome {
+    /// 正常执行了一条指令
+    Ran,
+    /// 程序已经结束：执行了 00FD、跳转到自身，或陷入了不依赖任何输入的死循环
+    Halted,
+    /// 正在等待按键、delay 计时器或 vblank，在外部状态变化前继续调用 `cycle` 不会有进展
+    Idle,
+    /// 指令执行失败，VM 停在这条指令上并停机
+    Fault(VmError),
+    /// 开启了 `set_break_on_modified_code`，这个地址上被改写过的指令还没有执行；
+    /// 再次调用 `cycle` 会执行它
+    Break(u16),
+}
+
+/// 执行指令的方式。[`Interpreter`] 是参考实现，其它后端的执行结果必须与它相同
+pub trait Backend: fmt::Debug {
+    /// 最多执行 `cycles` 条指令（与调用同样次数的 [`Chip8::cycle`] 等价），
+    /// 遇到 [`StepOutcome::Ran`] 以外的结果时提前返回该结果
+    fn run(&mut self, vm: &mut Chip8, cycles: u32) -> StepOutcome;
+}
+
+/// 逐条调用 [`Chip8::cycle`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Interpreter;
+
+impl Backend for Interpreter {
+    fn run(&mut self, vm: &mut Chip8, cycles: u32) -> StepOutcome {
+        for _ in 0..cycles {
+            let outcome = vm.cycle();
+            if outcome != StepOutcome::Ran {
+                return outcome;
+            }
+        }
+        StepOutcome::Ran
+    }
+}
+
+/// 指令的执行函数
+pub(crate) type Op = fn(&mut Chip8, &Instruction);
+
+/// CHIP-8E 平台上新增或含义不同的指令
+fn chip8e_op(ir: &Instruction) -> Option<Op> {
+    Some(match (ir.opcode, ir.x, ir.n, ir.kk) {
+        (0x0000, ..) => match ir.ir_code {
+            0x00ed => |vm, _| ops::e_stop(vm),
+            0x00f2 => |_, _| {},
+            0x0151 => |vm, _| ops::e_wait_dt(vm),
+            0x0188 => |vm, _| vm.skip_next(),
+            _ => return None,
+        },
+        (0x5000, _, 0x1, _) => ops::e_sgt_vx_vy,
+        (0x5000, _, 0x2, _) => ops::e_save_vx_vy,
+        (0x5000, _, 0x3, _) => ops::e_load_vx_vy,
+        (0xb000, 0xb, ..) => ops::e_jr_back_nn,
+        (0xb000, 0xf, ..) => ops::e_jr_forward_nn,
+        (0xf000, _, _, 0x03) => ops::e_out_vx,
+        (0xf000, _, _, 0x1b) => ops::e_skip_vx,
+        (0xf000, _, _, 0x4f) => ops::e_delay_vx,
+        (0xf000, _, _, 0xe3 | 0xe7) => ops::e_in_vx,
+        _ => return None,
+    })
 }
 
-impl Default for Chip8 {
-    fn default() -> Self {
-        Self::new()
+/// MegaChip 平台上新增或含义不同的指令。00E0、滚屏与 DXYN 在执行时才按是否处于 MegaChip 模式选择行为，
+/// 结果可以缓存
+fn megachip_op(ir: &Instruction) -> Option<Op> {
+    Some(match ir.ir_code {
+        0x0010 => |vm, _| ops::mega_off(vm),
+        0x0011 => |vm, _| ops::mega_on(vm),
+        0x00b0..=0x00bf => ops::mega_scu_n,
+        0x00c0..=0x00cf => ops::mega_scd_n,
+        0x00e0 => |vm, _| ops::mega_cls(vm),
+        0x00fb => |vm, _| ops::mega_scr(vm),
+        0x00fc => |vm, _| ops::mega_scl(vm),
+        0x0100..=0x01ff => ops::mega_ld_i_long,
+        0x0200..=0x02ff => ops::mega_palette,
+        0x0300..=0x03ff => ops::mega_sprite_width,
+        0x0400..=0x04ff => ops::mega_sprite_height,
+        0x0500..=0x05ff => ops::mega_alpha,
+        0x0600..=0x06ff => ops::mega_play,
+        0x0700 => |vm, _| ops::mega_stop(vm),
+        0x0800..=0x08ff => ops::mega_blend,
+        0x0900..=0x09ff => ops::mega_collision,
+        0xd000..=0xdfff => ops::mega_drw,
+        _ => return None,
+    })
+}
+
+/// 译码缓存中的一项
+#[derive(Debug, Clone, Copy)]
+struct Decoded {
+    ir: Instruction,
+    op: Op,
+}
+
+/// 按平台译码，结果是可以直接调用的执行函数。`Chip8::execute`、译码缓存与重编译器共用这张表
+pub(crate) fn decode(ir: &Instruction, platform: Platform) -> Op {
+    if !platform.supports(ir) {
+        return ops::unknown;
+    }
+    let extension = match platform {
+        Platform::MegaChip => megachip_op(ir),
+        Platform::Chip8E => chip8e_op(ir),
+        _ => None,
+    };
+    if let Some(op) = extension {
+        return op;
+    }
+    match ir.opcode {
+        0x0000 => match ir.ir_code {
+            0x00e0 => |vm, _| ops::cls(vm),
+            0x00ee => |vm, _| ops::ret(vm),
+            0x00fb => |vm, _| ops::s8_scr(vm),
+            0x00fc => |vm, _| ops::s8_scl(vm),
+            0x00fd => |vm, _| ops::s8_exit(vm),
+            0x00fe => |vm, _| ops::s8_low(vm),
+            0x00ff => |vm, _| ops::s8_high(vm),
+            0x00c0..=0x00cf => ops::s8_scd_n,
+            0x00d0..=0x00df => ops::xo_scu_n,
+            0x0010..=0x001f if platform == Platform::Chip8 => ops::exit_n,
+            0x0230 if platform == Platform::HiresChip8 => |vm, _| ops::cls(vm),
+            0x02a0 if platform == Platform::Chip8X => |vm, _| ops::x_cycle_bg(vm),
+            _ => |vm, _| ops::call_nnn(vm),
+        },
+        0x1000 => ops::jmp_nnn,
+        0x2000 => ops::call_nnn_2,
+        0x3000 => ops::se_vx_nn,
+        0x4000 => ops::sne_vx_nn,
+        0x5000 => match ir.n {
+            0x2 => ops::xo_save_vx_vy,
+            0x3 => ops::xo_load_vx_vy,
+            _ => ops::se_vx_vy,
+        },
+        0x6000 => ops::ld_vx_nn,
+        0x7000 => ops::add_vx_nn,
+        0x8000 => match ir.n {
+            0x0 => ops::ld_vx_vy,
+            0x1 => ops::or_vx_vy,
+            0x2 => ops::and_vx_vy,
+            0x3 => ops::xor_vx_vy,
+            0x4 => ops::add_vx_vy,
+            0x5 => ops::sub_vx_vy,
+            0x6 => ops::shr_vx_vy,
+            0x7 => ops::subn_vy_vx,
+            0xe => ops::shl_vx_vy,
+            _ => ops::unknown,
+        },
+        0x9000 => ops::sne_vx_vy,
+        0xa000 => ops::ld_i_nnn,
+        0xb000 if platform == Platform::Chip8X => ops::x_col_vx_vy_n,
+        0xb000 => ops::jp_v0_nnn,
+        0xc000 => ops::rnd_vx_nn,
+        // DXY0 要到执行时才知道是否处于高分辨率模式
+        0xd000 if ir.n == 0 => |vm, ir| {
+            if vm.high_res {
+                ops::s8_drw_vx_vy_0(vm, ir)
+            } else {
+                ops::drw_vx_vy_n(vm, ir)
+            }
+        },
+        0xd000 => ops::drw_vx_vy_n,
+        0xe000 => match ir.kk {
+            0x009e => ops::skp_vx,
+            0x00a1 => ops::sknp_vx,
+            _ => ops::unknown,
+        },
+        0xf000 => match ir.kk {
+            0x0000 => |vm, _| ops::xo_ld_i_long(vm),
+            0x0001 => ops::xo_plane_n,
+            0x0002 => |vm, _| ops::xo_audio(vm),
+            0x0007 => ops::ld_vx_dt,
+            0x000a => ops::ld_vx_key,
+            0x0015 => ops::ld_dt_vx,
+            0x0018 => ops::ld_st_vx,
+            0x001e => ops::add_i_vx,
+            0x0029 => ops::ld_i_font_vx,
+            0x0030 => ops::s8_ld_i_font_vx,
+            0x0033 => ops::bcd_vx,
+            0x003a => ops::xo_pitch_vx,
+            0x0055 => ops::ld_i_vx,
+            0x0065 => ops::ld_vx_i,
+            0x0075 => ops::ld_r_vx,
+            0x0085 => ops::ld_vx_r,
+            0x00f8 => ops::x_out_vx,
+            0x00fb => ops::x_in_vx,
+            _ => ops::unknown,
+        },
+        _ => ops::unknown,
     }
 }
 
+#[derive(Debug, Clone, Copy)]
 pub struct Instruction {
     pub ir_code: u16,
     /// 操作码，第一位
@@ -291,16 +1335,20 @@ impl Instruction {
 }
 
 mod ops {
-    use rand::Rng;
+    use super::{BlendMode, Box, Chip8, Instruction, KeyWait, MegaChip, VmError};
 
-    use super::{Chip8, Instruction, LARGE_FONT_BASE};
+    /// 当前平台上不存在的指令
+    pub fn unknown(vm: &mut Chip8, ir: &Instruction) {
+        vm.fault = Some(VmError::UnknownOpcode(ir.ir_code));
+    }
 
     /**
      * 001N
      * From Peter Miller's chip8run. Exit emulator with a return value of N.
      */
-    pub fn exit_n(_vm: &Chip8, ir: &Instruction) {
-        panic!("Exit with return value {}", ir.n);
+    pub fn exit_n(vm: &mut Chip8, ir: &Instruction) {
+        vm.exit_code = Some(ir.n);
+        vm.running = false;
     }
 
     /**
@@ -318,6 +1366,35 @@ mod ops {
                 vm.screen.set_pixel(x, y, false);
             }
         }
+        vm.store_display();
+        vm.draw_flag = true;
+    }
+
+    /**
+     * 00DN
+     * XO-CHIP: scroll display N lines up.
+     */
+    pub fn xo_scu_n(vm: &mut Chip8, ir: &Instruction) {
+        for y in 0..vm.screen.rows - ir.n {
+            for x in 0..vm.screen.columns {
+                vm.screen.set_pixel(x, y, vm.screen.get_pixel(x, y + ir.n));
+            }
+        }
+        for y in vm.screen.rows - ir.n..vm.screen.rows {
+            for x in 0..vm.screen.columns {
+                vm.screen.set_pixel(x, y, false);
+            }
+        }
+        vm.store_display();
+        vm.draw_flag = true;
+    }
+
+    /**
+     * 02A0
+     * CHIP-8X: step the background color (black, blue, green, red).
+     */
+    pub fn x_cycle_bg(vm: &mut Chip8) {
+        vm.screen.colors.cycle_background();
         vm.draw_flag = true;
     }
 
@@ -326,16 +1403,20 @@ mod ops {
      * Clears the display. Sets all pixels to off.
      */
     pub fn cls(vm: &mut Chip8) {
-        vm.screen.clear();
+        if !vm.first_plane() {
+            return;
+        }
+        vm.clear_display();
         vm.draw_flag = true;
     }
 
     /**
      * 00EE
      * Return from subroutine. Set the PC to the address at the top of the stack and subtract 1 from the SP.
+     * Fails with `VmError::StackUnderflow` on an empty stack unless `stack_policy` is `Wrap`.
      */
     pub fn ret(vm: &mut Chip8) {
-        if let Some(res) = vm.stack.pop() {
+        if let Some(res) = vm.pop_stack() {
             vm.r_pc = res;
         }
     }
@@ -345,15 +1426,16 @@ mod ops {
      * Scroll display 4 pixels to the right.
      */
     pub fn s8_scr(vm: &mut Chip8) {
-        for y in 0..vm.screen.columns {
+        for y in 0..vm.screen.rows {
             for x in (4..vm.screen.columns).rev() {
-                vm.screen.set_pixel(x - 4, y, vm.screen.get_pixel(x, y));
+                vm.screen.set_pixel(x, y, vm.screen.get_pixel(x - 4, y));
             }
 
             for x in 0..4 {
                 vm.screen.set_pixel(x, y, false);
             }
         }
+        vm.store_display();
         vm.draw_flag = true;
     }
 
@@ -371,6 +1453,7 @@ mod ops {
                 }
             }
         }
+        vm.store_display();
         vm.draw_flag = true;
     }
 
@@ -405,7 +1488,7 @@ mod ops {
      * It is ignored by modern interpreters.
      */
     pub fn call_nnn(vm: &mut Chip8) {
-        vm.r_pc = (vm.r_pc + 2) & 0xfff;
+        vm.r_pc = vm.r_pc.wrapping_add(2) & 0xfff;
     }
 
     /**
@@ -421,10 +1504,11 @@ mod ops {
      * Call subroutine a NNN.
      * Increment the SP and put the current PC value on the top of the stack.
      * Then set the PC to NNN.
-     * Generally there is a limit of 16 successive calls.
+     * The limit of successive calls is `stack_depth()`: 12 on the VIP, 16 on SCHIP.
+     * Fails with `VmError::StackOverflow` on a full stack unless `stack_policy` is `Wrap`.
      */
     pub fn call_nnn_2(vm: &mut Chip8, ir: &Instruction) {
-        vm.stack.push(vm.r_pc);
+        vm.push_stack(vm.r_pc);
         vm.r_pc = ir.nnn;
     }
 
@@ -434,7 +1518,7 @@ mod ops {
      */
     pub fn se_vx_nn(vm: &mut Chip8, ir: &Instruction) {
         if vm.r_v[ir.x as usize] == ir.kk {
-            vm.r_pc += 2;
+            vm.skip_next();
         }
     }
 
@@ -444,7 +1528,7 @@ mod ops {
      */
     pub fn sne_vx_nn(vm: &mut Chip8, ir: &Instruction) {
         if vm.r_v[ir.x as usize] != ir.kk {
-            vm.r_pc += 2;
+            vm.skip_next();
         }
     }
 
@@ -454,7 +1538,7 @@ mod ops {
      */
     pub fn se_vx_vy(vm: &mut Chip8, ir: &Instruction) {
         if vm.r_v[ir.x as usize] == vm.r_v[ir.y as usize] {
-            vm.r_pc += 2;
+            vm.skip_next();
         }
     }
 
@@ -489,6 +1573,9 @@ mod ops {
      */
     pub fn or_vx_vy(vm: &mut Chip8, ir: &Instruction) {
         vm.r_v[ir.x as usize] |= vm.r_v[ir.y as usize];
+        if vm.quirks.vf_reset {
+            vm.r_v[0xF] = 0;
+        }
     }
 
     /**
@@ -497,6 +1584,9 @@ mod ops {
      */
     pub fn and_vx_vy(vm: &mut Chip8, ir: &Instruction) {
         vm.r_v[ir.x as usize] &= vm.r_v[ir.y as usize];
+        if vm.quirks.vf_reset {
+            vm.r_v[0xF] = 0;
+        }
     }
 
     /**
@@ -507,6 +1597,9 @@ mod ops {
      */
     pub fn xor_vx_vy(vm: &mut Chip8, ir: &Instruction) {
         vm.r_v[ir.x as usize] ^= vm.r_v[ir.y as usize];
+        if vm.quirks.vf_reset {
+            vm.r_v[0xF] = 0;
+        }
     }
 
     /**
@@ -546,8 +1639,13 @@ mod ops {
      * but functional due to how the 8XXX instructions were implemented on teh COSMAC VIP.
      */
     pub fn shr_vx_vy(vm: &mut Chip8, ir: &Instruction) {
-        vm.r_v[0xF] = vm.r_v[ir.x as usize] & 0x1;
-        vm.r_v[ir.x as usize] >>= 1;
+        let x = ir.x as usize;
+        if vm.quirks.shift_vy {
+            vm.r_v[x] = vm.r_v[ir.y as usize];
+        }
+        let vx = vm.r_v[x];
+        vm.r_v[x] = vx >> 1;
+        vm.r_v[0xF] = vx & 0x1;
     }
 
     /**
@@ -577,9 +1675,39 @@ mod ops {
      */
     pub fn shl_vx_vy(vm: &mut Chip8, ir: &Instruction) {
         let x = ir.x as usize;
-        vm.r_v[0xF] = (vm.r_v[x] >> 7) & 0x1;
-        vm.r_v[x] <<= 1;
-        vm.r_v[x] &= 0xFF;
+        if vm.quirks.shift_vy {
+            vm.r_v[x] = vm.r_v[ir.y as usize];
+        }
+        let vx = vm.r_v[x];
+        vm.r_v[x] = vx << 1;
+        vm.r_v[0xF] = (vx >> 7) & 0x1;
+    }
+
+    /// 5XY2/5XY3 涉及的寄存器编号，X 大于 Y 时按倒序
+    fn register_range(ir: &Instruction) -> impl Iterator<Item = usize> {
+        let (x, y) = (ir.x as usize, ir.y as usize);
+        let len = x.abs_diff(y) + 1;
+        (0..len).map(move |i| if x <= y { x + i } else { x - i })
+    }
+
+    /**
+     * 5XY2
+     * XO-CHIP: save VX through VY to memory starting at I. I does not change.
+     */
+    pub fn xo_save_vx_vy(vm: &mut Chip8, ir: &Instruction) {
+        for (offset, reg) in register_range(ir).enumerate() {
+            vm.write_byte(vm.i_plus(offset as u32), vm.r_v[reg]);
+        }
+    }
+
+    /**
+     * 5XY3
+     * XO-CHIP: load VX through VY from memory starting at I. I does not change.
+     */
+    pub fn xo_load_vx_vy(vm: &mut Chip8, ir: &Instruction) {
+        for (offset, reg) in register_range(ir).enumerate() {
+            vm.r_v[reg] = vm.read_byte(vm.i_plus(offset as u32));
+        }
     }
 
     /**
@@ -590,7 +1718,7 @@ mod ops {
         let x = ir.x as usize;
         let y = ir.y as usize;
         if vm.r_v[x] != vm.r_v[y] {
-            vm.r_pc += 2;
+            vm.skip_next();
         }
     }
 
@@ -599,15 +1727,48 @@ mod ops {
      * Set I equal to NNN.
      */
     pub fn ld_i_nnn(vm: &mut Chip8, ir: &Instruction) {
-        vm.r_i = ir.nnn;
+        vm.r_i = ir.nnn.into();
     }
 
     /**
      * BNNN
      * Set the PC to NNN plus the value in V0.
+     * With `quirks.jump_vx` (CHIP-48/SCHIP) this is BXNN: the offset comes from VX instead.
      */
     pub fn jp_v0_nnn(vm: &mut Chip8, ir: &Instruction) {
-        vm.r_pc = ir.nnn + vm.r_v[0x0] as u16;
+        let offset = if vm.quirks.jump_vx { ir.x } else { 0x0 };
+        vm.r_pc = ir.nnn + vm.r_v[offset as usize] as u16;
+    }
+
+    /**
+     * BXY0 / BXYN
+     * CHIP-8X: set the foreground color zones to the color in VY.
+     * BXY0 colors whole 8x4 blocks: the low nibbles of VX and VX+1 are the first block column and row,
+     * the high nibbles how many more blocks follow. BXYN colors N 8x1 rows of the column containing
+     * pixel VX, starting at pixel row VX+1.
+     */
+    pub fn x_col_vx_vy_n(vm: &mut Chip8, ir: &Instruction) {
+        let horizontal = vm.r_v[ir.x as usize];
+        let vertical = vm.r_v[(ir.x as usize + 1) % 16];
+        let color = vm.r_v[ir.y as usize];
+        if ir.n == 0 {
+            let columns =
+                (horizontal & 0xf) as usize..=((horizontal & 0xf) + (horizontal >> 4)) as usize;
+            let blocks = (vertical & 0xf) as usize..=((vertical & 0xf) + (vertical >> 4)) as usize;
+            for block in blocks {
+                for row in block * 4..block * 4 + 4 {
+                    for column in columns.clone() {
+                        vm.screen.colors.set_zone(column, row, color);
+                    }
+                }
+            }
+        } else {
+            let column = horizontal as usize / 8;
+            for row in vertical as usize..vertical as usize + ir.n as usize {
+                vm.screen.colors.set_zone(column, row, color);
+            }
+        }
+        vm.draw_flag = true;
     }
 
     /**
@@ -615,7 +1776,7 @@ mod ops {
      * Set VX equal to a random number ranging from 0 to 255 which is logically anded with NN.
      */
     pub fn rnd_vx_nn(vm: &mut Chip8, ir: &Instruction) {
-        vm.r_v[ir.x as usize] = vm.rng.gen_range(0..0xFF) & ir.kk;
+        vm.r_v[ir.x as usize] = vm.rng.next_u8() & ir.kk;
     }
 
     /**
@@ -628,22 +1789,24 @@ mod ops {
      */
     pub fn s8_drw_vx_vy_0(vm: &mut Chip8, ir: &Instruction) {
         vm.r_v[0xF] = 0;
-        let x = ir.x as usize;
-        let y = ir.y as usize;
-        let vx = vm.r_v[x];
-        let vy = vm.r_v[y];
+        if !vm.first_plane() {
+            return;
+        }
+        let (width, height) = vm.display_size();
+        let vx = vm.r_v[ir.x as usize] % width;
+        let vy = vm.r_v[ir.y as usize] % height;
 
         for row in 0..16 {
-            if vy + row >= vm.screen.rows {
+            let Some(y_cord) = wrap_cord(vm, vy, row, height) else {
                 break;
-            }
-            let sprite_byte_1 = vm.memory[(vm.r_i + 2 * row as u16) as usize];
-            let sprite_byte_2 = vm.memory[(vm.r_i + (2 * row + 1) as u16) as usize];
+            };
+            let sprite_byte_1 = vm.read_byte(vm.i_plus(2 * row as u32));
+            let sprite_byte_2 = vm.read_byte(vm.i_plus((2 * row + 1) as u32));
 
             for bit_index in 0..16 {
-                if vx + bit_index >= vm.screen.columns {
-                    continue;
-                }
+                let Some(x_cord) = wrap_cord(vm, vx, bit_index, width) else {
+                    break;
+                };
 
                 let sprite_bit = if bit_index < 8 {
                     sprite_byte_1 >> (7 - bit_index) & 0x1
@@ -651,20 +1814,21 @@ mod ops {
                     sprite_byte_2 >> (15 - bit_index) & 0x1
                 };
 
-                let display_bit = vm.screen.get_pixel(vx + bit_index, vy + row);
+                let display_bit = vm.get_pixel(x_cord, y_cord);
 
                 if sprite_bit == 1 {
                     if display_bit {
                         vm.r_v[0xF] = 1;
-                        vm.screen.set_pixel(vx + bit_index, vy + row, false);
+                        vm.set_pixel(x_cord, y_cord, false);
                     } else {
-                        vm.screen.set_pixel(vx + bit_index, vy + row, true);
+                        vm.set_pixel(x_cord, y_cord, true);
                     }
                 }
             }
         }
 
         vm.draw_flag = true;
+        vm.vblank_wait = vm.quirks.display_wait;
     }
 
     /**
@@ -675,33 +1839,48 @@ mod ops {
      */
     pub fn drw_vx_vy_n(vm: &mut Chip8, ir: &Instruction) {
         vm.r_v[0xF] = 0;
-        let x = ir.x as usize;
-        let y = ir.y as usize;
-        let nums = ir.n as usize;
-        let vx = vm.r_v[x];
-        let vy = vm.r_v[y];
+        if !vm.first_plane() {
+            return;
+        }
+        let nums = ir.n;
+        let (width, height) = vm.display_size();
+        let vx = vm.r_v[ir.x as usize] % width;
+        let vy = vm.r_v[ir.y as usize] % height;
 
-        // 64 * 32
         for yy in 0..nums {
-            let sys_byte = vm.memory[vm.r_i as usize + yy];
+            let Some(y_cord) = wrap_cord(vm, vy, yy, height) else {
+                break;
+            };
+            let sys_byte = vm.read_byte(vm.i_plus(yy as u32));
 
             for xx in 0..8 {
-                let x_cord = vx + xx;
-                let y_cord = vy + yy as u8;
-
-                if x_cord < vm.screen.columns && y_cord < vm.screen.rows {
-                    let sys_bit = (sys_byte >> (7 - xx)) & 0x1;
-                    let xy_bit = vm.screen.get_pixel(x_cord, y_cord) as u8;
+                let Some(x_cord) = wrap_cord(vm, vx, xx, width) else {
+                    break;
+                };
+                let sys_bit = (sys_byte >> (7 - xx)) & 0x1;
+                let xy_bit = vm.get_pixel(x_cord, y_cord) as u8;
 
-                    if (xy_bit & sys_bit) == 1 {
-                        vm.r_v[0xF] = 1;
-                    }
-                    vm.screen.set_pixel(x_cord, y_cord, (xy_bit ^ sys_bit) == 1);
+                if (xy_bit & sys_bit) == 1 {
+                    vm.r_v[0xF] = 1;
                 }
+                vm.set_pixel(x_cord, y_cord, (xy_bit ^ sys_bit) == 1);
             }
         }
 
         vm.draw_flag = true;
+        vm.vblank_wait = vm.quirks.display_wait;
+    }
+
+    /// 精灵内偏移 offset 处的坐标，超出屏幕时按 clipping quirk 裁剪（None）或绕回
+    fn wrap_cord(vm: &Chip8, origin: u8, offset: u8, size: u8) -> Option<u8> {
+        let cord = origin as u16 + offset as u16;
+        if cord < size as u16 {
+            Some(cord as u8)
+        } else if vm.quirks.clipping {
+            None
+        } else {
+            Some((cord % size as u16) as u8)
+        }
     }
 
     /**
@@ -711,7 +1890,7 @@ mod ops {
     pub fn skp_vx(vm: &mut Chip8, ir: &Instruction) {
         let i = vm.r_v[ir.x as usize] as usize;
         if i < 0xF && vm.keyboard.keys[i] {
-            vm.r_pc += 2;
+            vm.skip_next();
         }
     }
 
@@ -722,9 +1901,36 @@ mod ops {
     pub fn sknp_vx(vm: &mut Chip8, ir: &Instruction) {
         let i = vm.r_v[ir.x as usize] as usize;
         if i < 0xF && !vm.keyboard.keys[i] {
-            vm.r_pc += 2;
+            vm.skip_next();
+        }
+    }
+    /**
+     * F000 NNNN
+     * XO-CHIP: set I to the 16-bit address in the following word, then skip it.
+     */
+    pub fn xo_ld_i_long(vm: &mut Chip8) {
+        vm.r_i = vm.read_opcode(vm.r_pc).into();
+        vm.r_pc = vm.r_pc.wrapping_add(2);
+    }
+
+    /**
+     * FN01
+     * XO-CHIP: select the drawing planes given by the bitmask N.
+     */
+    pub fn xo_plane_n(vm: &mut Chip8, ir: &Instruction) {
+        vm.planes = ir.x;
+    }
+
+    /**
+     * F002
+     * XO-CHIP: load the 16-byte audio pattern from memory starting at I.
+     */
+    pub fn xo_audio(vm: &mut Chip8) {
+        for i in 0..16 {
+            vm.audio_pattern[i] = vm.read_byte(vm.i_plus(i as u32));
         }
     }
+
     /**
      * FX07
      * Set VX equal to the delay timer.
@@ -736,19 +1942,16 @@ mod ops {
     /**
      * FX0A
      * Wait for a key press and store the value of the key into VX.
+     * Keys already held when the wait starts are ignored. Depending on `quirks.key_wait_release`
+     * the wait completes on the press or, like the COSMAC VIP, on the following release.
+     * Timers keep running while waiting.
      */
     pub fn ld_vx_key(vm: &mut Chip8, ir: &Instruction) {
-        let mut key_pressed = false;
-        for (i, key) in vm.keyboard.keys.iter().enumerate() {
-            if *key {
-                vm.r_v[ir.x as usize] = i as u8;
-                key_pressed = true;
-                break;
-            }
-        }
-        if !key_pressed {
-            vm.r_pc -= 2;
-        }
+        vm.key_wait = Some(KeyWait {
+            x: ir.x,
+            held: vm.keyboard.mask(),
+            pressed: None,
+        });
     }
 
     /**
@@ -770,30 +1973,33 @@ mod ops {
     /**
      * FX1E
      * Add VX to I. VF is set to 1 if I > 0x0FFF. Otherwise set to 0.
+     * I wraps around at the end of memory (4 KiB, 64 KiB on XO-CHIP, or 16 MiB on MegaChip).
      */
     pub fn add_i_vx(vm: &mut Chip8, ir: &Instruction) {
         let vx = vm.r_v[ir.x as usize];
-        let i = vm.r_i;
-        let i_plus_vx = i + vx as u16;
+        let i_plus_vx = vm.r_i as usize + vx as usize;
         vm.r_v[0xF] = if i_plus_vx > 0x0FFF { 1 } else { 0 };
-        vm.r_i = i_plus_vx & 0x0FFF;
+        vm.r_i = (i_plus_vx % vm.memory.len()) as u32;
     }
 
     /**
      * FX29
      * Set I to the address of the CHIP-8 8x5 font sprite representing the value in VX.
+     * The glyphs and their base address come from `MachineConfig::font_set` and `small_font_base`.
+     * Only the low nibble of VX selects the digit.
      */
     pub fn ld_i_font_vx(vm: &mut Chip8, ir: &Instruction) {
-        vm.r_i = vm.r_v[ir.x as usize] as u16 * 5;
+        vm.r_i = vm.config.small_glyph(vm.r_v[ir.x as usize]).into();
     }
 
     /**
      * FX30
      * Set I to the address of the SCHIP-8 16x10 font sprite representing the value in VX.
+     * The base address comes from `MachineConfig::large_font_base`.
+     * Falls back to the small sprite when the font set has no large glyph for VX.
      */
     pub fn s8_ld_i_font_vx(vm: &mut Chip8, ir: &Instruction) {
-        let val = vm.r_v[ir.x as usize] as u16 * 10;
-        vm.r_i = (LARGE_FONT_BASE + val) & 0x0FFF;
+        vm.r_i = (vm.config.large_glyph(vm.r_v[ir.x as usize]) & 0x0FFF).into();
     }
 
     /**
@@ -804,54 +2010,392 @@ mod ops {
     pub fn bcd_vx(vm: &mut Chip8, ir: &Instruction) {
         let vx = vm.r_v[ir.x as usize];
         // 百位
-        vm.memory[vm.r_i as usize] = vx / 100;
+        vm.write_byte(vm.r_i, vx / 100);
         // 十位
-        vm.memory[(vm.r_i + 1) as usize] = (vx % 100) / 10;
+        vm.write_byte(vm.i_plus(1), (vx % 100) / 10);
         // 个位
-        vm.memory[(vm.r_i + 2) as usize] = vx % 100 % 10;
+        vm.write_byte(vm.i_plus(2), vx % 100 % 10);
+    }
+
+    /**
+     * FX3A
+     * XO-CHIP: set the audio pitch register to VX.
+     */
+    pub fn xo_pitch_vx(vm: &mut Chip8, ir: &Instruction) {
+        vm.pitch = vm.r_v[ir.x as usize];
+    }
+
+    /**
+     * FXF8
+     * CHIP-8X: output VX to the I/O port (the VP-595 tone pitch).
+     */
+    pub fn x_out_vx(vm: &mut Chip8, ir: &Instruction) {
+        vm.io_output = vm.r_v[ir.x as usize];
+    }
+
+    /**
+     * FXFB
+     * CHIP-8X: load VX from the I/O port. The port is not strobed, the last value set by `set_io_input` is read.
+     */
+    pub fn x_in_vx(vm: &mut Chip8, ir: &Instruction) {
+        vm.r_v[ir.x as usize] = vm.io_input;
     }
 
     /**
      * FX55
-     * Store registers V0 through VX in memory starting at location I. I does not change.
+     * Store registers V0 through VX in memory starting at location I.
+     * I does not change, unless `quirks.memory_increment` is set (COSMAC VIP), then I is set to I + X + 1.
      */
     pub fn ld_i_vx(vm: &mut Chip8, ir: &Instruction) {
         for i in 0..=ir.x {
-            let i = i as usize;
-            vm.memory[(vm.r_i) as usize + i] = vm.r_v[i];
+            vm.write_byte(vm.i_plus(i as u32), vm.r_v[i as usize]);
+        }
+        if vm.quirks.memory_increment {
+            vm.r_i = vm.i_plus(ir.x as u32 + 1);
         }
     }
 
     /**
      * FX65
-     * Copy values from memory location I through I + X into registers V0 through VX. I does not change.
+     * Copy values from memory location I through I + X into registers V0 through VX.
+     * I does not change, unless `quirks.memory_increment` is set (COSMAC VIP), then I is set to I + X + 1.
      */
     pub fn ld_vx_i(vm: &mut Chip8, ir: &Instruction) {
         for i in 0..=ir.x {
-            let i = i as usize;
-            vm.r_v[i] = vm.memory[(vm.r_i) as usize + i];
+            vm.r_v[i as usize] = vm.read_byte(vm.i_plus(i as u32));
+        }
+        if vm.quirks.memory_increment {
+            vm.r_i = vm.i_plus(ir.x as u32 + 1);
         }
     }
 
     /**
      * FX75
-     * Store V0 through VX to HP-48 RPL user flags (X <= 7).
+     * Store V0 through VX to HP-48 RPL user flags (X <= 7), then persist them if storage is attached.
+     * With `quirks.rpl_limit` set, X > 7 fails with `VmError::RplOutOfRange`.
      */
     pub fn ld_r_vx(vm: &mut Chip8, ir: &Instruction) {
+        if vm.quirks.rpl_limit && ir.x > 7 {
+            vm.fault = Some(VmError::RplOutOfRange(ir.x));
+            return;
+        }
         for i in 0..=ir.x {
             let i = i as usize;
             vm.r_rpl[i] = vm.r_v[i];
         }
+        vm.flush_rpl();
     }
 
     /**
      * FX85
      * Read V0 through VX to HP-48 RPL user flags (X <= 7)
+     * With `quirks.rpl_limit` set, X > 7 fails with `VmError::RplOutOfRange`.
      */
     pub fn ld_vx_r(vm: &mut Chip8, ir: &Instruction) {
+        if vm.quirks.rpl_limit && ir.x > 7 {
+            vm.fault = Some(VmError::RplOutOfRange(ir.x));
+            return;
+        }
         for i in 0..=ir.x {
             let i = i as usize;
             vm.r_v[i] = vm.r_rpl[i];
         }
     }
+
+    /// 处于 MegaChip 模式时的彩色画面
+    fn mega_mode(megachip: &mut Option<Box<MegaChip>>) -> Option<&mut MegaChip> {
+        megachip.as_deref_mut().filter(|megachip| megachip.enabled)
+    }
+
+    /**
+     * 0010
+     * MegaChip: leave MegaChip mode, back to the SUPER-CHIP display.
+     */
+    pub fn mega_off(vm: &mut Chip8) {
+        if let Some(megachip) = vm.megachip.as_deref_mut() {
+            megachip.enabled = false;
+        }
+        vm.draw_flag = true;
+    }
+
+    /**
+     * 0011
+     * MegaChip: enter MegaChip mode with the 256x192 color display.
+     */
+    pub fn mega_on(vm: &mut Chip8) {
+        if let Some(megachip) = vm.megachip.as_deref_mut() {
+            megachip.enabled = true;
+        }
+        vm.draw_flag = true;
+    }
+
+    /**
+     * 00BN
+     * MegaChip: scroll display N lines up.
+     */
+    pub fn mega_scu_n(vm: &mut Chip8, ir: &Instruction) {
+        match mega_mode(&mut vm.megachip) {
+            Some(megachip) => {
+                megachip.screen.scroll(0, -(ir.n as isize));
+                vm.draw_flag = true;
+            }
+            None => xo_scu_n(vm, ir),
+        }
+    }
+
+    /// 00CN，MegaChip 模式下滚动彩色画面
+    pub fn mega_scd_n(vm: &mut Chip8, ir: &Instruction) {
+        match mega_mode(&mut vm.megachip) {
+            Some(megachip) => {
+                megachip.screen.scroll(0, ir.n as isize);
+                vm.draw_flag = true;
+            }
+            None => s8_scd_n(vm, ir),
+        }
+    }
+
+    /// 00E0，MegaChip 模式下清除彩色画面
+    pub fn mega_cls(vm: &mut Chip8) {
+        match mega_mode(&mut vm.megachip) {
+            Some(megachip) => {
+                megachip.screen.clear();
+                vm.draw_flag = true;
+            }
+            None => cls(vm),
+        }
+    }
+
+    /// 00FB，MegaChip 模式下滚动彩色画面
+    pub fn mega_scr(vm: &mut Chip8) {
+        match mega_mode(&mut vm.megachip) {
+            Some(megachip) => {
+                megachip.screen.scroll(4, 0);
+                vm.draw_flag = true;
+            }
+            None => s8_scr(vm),
+        }
+    }
+
+    /// 00FC，MegaChip 模式下滚动彩色画面
+    pub fn mega_scl(vm: &mut Chip8) {
+        match mega_mode(&mut vm.megachip) {
+            Some(megachip) => {
+                megachip.screen.scroll(-4, 0);
+                vm.draw_flag = true;
+            }
+            None => s8_scl(vm),
+        }
+    }
+
+    /**
+     * 01NN NNNN
+     * MegaChip: set I to the 24-bit address NN NNNN, then skip the second word.
+     * Fails with `VmError::AddressOutOfRange` beyond the emulated memory.
+     */
+    pub fn mega_ld_i_long(vm: &mut Chip8, ir: &Instruction) {
+        let addr = (ir.kk as u32) << 16 | vm.read_opcode(vm.r_pc) as u32;
+        if addr as usize >= vm.memory.len() {
+            vm.fault = Some(VmError::AddressOutOfRange(addr));
+            return;
+        }
+        vm.r_i = addr;
+        vm.r_pc = vm.r_pc.wrapping_add(2);
+    }
+
+    /**
+     * 02NN
+     * MegaChip: load NN ARGB colors from I into palette entries 1..=NN.
+     */
+    pub fn mega_palette(vm: &mut Chip8, ir: &Instruction) {
+        if let Some(megachip) = vm.megachip.as_deref_mut() {
+            megachip.load_palette(&vm.memory, vm.r_i as usize, ir.kk);
+        }
+    }
+
+    /**
+     * 03NN
+     * MegaChip: set the sprite width to NN (0 means 256).
+     */
+    pub fn mega_sprite_width(vm: &mut Chip8, ir: &Instruction) {
+        if let Some(megachip) = vm.megachip.as_deref_mut() {
+            megachip.sprite_width = ir.kk as u16;
+        }
+    }
+
+    /**
+     * 04NN
+     * MegaChip: set the sprite height to NN (0 means 256).
+     */
+    pub fn mega_sprite_height(vm: &mut Chip8, ir: &Instruction) {
+        if let Some(megachip) = vm.megachip.as_deref_mut() {
+            megachip.sprite_height = ir.kk as u16;
+        }
+    }
+
+    /**
+     * 05NN
+     * MegaChip: set the screen alpha to NN.
+     */
+    pub fn mega_alpha(vm: &mut Chip8, ir: &Instruction) {
+        if let Some(megachip) = vm.megachip.as_deref_mut() {
+            megachip.alpha = ir.kk;
+        }
+    }
+
+    /**
+     * 060N
+     * MegaChip: play the digitised sample at I, looping when N is 0, once when N is 1.
+     */
+    pub fn mega_play(vm: &mut Chip8, ir: &Instruction) {
+        if let Some(megachip) = vm.megachip.as_deref_mut() {
+            megachip.sample.play(&vm.memory, vm.r_i as usize, ir.n == 0);
+        }
+    }
+
+    /**
+     * 0700
+     * MegaChip: stop the digitised sample.
+     */
+    pub fn mega_stop(vm: &mut Chip8) {
+        if let Some(megachip) = vm.megachip.as_deref_mut() {
+            megachip.sample.stop();
+        }
+    }
+
+    /**
+     * 080N
+     * MegaChip: select the sprite blend mode (normal, 25%, 50%, 75%, additive, multiply).
+     */
+    pub fn mega_blend(vm: &mut Chip8, ir: &Instruction) {
+        let Some(blend) = BlendMode::from_n(ir.n) else {
+            unknown(vm, ir);
+            return;
+        };
+        if let Some(megachip) = vm.megachip.as_deref_mut() {
+            megachip.blend = blend;
+        }
+    }
+
+    /**
+     * 09NN
+     * MegaChip: set the palette index that counts as a collision.
+     */
+    pub fn mega_collision(vm: &mut Chip8, ir: &Instruction) {
+        if let Some(megachip) = vm.megachip.as_deref_mut() {
+            megachip.collision_color = ir.kk;
+        }
+    }
+
+    /**
+     * DXYN
+     * In MegaChip mode, blit the sprite at I (one palette index per byte) at (VX, VY) using the sprite
+     * size and blend mode. VF is set to 1 when it covers a pixel of the collision color.
+     */
+    pub fn mega_drw(vm: &mut Chip8, ir: &Instruction) {
+        let x = vm.r_v[ir.x as usize];
+        let y = vm.r_v[ir.y as usize];
+        match mega_mode(&mut vm.megachip) {
+            Some(megachip) => {
+                let collision = megachip.draw_sprite(&vm.memory, vm.r_i as usize, x, y);
+                vm.r_v[0xF] = collision as u8;
+                vm.draw_flag = true;
+            }
+            None if vm.high_res && ir.n == 0 => s8_drw_vx_vy_0(vm, ir),
+            None => drw_vx_vy_n(vm, ir),
+        }
+    }
+
+    /**
+     * 00ED
+     * CHIP-8E: stop the interpreter.
+     */
+    pub fn e_stop(vm: &mut Chip8) {
+        vm.running = false;
+    }
+
+    /**
+     * 0151
+     * CHIP-8E: wait until the delay timer reaches zero.
+     */
+    pub fn e_wait_dt(vm: &mut Chip8) {
+        vm.timer_wait = true;
+    }
+
+    /**
+     * 5XY1
+     * CHIP-8E: skip the next instruction if VX is greater than VY.
+     */
+    pub fn e_sgt_vx_vy(vm: &mut Chip8, ir: &Instruction) {
+        if vm.r_v[ir.x as usize] > vm.r_v[ir.y as usize] {
+            vm.skip_next();
+        }
+    }
+
+    /**
+     * 5XY2
+     * CHIP-8E: save VX through VY to memory starting at I, then advance I past them.
+     */
+    pub fn e_save_vx_vy(vm: &mut Chip8, ir: &Instruction) {
+        xo_save_vx_vy(vm, ir);
+        vm.r_i = vm.i_plus(register_range(ir).count() as u32);
+    }
+
+    /**
+     * 5XY3
+     * CHIP-8E: load VX through VY from memory starting at I, then advance I past them.
+     */
+    pub fn e_load_vx_vy(vm: &mut Chip8, ir: &Instruction) {
+        xo_load_vx_vy(vm, ir);
+        vm.r_i = vm.i_plus(register_range(ir).count() as u32);
+    }
+
+    /**
+     * BBNN
+     * CHIP-8E: branch NN bytes backwards from the following instruction.
+     */
+    pub fn e_jr_back_nn(vm: &mut Chip8, ir: &Instruction) {
+        vm.r_pc = vm.r_pc.wrapping_sub(ir.kk as u16);
+    }
+
+    /**
+     * BFNN
+     * CHIP-8E: branch NN bytes forwards from the following instruction.
+     */
+    pub fn e_jr_forward_nn(vm: &mut Chip8, ir: &Instruction) {
+        vm.r_pc = vm.r_pc.wrapping_add(ir.kk as u16);
+    }
+
+    /**
+     * FX03
+     * CHIP-8E: output VX to I/O port 3.
+     */
+    pub fn e_out_vx(vm: &mut Chip8, ir: &Instruction) {
+        vm.io_output = vm.r_v[ir.x as usize];
+    }
+
+    /**
+     * FX1B
+     * CHIP-8E: skip VX bytes.
+     */
+    pub fn e_skip_vx(vm: &mut Chip8, ir: &Instruction) {
+        vm.r_pc = vm.r_pc.wrapping_add(vm.r_v[ir.x as usize] as u16);
+    }
+
+    /**
+     * FX4F
+     * CHIP-8E: set the delay timer to VX and wait until it reaches zero.
+     */
+    pub fn e_delay_vx(vm: &mut Chip8, ir: &Instruction) {
+        vm.d_timer = vm.r_v[ir.x as usize];
+        vm.timer_wait = true;
+    }
+
+    /**
+     * FXE3 / FXE7
+     * CHIP-8E: load VX from I/O port 3. FXE3 waits for the input strobe on real hardware;
+     * here both read the last value set by `set_io_input`.
+     */
+    pub fn e_in_vx(vm: &mut Chip8, ir: &Instruction) {
+        vm.r_v[ir.x as usize] = vm.io_input;
+    }
 }
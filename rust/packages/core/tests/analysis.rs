@@ -0,0 +1,102 @@
+use std::fs;
+use std::path::PathBuf;
+
+use chip8_core::analysis::{self, Hint};
+use chip8_core::platform::Platform;
+use chip8_core::quirks::Quirks;
+
+mod common;
+
+use common::rom;
+
+#[test]
+fn bundled_roms_are_detected_by_directory() {
+    let roms = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../../assets/roms");
+    for (dir, platform) in [
+        ("chip8", Platform::Chip8),
+        ("chip8/TEST", Platform::Chip8),
+        ("schip8", Platform::SuperChip),
+        ("schip8/TEST", Platform::SuperChip),
+    ] {
+        for entry in fs::read_dir(roms.join(dir)).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|ext| ext != "ch8") {
+                continue;
+            }
+            let analysis = analysis::analyze(&fs::read(&path).unwrap(), 0x200);
+            assert_eq!(analysis.platform, platform, "{:?}", path);
+        }
+    }
+}
+
+#[rustfmt::skip]
+const SKIPS_DATA: [u16; 4] = [
+    0x0123, // 200: SYS 0x123
+    0x1206, // 202: JP 0x206
+    0x00FF, // 204: 数据，不可达
+    0x1206, // 206: JP 0x206
+];
+
+#[test]
+fn only_reachable_code_is_scanned() {
+    let analysis = analysis::analyze(&rom(&SKIPS_DATA), 0x200);
+
+    assert_eq!(
+        analysis.reachable.iter().copied().collect::<Vec<_>>(),
+        [0x200, 0x202, 0x206]
+    );
+    assert!(!analysis.has(Hint::SuperChipOpcode));
+    assert_eq!(analysis.count(Hint::MachineCall), 1);
+    assert_eq!(analysis.platform, Platform::Chip8);
+    assert_eq!(analysis.quirks, Quirks::vip());
+}
+
+#[rustfmt::skip]
+const CLEAR_LOOP: [u16; 6] = [
+    0xA300, // 200: LD I, 0x300
+    0x6000, // 202: LD V0, 0x00
+    0xF055, // 204: LD [I], V0
+    0x7101, // 206: ADD V1, 0x01
+    0x3120, // 208: SE V1, 0x20
+    0x1204, // 20A: JP 0x204
+];
+
+#[test]
+fn store_loop_without_reloading_i_needs_memory_increment() {
+    let analysis = analysis::analyze(&rom(&CLEAR_LOOP), 0x200);
+    assert_eq!(analysis.count(Hint::MemoryIncrement), 1);
+    assert!(analysis.quirks.memory_increment);
+
+    // 循环回到 ANNN 之前，每轮都重新设置 I
+    let mut reload = CLEAR_LOOP;
+    reload[5] = 0x1200;
+    let analysis = analysis::analyze(&rom(&reload), 0x200);
+    assert!(!analysis.has(Hint::MemoryIncrement));
+    assert!(!analysis.quirks.memory_increment);
+}
+
+#[rustfmt::skip]
+const XO_SHIFT: [u16; 7] = [
+    0x8126, // 200: SHR V1, V2
+    0x8106, // 202: SHR V1, V0（CHIP-48 写法，不算）
+    0x3000, // 204: SE V0, 0x00
+    0xF000, // 206: F000 NNNN，跳过时跳过 4 个字节
+    0x0300, // 208
+    0xB210, // 20A: JP V2, 0x210
+    0x120A, // 20C
+];
+
+#[test]
+fn xo_chip_long_load_and_ambiguous_quirks() {
+    let analysis = analysis::analyze(&rom(&XO_SHIFT), 0x200);
+
+    assert_eq!(analysis.platform, Platform::XoChip);
+    assert_eq!(analysis.count(Hint::ShiftUsesVy), 1);
+    assert_eq!(analysis.count(Hint::JumpWithOffset), 1);
+    assert!(analysis.incomplete);
+    // 0x208 是 F000 的操作数，不是指令
+    assert!(!analysis.reachable.contains(&0x208));
+    assert!(analysis.reachable.contains(&0x20A));
+    assert!(!analysis.reachable.contains(&0x20C));
+    assert!(analysis.confidence < 0.75);
+}
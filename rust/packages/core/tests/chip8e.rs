@@ -0,0 +1,93 @@
+use chip8_core::platform::Platform;
+use chip8_core::vm::{StepOutcome, VmError};
+
+mod common;
+
+use common::{load, run};
+
+#[rustfmt::skip]
+const RANGES: [u16; 8] = [
+    0x6005, // 200: LD V0, 0x05
+    0x6103, // 202: LD V1, 0x03
+    0x5011, // 204: SGT V0, V1
+    0x6077, // 206: LD V0, 0x77  被跳过
+    0xA400, // 208: LD I, 0x400
+    0x5012, // 20A: SAVE V0 - V1
+    0xA400, // 20C: LD I, 0x400
+    0x5233, // 20E: LOAD V2 - V3
+];
+
+#[test]
+fn skip_greater_and_register_ranges_advance_i() {
+    let mut vm = load(Platform::Chip8E, &RANGES);
+    run(&mut vm, 3);
+    assert_eq!(vm.r_pc, 0x208);
+    run(&mut vm, 2);
+    assert_eq!(&vm.memory[0x400..0x402], [0x05, 0x03]);
+    assert_eq!(vm.r_i, 0x402);
+    run(&mut vm, 2);
+    assert_eq!(&vm.r_v[2..4], [0x05, 0x03]);
+    assert_eq!(vm.r_i, 0x402);
+
+    // XO-CHIP 上同样的编码不移动 I，也没有 5XY1
+    let mut vm = load(Platform::XoChip, &RANGES[4..6]);
+    run(&mut vm, 2);
+    assert_eq!(vm.r_i, 0x400);
+    let mut vm = load(Platform::XoChip, &[0x5011]);
+    assert_eq!(
+        vm.cycle(),
+        StepOutcome::Fault(VmError::UnknownOpcode(0x5011))
+    );
+}
+
+#[rustfmt::skip]
+const BRANCHES: [u16; 7] = [
+    0xBF04, // 200: JR +4 -> 206
+    0x00E0, // 202
+    0x00ED, // 204: STOP
+    0x6002, // 206: LD V0, 0x02
+    0xF01B, // 208: 跳过 2 字节 -> 20C
+    0x00F2, // 20A: NOP
+    0xBB0A, // 20C: JR -10 -> 204
+];
+
+#[test]
+fn relative_branches_skips_and_stop() {
+    let mut vm = load(Platform::Chip8E, &BRANCHES);
+    run(&mut vm, 1);
+    assert_eq!(vm.r_pc, 0x206);
+    run(&mut vm, 2);
+    assert_eq!(vm.r_pc, 0x20C);
+    run(&mut vm, 1);
+    assert_eq!(vm.r_pc, 0x204);
+    assert_eq!(vm.cycle(), StepOutcome::Halted);
+    assert_eq!(vm.r_pc, 0x206);
+    assert_eq!(vm.cycle(), StepOutcome::Halted);
+
+    // 其它平台上 BBNN 仍然是 BNNN 跳转
+    let mut vm = load(Platform::Vip, &[0xBB0A]);
+    run(&mut vm, 1);
+    assert_eq!(vm.r_pc, 0xB0A);
+}
+
+#[test]
+fn io_port_and_timer_waits() {
+    let mut vm = load(
+        Platform::Chip8E,
+        &[0x6042, 0xF003, 0xF1E7, 0x6002, 0xF04F, 0x6201],
+    );
+    vm.set_io_input(0x17);
+    run(&mut vm, 3);
+    assert_eq!(vm.io_output(), 0x42);
+    assert_eq!(vm.r_v[1], 0x17);
+
+    // FX4F 设置 delay 计时器并等到它归零
+    run(&mut vm, 2);
+    assert_eq!(vm.d_timer, 2);
+    assert_eq!(vm.cycle(), StepOutcome::Idle);
+    vm.ticker();
+    assert_eq!(vm.cycle(), StepOutcome::Idle);
+    vm.ticker();
+    run(&mut vm, 1);
+    assert_eq!(vm.r_v[2], 0x01);
+}
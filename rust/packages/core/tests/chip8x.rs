@@ -0,0 +1,79 @@
+use chip8_core::hardware::{CHIP8X_BACKGROUND, CHIP8X_FOREGROUND};
+use chip8_core::platform::Platform;
+use chip8_core::vm::{StepOutcome, VmError};
+
+mod common;
+
+use common::{load, run};
+
+#[rustfmt::skip]
+const COLOR_BLOCKS: [u16; 6] = [
+    0x6011, // 300: LD V0, 0x11  第 1 列起共 2 列
+    0x6102, // 302: LD V1, 0x02  第 2 块（像素行 8..12）
+    0x6204, // 304: LD V2, 0x04  绿
+    0xB020, // 306: COL V0, V2
+    0x02A0, // 308: 背景切换到蓝
+    0x0000,
+];
+
+#[test]
+fn bxy0_colors_8x4_blocks_and_02a0_cycles_the_background() {
+    let mut vm = load(Platform::Chip8X, &COLOR_BLOCKS);
+    assert_eq!(vm.r_pc, 0x300);
+    run(&mut vm, 5);
+
+    let colors = &vm.screen.colors;
+    for (x, y, color) in [(8, 8, 4), (23, 11, 4), (7, 8, 1), (24, 8, 1), (8, 12, 1)] {
+        assert_eq!(colors.foreground(x, y), color, "({}, {})", x, y);
+    }
+    assert_eq!(vm.pixel_color(8, 8), CHIP8X_BACKGROUND[1]);
+    vm.screen.set_pixel(8, 8, true);
+    assert_eq!(vm.pixel_color(8, 8), CHIP8X_FOREGROUND[4]);
+
+    // 清屏不改变颜色，reset 恢复初始值
+    vm.screen.clear();
+    assert_eq!(vm.screen.colors.foreground(8, 8), 4);
+    vm.reset();
+    assert_eq!(vm.screen.colors.foreground(8, 8), 1);
+    assert_eq!(vm.screen.colors.background, 0);
+}
+
+#[rustfmt::skip]
+const COLOR_ROWS: [u16; 5] = [
+    0x6012, // 300: LD V0, 0x12  像素 18 所在的第 2 列
+    0x6105, // 302: LD V1, 0x05  从像素行 5 开始
+    0x6207, // 304: LD V2, 0x07  白
+    0xB023, // 306: COL V0, V2, 3
+    0x0000,
+];
+
+#[test]
+fn bxyn_colors_n_single_pixel_rows() {
+    let mut vm = load(Platform::Chip8X, &COLOR_ROWS);
+    run(&mut vm, 4);
+
+    let colors = &vm.screen.colors;
+    assert_eq!(colors.foreground(16, 5), 7);
+    assert_eq!(colors.foreground(23, 7), 7);
+    assert_eq!(colors.foreground(16, 8), 1);
+    assert_eq!(colors.foreground(15, 5), 1);
+}
+
+#[test]
+fn io_port_opcodes() {
+    let mut vm = load(Platform::Chip8X, &[0x6042, 0xF0F8, 0xF1FB]);
+    vm.set_io_input(0x17);
+    run(&mut vm, 3);
+    assert_eq!(vm.io_output(), 0x42);
+    assert_eq!(vm.r_v[1], 0x17);
+
+    // 其它平台上没有 I/O 指令，BNNN 仍然是跳转
+    let mut vm = load(Platform::Vip, &[0xF0F8]);
+    assert_eq!(
+        vm.cycle(),
+        StepOutcome::Fault(VmError::UnknownOpcode(0xF0F8))
+    );
+    let mut vm = load(Platform::Vip, &[0xB020]);
+    run(&mut vm, 1);
+    assert_eq!(vm.r_pc, 0x020);
+}
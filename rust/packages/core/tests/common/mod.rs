@@ -0,0 +1,95 @@
+//! 集成测试共用的辅助函数。每个测试文件只用到其中一部分
+
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+
+use chip8_core::platform::Platform;
+use chip8_core::vm::{Chip8, StepOutcome};
+
+/// 比较两种执行方式时每个 ROM 运行的帧数
+pub const FRAMES: usize = 600;
+/// 每隔这么多帧换一个按下的键，让游戏走出标题画面
+const KEY_FRAMES: usize = 20;
+const KEYS: &[char] = &['w', 'q', 'e', 's', 'x', '1', 'v', 'a', 'd', '2'];
+
+/// 把指令按大端序拼成 ROM
+pub fn rom(program: &[u16]) -> Vec<u8> {
+    program.iter().flat_map(|op| op.to_be_bytes()).collect()
+}
+
+/// 默认配置的 VM，`program` 加载在 0x200
+pub fn boot(program: &[u16]) -> Chip8 {
+    let mut vm = Chip8::create();
+    vm.load_rom(&rom(program)).unwrap();
+    vm
+}
+
+/// `platform` 的 VM，`program` 加载在平台的加载地址
+pub fn load(platform: Platform, program: &[u16]) -> Chip8 {
+    let mut vm = Chip8::with_platform(platform);
+    vm.load_rom(&rom(program)).unwrap();
+    vm
+}
+
+/// 执行 `cycles` 条指令，每一条都必须正常执行完
+pub fn run(vm: &mut Chip8, cycles: usize) {
+    for _ in 0..cycles {
+        assert_eq!(vm.cycle(), StepOutcome::Ran);
+    }
+}
+
+/// `assets/roms` 中 CHIP-8 与 SUPER-CHIP 的 ROM，按路径排序
+pub fn bundled_roms() -> Vec<PathBuf> {
+    let roms = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../../assets/roms");
+    let mut paths = vec![];
+    for dir in ["chip8", "chip8/TEST", "schip8", "schip8/TEST"] {
+        for entry in fs::read_dir(roms.join(dir)).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "ch8") {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+    paths
+}
+
+/// 固定随机数种子、按数据库自动配置的 VM，加载 `rom`
+pub fn boot_rom(rom: &[u8]) -> Chip8 {
+    let mut vm = Chip8::create();
+    vm.seed_rng(7);
+    vm.set_auto_configure(true);
+    vm.load_rom(rom).unwrap();
+    vm
+}
+
+/// 每 `KEY_FRAMES` 帧给各台 VM 换一个按下的键
+pub fn press_keys(frame: usize, vms: [&mut Chip8; 2]) {
+    if frame.is_multiple_of(KEY_FRAMES) {
+        let key = KEYS[frame / KEY_FRAMES % KEYS.len()];
+        for vm in vms {
+            vm.keyboard.reset();
+            vm.keyboard.key_down(key);
+        }
+    }
+}
+
+/// 比较两台 VM 中所有会影响后续执行的状态
+pub fn assert_same_state(expected: &Chip8, actual: &Chip8, context: &str) {
+    assert_eq!(expected.r_pc, actual.r_pc, "pc {}", context);
+    assert_eq!(expected.r_v, actual.r_v, "V {}", context);
+    assert_eq!(expected.r_i, actual.r_i, "I {}", context);
+    assert_eq!(expected.r_sp, actual.r_sp, "sp {}", context);
+    assert_eq!(expected.stack, actual.stack, "stack {}", context);
+    assert_eq!(expected.d_timer, actual.d_timer, "delay timer {}", context);
+    assert_eq!(expected.s_timer, actual.s_timer, "sound timer {}", context);
+    assert_eq!(expected.high_res, actual.high_res, "resolution {}", context);
+    assert!(expected.memory == actual.memory, "memory {}", context);
+    assert!(
+        expected.screen.bit_map == actual.screen.bit_map,
+        "screen {}",
+        context
+    );
+}
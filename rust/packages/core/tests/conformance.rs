@@ -0,0 +1,163 @@
+//! 用自带的测试 ROM 做回归测试：无界面运行固定帧数后，将屏幕与 `tests/golden` 下的 PBM 图片比对。
+//!
+//! 修改了预期行为时，使用 `UPDATE_GOLDEN=1 cargo test` 重新生成图片。
+
+use std::{env, fs, path::PathBuf};
+
+use chip8_core::vm::Chip8;
+
+const SEED: u32 = 0xC8;
+const TIMER_HZ: u16 = 60;
+
+fn rom_path(rom: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../../../assets/roms")
+        .join(rom)
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.pbm", name))
+}
+
+fn run(rom: &str, frames: usize) -> Chip8 {
+    let bytes = fs::read(rom_path(rom)).unwrap();
+    let mut vm = Chip8::create();
+    vm.seed_rng(SEED);
+    vm.load_rom(&bytes).unwrap();
+
+    for _ in 0..frames {
+        for _ in 0..vm.rate / TIMER_HZ {
+            vm.cycle();
+        }
+        vm.ticker();
+    }
+    vm
+}
+
+/// 低分辨率下只输出左上角 64x32 的可见区域
+fn to_pbm(vm: &Chip8) -> String {
+    let (columns, rows) = if vm.high_res {
+        (vm.screen.columns, vm.screen.rows)
+    } else {
+        (vm.screen.columns / 2, vm.screen.rows / 2)
+    };
+
+    let mut pbm = format!("P1\n{} {}\n", columns, rows);
+    for y in 0..rows {
+        let line: Vec<&str> = (0..columns)
+            .map(|x| if vm.screen.get_pixel(x, y) { "1" } else { "0" })
+            .collect();
+        pbm.push_str(&line.join(" "));
+        pbm.push('\n');
+    }
+    pbm
+}
+
+fn assert_golden(name: &str, rom: &str, frames: usize) {
+    let actual = to_pbm(&run(rom, frames));
+    let path = golden_path(name);
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("missing golden image {}", path.display()));
+    assert!(
+        expected == actual,
+        "{} does not match {}\n{}",
+        rom,
+        path.display(),
+        actual
+    );
+}
+
+#[test]
+fn ibm_logo() {
+    assert_golden("ibm", "chip8/TEST/IBM.ch8", 60);
+}
+
+#[test]
+fn test_opcode() {
+    assert_golden("test_opcode", "chip8/TEST/test_opcode.ch8", 120);
+}
+
+#[test]
+fn test_bc() {
+    assert_golden("test_bc", "chip8/TEST/test_bc.ch8", 120);
+}
+
+#[test]
+fn c8pic() {
+    assert_golden("c8pic", "chip8/TEST/C8PIC.ch8", 120);
+}
+
+#[test]
+fn x_mirror() {
+    assert_golden("x_mirror", "chip8/TEST/X-MIRROR.ch8", 120);
+}
+
+#[test]
+fn hex_mixt() {
+    assert_golden("hex_mixt", "schip8/TEST/HEX_MIXT.ch8", 120);
+}
+
+#[test]
+fn linedemo() {
+    assert_golden("linedemo", "schip8/TEST/LINEDEMO.ch8", 240);
+}
+
+#[test]
+fn test_128() {
+    assert_golden("test_128", "schip8/TEST/TEST_128.ch8", 240);
+}
+
+#[test]
+fn schip_test() {
+    assert_golden("schip_test", "schip8/TEST/TEST.ch8", 240);
+}
+
+#[test]
+fn robot() {
+    assert_golden("robot", "schip8/TEST/ROBOT.ch8", 240);
+}
+
+#[test]
+fn worms() {
+    assert_golden("worms", "schip8/TEST/WORMS.ch8", 240);
+}
+
+#[test]
+fn bmp_viewer() {
+    assert_golden("bmp_viewer", "schip8/TEST/BMPviewer.ch8", 240);
+}
+
+#[test]
+fn rocket2() {
+    assert_golden("rocket2", "chip8/TEST/Rocket2.ch8", 120);
+}
+
+/// 精灵在第 10 帧前就向右滚出可见区域，取滚动到一半时的画面
+#[test]
+fn scr_test() {
+    assert_golden("scr_test", "schip8/TEST/SCR_TEST.ch8", 6);
+}
+
+/// 用延时计时器数满 3 秒后下滚一行并停机
+#[test]
+fn emutest() {
+    assert_golden("emutest", "schip8/TEST/Emutest.ch8", 240);
+}
+
+#[test]
+fn tapeworm() {
+    assert_golden("tapeworm", "chip8/TEST/TAPEWORM.ch8", 120);
+}
+
+#[test]
+fn timebomb() {
+    assert_golden("timebomb", "chip8/TEST/TIMEBOMB.ch8", 120);
+}
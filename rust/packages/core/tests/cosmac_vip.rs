@@ -0,0 +1,150 @@
+use chip8_core::cdp1802::{Bus, Cdp1802};
+use chip8_core::cosmac_vip::CosmacVip;
+
+struct Ram([u8; 0x100]);
+
+impl Bus for Ram {
+    fn read(&mut self, addr: u16) -> u8 {
+        self.0[addr as usize & 0xff]
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        self.0[addr as usize & 0xff] = value;
+    }
+
+    fn output(&mut self, _port: u8, _value: u8) {}
+
+    fn input(&mut self, _port: u8) -> u8 {
+        0
+    }
+
+    fn flag(&mut self, _flag: u8) -> bool {
+        false
+    }
+}
+
+#[rustfmt::skip]
+const ARITHMETIC: [u8; 20] = [
+    0xF8, 0xF0,       // 00: LDI F0
+    0xFC, 0x20,       // 02: ADI 20      D = 10，DF = 1
+    0x7C, 0x00,       // 04: ADCI 00     D = 11，DF = 0
+    0xBA,             // 06: PHI A
+    0xFF, 0x12,       // 07: SMI 12      D = FF，借位
+    0xC3, 0x00, 0x20, // 09: LBDF 0020   不跳转
+    0xC7,             // 0C: LSNF        跳过下一条
+    0xF8, 0x55,       // 0D: LDI 55
+    0x3B, 0x13,       // 0F: BNF 13
+    0xF8, 0x66,       // 11: LDI 66
+    0x00,             // 13: IDL
+];
+
+#[test]
+fn cpu_arithmetic_branches_and_cycle_counts() {
+    let mut ram = Ram([0; 0x100]);
+    ram.0[..ARITHMETIC.len()].copy_from_slice(&ARITHMETIC);
+    let mut cpu = Cdp1802::new();
+    while !cpu.idle {
+        cpu.step(&mut ram);
+    }
+    assert_eq!(cpu.d, 0xff);
+    assert!(!cpu.df);
+    assert_eq!(cpu.r[0xa] >> 8, 0x11);
+    assert_eq!(cpu.r[0], 0x14);
+    assert_eq!(cpu.cycles, 20);
+    assert_eq!(cpu.step(&mut ram), 0);
+}
+
+/// 与 CHIP-8 解释器开头相同的初始化：RB.1 为显示页，R2 为栈，R1 指向中断程序，
+/// 然后设置计时器、打开蜂鸣器并在显示页写入两行
+#[rustfmt::skip]
+const DISPLAY: [u8; 38] = [
+    0x91,             // 00: GHI 1
+    0xBB,             // 01: PHI B       显示页 0F00
+    0xFF, 0x01,       // 02: SMI 01
+    0xB2,             // 04: PHI 2
+    0xF8, 0xCF,       // 05: LDI CF
+    0xA2,             // 07: PLO 2       R2 = 0ECF
+    0xF8, 0x81,       // 08: LDI 81
+    0xB1,             // 0A: PHI 1
+    0xF8, 0x46,       // 0B: LDI 46
+    0xA1,             // 0D: PLO 1       R1 = 8146
+    0xF8, 0x03,       // 0E: LDI 03
+    0xB8,             // 10: PHI 8       delay = 3
+    0xF8, 0x05,       // 11: LDI 05
+    0xA8,             // 13: PLO 8       sound = 5
+    0x7B,             // 14: SEQ
+    0xF8, 0x0F,       // 15: LDI 0F
+    0xB3,             // 17: PHI 3
+    0xF8, 0x00,       // 18: LDI 00
+    0xA3,             // 1A: PLO 3
+    0xF8, 0xFF,       // 1B: LDI FF
+    0x53,             // 1D: STR 3       M(0F00) = FF
+    0xF8, 0x08,       // 1E: LDI 08
+    0xA3,             // 20: PLO 3
+    0xF8, 0x81,       // 21: LDI 81
+    0x53,             // 23: STR 3       M(0F08) = 81
+    0x30, 0x24,       // 24: BR 24
+];
+
+#[test]
+fn interrupt_routine_shows_display_page_and_counts_down_timers() {
+    let mut vip = CosmacVip::new(&DISPLAY).unwrap();
+    vip.run_frame();
+    assert!(vip.draw_flag);
+    assert!((0..8).all(|x| vip.screen.get_pixel(x, 0)));
+    assert!(!vip.screen.get_pixel(8, 0));
+    assert!(vip.screen.get_pixel(0, 1) && vip.screen.get_pixel(7, 1));
+    assert!(!vip.screen.get_pixel(1, 1));
+    assert_eq!(vip.cpu.r[8], 0x0204);
+    assert!(vip.tone());
+    // 中断程序返回后继续执行主循环
+    assert_eq!(vip.cpu.p, 0);
+    assert!(vip.cpu.ie);
+
+    for _ in 0..4 {
+        vip.run_frame();
+    }
+    assert_eq!(vip.cpu.r[8], 0);
+    assert!(!vip.tone());
+}
+
+#[rustfmt::skip]
+const KEYPAD: [u8; 17] = [
+    0xF8, 0x00,       // 00: LDI 00
+    0xB2,             // 02: PHI 2
+    0xF8, 0x40,       // 03: LDI 40
+    0xA2,             // 05: PLO 2
+    0xE2,             // 06: SEX 2
+    0x61,             // 07: OUT 1       关闭画面与中断
+    0xF8, 0x07,       // 08: LDI 07
+    0x52,             // 0A: STR 2
+    0x62,             // 0B: OUT 2       选中按键 7
+    0x3E, 0x0C,       // 0C: BN3 0C
+    0x7B,             // 0E: SEQ
+    0x30, 0x0F,       // 0F: BR 0F
+];
+
+#[test]
+fn keypad_latch_drives_ef3() {
+    let mut vip = CosmacVip::new(&KEYPAD).unwrap();
+    vip.run_frame();
+    assert!(!vip.display_on());
+    assert!(!vip.tone());
+    vip.keyboard.keys[3] = true;
+    vip.run_frame();
+    assert!(!vip.tone());
+    vip.keyboard.keys[7] = true;
+    vip.run_frame();
+    assert!(vip.tone());
+    assert_eq!(vip.cpu.r[0], 0x0f);
+}
+
+#[test]
+fn rejects_oversized_images() {
+    assert!(CosmacVip::new(&[0; 0x201]).is_err());
+    assert!(CosmacVip::with_ram(&[], 0x1100).is_err());
+    let mut vip = CosmacVip::new(&[]).unwrap();
+    assert!(vip.set_monitor(&[0; 0x100]).is_err());
+    assert!(vip.load_rom(&[0; 0xe00]).is_ok());
+    assert!(vip.load_rom(&[0; 0xe01]).is_err());
+}
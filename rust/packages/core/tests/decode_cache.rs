@@ -0,0 +1,65 @@
+use std::fs;
+
+use chip8_core::vm::{Chip8, StepOutcome};
+
+mod common;
+
+use common::{assert_same_state, boot_rom, bundled_roms, press_keys, FRAMES};
+
+fn boot(rom: &[u8], cached: bool) -> Chip8 {
+    let mut vm = boot_rom(rom);
+    vm.set_decode_cache(cached);
+    vm
+}
+
+#[test]
+fn cached_decode_matches_the_interpreter_on_bundled_roms() {
+    for path in bundled_roms() {
+        let rom = fs::read(&path).unwrap();
+        let mut plain = boot(&rom, false);
+        let mut cached = boot(&rom, true);
+
+        for frame in 0..FRAMES {
+            press_keys(frame, [&mut plain, &mut cached]);
+            for _ in 0..plain.rate / 60 {
+                assert_eq!(plain.cycle(), cached.cycle(), "{:?}", path);
+            }
+            plain.ticker();
+            cached.ticker();
+            assert_same_state(&plain, &cached, &format!("{:?} frame {}", path, frame));
+        }
+    }
+}
+
+#[rustfmt::skip]
+const REWRITE_IN_LOOP: [u16; 7] = [
+    0xA207, // 200: LD I, 0x207，指向 0x206 处指令的低字节
+    0x7001, // 202: ADD V0, 0x01
+    0xF055, // 204: LD [I], V0
+    0x6100, // 206: LD V1, 0x00，每一轮都被改写为 LD V1, V0 的值
+    0x3003, // 208: SE V0, 0x03
+    0x1202, // 20A: JP 0x202
+    0x120C, // 20C: JP 0x20C
+];
+
+#[test]
+fn writes_invalidate_cached_instructions() {
+    let mut vm = boot(&common::rom(&REWRITE_IN_LOOP), true);
+    while vm.cycle() == StepOutcome::Ran {}
+
+    assert_eq!(vm.r_v[0], 3);
+    assert_eq!(vm.r_v[1], 3);
+}
+
+#[test]
+fn direct_memory_edits_need_an_explicit_invalidation() {
+    let mut vm = boot(&[0x61, 0x01, 0x12, 0x00], true);
+    vm.cycle();
+    vm.cycle();
+    assert_eq!(vm.r_v[1], 1);
+
+    vm.memory[0x201] = 0x02;
+    vm.invalidate_decode_cache();
+    vm.cycle();
+    assert_eq!(vm.r_v[1], 2);
+}
@@ -0,0 +1,154 @@
+use chip8_core::config::MachineConfig;
+use chip8_core::font::FontSet;
+use chip8_core::vm::Chip8;
+
+mod common;
+
+const FONT_SETS: [FontSet; 5] = [
+    FontSet::Vip,
+    FontSet::Dream6800,
+    FontSet::Eti660,
+    FontSet::Schip,
+    FontSet::Octo,
+];
+
+fn boot(font_set: FontSet, program: &[u16]) -> Chip8 {
+    let config = MachineConfig {
+        font_set,
+        large_font_base: font_set.large_base(),
+        ..MachineConfig::default()
+    };
+    let mut vm = Chip8::with_config(config).unwrap();
+    vm.load_rom(&common::rom(program)).unwrap();
+    vm
+}
+
+#[test]
+fn default_font_keeps_the_legacy_layout() {
+    let vm = Chip8::create();
+    assert_eq!(vm.config().font_set, FontSet::Schip);
+    assert_eq!(vm.memory[0..5], [0xF0, 0x90, 0x90, 0x90, 0xF0]);
+    assert_eq!(vm.memory[0xA0..0xA2], [0x3C, 0x7E]);
+}
+
+#[test]
+fn fx29_points_at_the_selected_glyphs() {
+    for font_set in FONT_SETS {
+        // 600B: LD V0, 0xB, F029: LD F, V0
+        let mut vm = boot(font_set, &[0x600B, 0xF029]);
+        vm.cycle();
+        vm.cycle();
+
+        let i = vm.r_i as usize;
+        assert_eq!(
+            vm.r_i,
+            u32::from(font_set.small_base() + 0xB * 5),
+            "{:?}",
+            font_set
+        );
+        assert_eq!(vm.r_i, u32::from(vm.config().small_glyph(0xB)));
+        assert_eq!(vm.memory[i..i + 5], font_set.small_glyphs()[55..60]);
+    }
+
+    // 只看 VX 的低 4 位
+    let mut vm = boot(FontSet::Schip, &[0x60FB, 0xF029]);
+    vm.cycle();
+    vm.cycle();
+    assert_eq!(vm.r_i, 0xB * 5);
+
+    let config = MachineConfig {
+        small_font_base: 0xFFF0,
+        ..MachineConfig::default()
+    };
+    assert_eq!(config.small_glyph(0xFF), 0xFFF0_u16.wrapping_add(0xF * 5));
+}
+
+#[test]
+fn fx30_uses_large_glyphs_or_falls_back() {
+    // 600F: LD V0, 0xF, F030: LD HF, V0
+    let mut vm = boot(FontSet::Octo, &[0x600F, 0xF030]);
+    vm.cycle();
+    vm.cycle();
+    assert_eq!(vm.r_i, 0x050 + 0xF * 10);
+    assert_eq!(vm.memory[vm.r_i as usize..][..2], [0xFF, 0xFF]);
+
+    // SUPER-CHIP 只有 0 ~ 9 的大字形
+    let mut vm = boot(FontSet::Schip, &[0x600F, 0xF030]);
+    vm.cycle();
+    vm.cycle();
+    assert_eq!(vm.r_i, 0xF * 5);
+
+    let mut vm = boot(FontSet::Vip, &[0x6003, 0xF030]);
+    vm.cycle();
+    vm.cycle();
+    assert_eq!(vm.r_i, 3 * 5);
+}
+
+#[test]
+fn presets_select_their_fonts() {
+    let vm = Chip8::with_config(MachineConfig::eti660()).unwrap();
+    assert_eq!(vm.memory[5..10], [0x20; 5]);
+    let vm = Chip8::with_config(MachineConfig::xochip()).unwrap();
+    assert_eq!(vm.memory[0x50..0x52], [0xFF, 0xFF]);
+}
+
+#[test]
+fn small_font_base_is_configurable() {
+    let config = MachineConfig {
+        small_font_base: 0x050,
+        ..MachineConfig::vip()
+    };
+    // 6007: LD V0, 7, F029: LD F, V0, F030: LD HF, V0
+    let rom = [0x60, 0x07, 0xF0, 0x29, 0xF0, 0x30];
+    let mut vm = Chip8::with_config(config).unwrap();
+    vm.load_rom(&rom).unwrap();
+    assert_eq!(vm.memory[0x050..0x055], FontSet::Vip.small_glyphs()[..5]);
+    assert!(vm.memory[..0x050].iter().all(|&b| b == 0));
+
+    vm.cycle();
+    vm.cycle();
+    assert_eq!(vm.r_i, 0x050 + 7 * 5);
+    // 没有大字体时 FX30 也使用新的地址
+    vm.cycle();
+    assert_eq!(vm.r_i, 0x050 + 7 * 5);
+
+    let overlapping = MachineConfig {
+        small_font_base: 0x0A0,
+        ..MachineConfig::default()
+    };
+    assert!(Chip8::with_config(overlapping).is_err());
+    let past_program = MachineConfig {
+        small_font_base: 0x1D0,
+        ..MachineConfig::vip()
+    };
+    assert!(Chip8::with_config(past_program).is_err());
+}
+
+#[test]
+fn large_font_base_is_configurable() {
+    let config = MachineConfig {
+        large_font_base: 0x100,
+        ..MachineConfig::xochip()
+    };
+    // 6002: LD V0, 2, F030: LD HF, V0
+    let mut vm = Chip8::with_config(config).unwrap();
+    vm.load_rom(&common::rom(&[0x6002, 0xF030])).unwrap();
+    assert_eq!(vm.memory[0x100..0x10A], FontSet::Octo.large_glyphs()[..10]);
+    assert!(vm.memory[0x050..0x100].iter().all(|&b| b == 0));
+
+    vm.cycle();
+    vm.cycle();
+    assert_eq!(vm.r_i, 0x100 + 2 * 10);
+    assert_eq!(vm.config().font_end(), 0x100 + 160);
+
+    let overlapping = MachineConfig {
+        large_font_base: 0x040,
+        ..MachineConfig::default()
+    };
+    assert!(Chip8::with_config(overlapping).is_err());
+    let past_program = MachineConfig {
+        large_font_base: 0x180,
+        ..MachineConfig::xochip()
+    };
+    assert!(Chip8::with_config(past_program).is_err());
+}
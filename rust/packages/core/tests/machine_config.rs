@@ -0,0 +1,108 @@
+use chip8_core::config::MachineConfig;
+use chip8_core::platform::Platform;
+use chip8_core::vm::{Chip8, MemoryLayout, StepOutcome};
+
+#[test]
+fn default_config_matches_the_vip() {
+    let vm = Chip8::create();
+    assert_eq!(*vm.config(), MachineConfig::default());
+    assert_eq!(vm.memory.len(), 0x1000);
+    assert_eq!(vm.r_pc, 0x200);
+}
+
+#[test]
+fn loads_and_starts_at_the_configured_address() {
+    let mut vm = Chip8::with_config(MachineConfig::eti660()).unwrap();
+    // 6007: LD V0, 7, 1602: JP 0x602
+    vm.load_rom(&[0x60, 0x07, 0x16, 0x02]).unwrap();
+    assert_eq!(vm.memory[0x600..0x604], [0x60, 0x07, 0x16, 0x02]);
+    assert_eq!(vm.cycle(), StepOutcome::Ran);
+    assert_eq!(vm.cycle(), StepOutcome::Halted);
+    assert_eq!(vm.r_v[0], 7);
+
+    // reset 回到配置的入口
+    vm.reset();
+    assert_eq!(vm.r_pc, 0x600);
+
+    let vm = Chip8::with_config(MachineConfig::hires()).unwrap();
+    assert_eq!(vm.r_pc, 0x2C0);
+}
+
+#[test]
+fn load_rom_validates_the_program_space() {
+    let config = MachineConfig::eti660();
+    let mut vm = Chip8::with_config(config).unwrap();
+    assert_eq!(config.program_space(), 0xA00);
+    assert!(vm.load_rom(&vec![0xAA; 0xA01]).is_err());
+    // 失败时不写入任何内容
+    assert!(vm.memory[0x600..].iter().all(|&b| b == 0));
+    assert!(vm.load_rom(&vec![0xAA; 0xA00]).is_ok());
+
+    let mut vm = Chip8::with_config(MachineConfig::xochip()).unwrap();
+    assert_eq!(vm.memory.len(), 0x10000);
+    assert!(vm.load_rom(&vec![0xAA; 0x10000 - 0x200]).is_ok());
+
+    vm.layout = MemoryLayout::Vip;
+    assert!(vm.load_rom(&vec![0xAA; 0x1000]).is_err());
+}
+
+#[test]
+fn rejects_invalid_configs() {
+    let too_small = MachineConfig {
+        memory_size: 0x800,
+        ..MachineConfig::default()
+    };
+    assert!(Chip8::with_config(too_small).is_err());
+
+    let outside = MachineConfig {
+        load_address: 0x1000,
+        ..MachineConfig::default()
+    };
+    assert!(Chip8::with_config(outside).is_err());
+}
+
+#[test]
+fn addresses_wrap_at_the_end_of_64k_memory() {
+    let mut vm = Chip8::with_platform(Platform::XoChip);
+    assert_eq!(vm.memory.len(), 0x10000);
+    #[rustfmt::skip]
+    vm.load_rom(&[
+        0xF0, 0x00, 0xFF, 0xFE, // 200: LD I, 0xFFFE
+        0xF3, 0x55,             // 204: LD [I], V0..V3，I = 0x0002
+        0xF0, 0x00, 0xFF, 0xFF, // 206: LD I, 0xFFFF
+        0x64, 0x03,             // 20A: LD V4, 3
+        0xF4, 0x1E,             // 20C: ADD I, V4
+    ])
+    .unwrap();
+    vm.r_v[..4].copy_from_slice(&[1, 2, 3, 4]);
+    vm.cycle();
+    vm.cycle();
+    assert_eq!(vm.memory[0xFFFE..], [1, 2]);
+    assert_eq!(vm.memory[..2], [3, 4]);
+    assert_eq!(vm.r_i, 0x0002);
+    for _ in 0..3 {
+        vm.cycle();
+    }
+    assert_eq!((vm.r_i, vm.r_v[0xF]), (0x0002, 1));
+
+    // 最后一条指令跨过内存末尾
+    vm.memory[0xFFFF] = 0x65;
+    vm.memory[0] = 0x09;
+    vm.r_pc = 0xFFFF;
+    assert_eq!(vm.cycle(), StepOutcome::Ran);
+    assert_eq!((vm.r_v[5], vm.r_pc), (0x09, 0x0001));
+}
+
+#[test]
+fn pc_wraps_at_the_end_of_4k_memory() {
+    let mut vm = Chip8::create();
+    vm.set_decode_cache(true);
+    // FFE: LD V0, 7
+    vm.memory[0xFFE..].copy_from_slice(&[0x60, 0x07]);
+    vm.memory[..2].copy_from_slice(&[0x61, 0x08]);
+    vm.r_pc = 0xFFE;
+    vm.cycle();
+    assert_eq!(vm.r_pc, 0x1000);
+    vm.cycle();
+    assert_eq!((vm.r_v[0], vm.r_v[1], vm.r_pc), (7, 8, 0x002));
+}
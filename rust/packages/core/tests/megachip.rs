@@ -0,0 +1,157 @@
+use chip8_core::megachip::{BlendMode, MegaScreen};
+use chip8_core::platform::Platform;
+use chip8_core::vm::{Chip8, StepOutcome, VmError};
+
+mod common;
+
+use common::{rom, run};
+
+/// 程序从 0x200 开始，`data` 放在 0x300
+fn load(program: &[u16], data: &[u8]) -> Chip8 {
+    let mut bytes = rom(program);
+    bytes.resize(0x100, 0);
+    bytes.extend(data);
+    let mut vm = Chip8::with_platform(Platform::MegaChip);
+    vm.load_rom(&bytes).unwrap();
+    vm
+}
+
+#[rustfmt::skip]
+const BLIT: [u16; 13] = [
+    0x0011,         // 200: 进入 MegaChip 模式
+    0x0100, 0x0300, // 202: LDHI I, 0x000300
+    0x0201,         // 206: 从 I 载入 1 个颜色
+    0x0100, 0x0304, // 208: LDHI I, 0x000304
+    0x0302,         // 20C: 精灵宽 2
+    0x0401,         // 20E: 精灵高 1
+    0x0901,         // 210: 碰撞颜色 1
+    0x6010,         // 212: LD V0, 0x10
+    0xD000,         // 214: DRW V0, V0
+    0xD000,         // 216: DRW V0, V0
+    0x0010,         // 218: 退出 MegaChip 模式
+];
+
+#[test]
+fn blits_palette_sprites_with_collision() {
+    let mut vm = load(&BLIT, &[0xFF, 0x11, 0x22, 0x33, 0x01, 0x00]);
+    run(&mut vm, 9);
+    assert_eq!(vm.framebuffer_size(), (256, 192));
+    assert_eq!(vm.r_v[0xF], 0);
+    assert_eq!(vm.pixel_color(16, 16), [0x11, 0x22, 0x33]);
+    // 下标 0 是透明的
+    assert_eq!(vm.pixel_color(17, 16), [0, 0, 0]);
+
+    run(&mut vm, 1);
+    assert_eq!(vm.r_v[0xF], 1);
+    let megachip = vm.megachip().unwrap();
+    assert_eq!(megachip.screen.indices[16 * 256 + 16], 1);
+    assert_eq!(megachip.palette[1], [0x11, 0x22, 0x33, 0xFF]);
+
+    run(&mut vm, 1);
+    assert_eq!(vm.framebuffer_size(), (64, 32));
+    assert!(Chip8::with_platform(Platform::SuperChip)
+        .megachip()
+        .is_none());
+}
+
+#[test]
+fn blend_modes_and_scrolling() {
+    let src = [0xFF, 0x80, 0x00, 0x40];
+    let dst = [0x00, 0x80, 0xFF, 0xFF];
+    assert_eq!(BlendMode::Normal.blend(src, dst), src);
+    assert_eq!(BlendMode::Alpha50.blend(src, dst), [0x7F, 0x80, 0x7F, 0xFF]);
+    assert_eq!(BlendMode::Alpha25.blend(src, dst), [0x3F, 0x80, 0xBF, 0xFF]);
+    assert_eq!(BlendMode::Add.blend(src, dst), [0xFF, 0xFF, 0xFF, 0xFF]);
+    assert_eq!(
+        BlendMode::Multiply.blend(src, dst),
+        [0x00, 0x40, 0x00, 0xFF]
+    );
+    assert_eq!(BlendMode::from_n(6), None);
+
+    let mut screen = MegaScreen::new();
+    screen.indices[0] = 3;
+    screen.rgba[0] = [1, 2, 3, 0xFF];
+    screen.scroll(4, 2);
+    assert_eq!(screen.indices[2 * 256 + 4], 3);
+    assert_eq!(screen.get(4, 2), [1, 2, 3, 0xFF]);
+    assert_eq!(screen.indices[0], 0);
+    screen.scroll(-4, -2);
+    assert_eq!(screen.indices[0], 3);
+}
+
+#[rustfmt::skip]
+const SAMPLE: [u16; 5] = [
+    0x0100, 0x0300, // 200: LDHI I, 0x000300
+    0x0601,         // 204: 播放一次
+    0x0600,         // 206: 循环播放
+    0x0700,         // 208: 停止
+];
+
+#[test]
+fn plays_digitised_samples() {
+    // 4000Hz，3 个采样
+    let header = [0x0F, 0xA0, 0x00, 0x00, 0x03, 0x00];
+    let data: Vec<u8> = header.into_iter().chain([0x10, 0x20, 0x30]).collect();
+    let mut vm = load(&SAMPLE, &data);
+    let mut out = [0u8; 8];
+    assert!(!vm.fill_sample_audio(8000, &mut out));
+
+    run(&mut vm, 2);
+    assert!(vm.fill_sample_audio(8000, &mut out));
+    assert_eq!(out, [0x10, 0x10, 0x20, 0x20, 0x30, 0x30, 0x80, 0x80]);
+    assert!(!vm.megachip().unwrap().sample.playing);
+
+    run(&mut vm, 1);
+    vm.fill_sample_audio(4000, &mut out);
+    assert_eq!(out, [0x10, 0x20, 0x30, 0x10, 0x20, 0x30, 0x10, 0x20]);
+
+    run(&mut vm, 1);
+    assert!(!vm.fill_sample_audio(4000, &mut out));
+}
+
+#[rustfmt::skip]
+const HIGH_DATA: [u16; 12] = [
+    0x0011,         // 200: 进入 MegaChip 模式
+    0x0101, 0x0000, // 202: LDHI I, 0x010000
+    0x0201,         // 206: 从 I 载入 1 个颜色
+    0x0101, 0x0004, // 208: LDHI I, 0x010004
+    0x0301,         // 20C: 精灵宽 1
+    0x0401,         // 20E: 精灵高 1
+    0x6010,         // 210: LD V0, 0x10
+    0xD000,         // 212: DRW V0, V0
+    0xF155,         // 214: LD [I], V1
+    0x0580,         // 216: 画面透明度 0x80
+];
+
+#[test]
+fn reaches_data_beyond_64k() {
+    // 数据放在 0x10000，ROM 超过了 64 KiB
+    let mut bytes = rom(&HIGH_DATA);
+    bytes.resize(0x10000 - 0x200, 0);
+    bytes.extend([0xFF, 0x11, 0x22, 0x33, 0x01]);
+    let mut vm = Chip8::with_platform(Platform::MegaChip);
+    vm.load_rom(&bytes).unwrap();
+
+    run(&mut vm, 8);
+    assert_eq!(vm.r_i, 0x010004);
+    assert_eq!(vm.pixel_color(16, 16), [0x11, 0x22, 0x33]);
+
+    vm.r_v[1] = 0x42;
+    run(&mut vm, 1);
+    assert_eq!(vm.memory[0x10004..0x10006], [0x10, 0x42]);
+    assert_eq!(vm.r_i, 0x010004);
+
+    // 画面按透明度叠在黑色背景上
+    run(&mut vm, 1);
+    assert_eq!(vm.pixel_color(16, 16), [0x08, 0x11, 0x19]);
+}
+
+#[test]
+fn megachip_opcodes_need_the_megachip_platform() {
+    let mut vm = Chip8::with_platform(Platform::SuperChip);
+    vm.load_rom(&[0x03, 0x10]).unwrap();
+    assert_eq!(
+        vm.cycle(),
+        StepOutcome::Fault(VmError::UnknownOpcode(0x0310))
+    );
+}
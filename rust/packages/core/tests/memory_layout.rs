@@ -0,0 +1,92 @@
+use chip8_core::vm::{Chip8, MemoryLayout, StepOutcome, VIP_DISPLAY, VIP_STACK};
+
+mod common;
+
+fn boot(layout: MemoryLayout, program: &[u16]) -> Chip8 {
+    let mut vm = Chip8::create();
+    vm.layout = layout;
+    vm.reset();
+    vm.load_rom(&common::rom(program)).unwrap();
+    vm
+}
+
+#[rustfmt::skip]
+const PATCH_RETURN: [u16; 9] = [
+    0x2208, // 200: CALL 0x208
+    0x6001, // 202: LD V0, 1
+    0x1204, // 204: JP 0x204
+    0x1206, // 206: JP 0x206
+    0x6002, // 208: LD V0, 2
+    0x6106, // 20A: LD V1, 6
+    0xAECE, // 20C: LD I, 0xECE
+    0xF155, // 20E: LD [I], V1
+    0x00EE, // 210: RET
+];
+
+#[test]
+fn vip_stack_lives_in_memory() {
+    let mut vm = boot(MemoryLayout::Vip, &PATCH_RETURN);
+    vm.cycle();
+    let top = VIP_STACK.end as usize - 2;
+    assert_eq!(top, 0xECE);
+    assert_eq!(vm.memory[top..top + 2], [0x02, 0x02]);
+
+    // 子程序把返回地址改写成 0x206
+    while vm.cycle() != StepOutcome::Halted {}
+    assert_eq!(vm.r_pc, 0x206);
+    assert_eq!(vm.r_v[0], 2);
+}
+
+#[test]
+fn flat_stack_is_outside_memory() {
+    let mut vm = boot(MemoryLayout::Flat, &PATCH_RETURN);
+    vm.cycle();
+    assert_eq!(vm.memory[0xECE..0xED0], [0, 0]);
+
+    while vm.cycle() != StepOutcome::Halted {}
+    assert_eq!(vm.r_pc, 0x204);
+    assert_eq!(vm.r_v[0], 1);
+}
+
+#[test]
+fn vip_display_lives_in_memory() {
+    #[rustfmt::skip]
+    let mut vm = boot(MemoryLayout::Vip, &[
+        0xA20C, // 200: LD I, 0x20C
+        0x6004, // 202: LD V0, 4
+        0xD001, // 204: DRW V0, V0, 1
+        0xAF00, // 206: LD I, 0xF00
+        0xF055, // 208: LD [I], V0
+        0x00E0, // 20A: CLS
+        0xF000, // 20C: sprite
+    ]);
+    let display = VIP_DISPLAY as usize;
+
+    for _ in 0..3 {
+        vm.cycle();
+    }
+    assert_eq!(vm.memory[display + 4 * 8], 0x0F);
+    assert!(vm.screen.get_pixel(4, 4) && vm.screen.get_pixel(7, 4));
+    assert!(!vm.screen.get_pixel(8, 4));
+
+    // 直接写显示缓冲区，screen 同步更新
+    for _ in 0..2 {
+        vm.cycle();
+    }
+    assert_eq!(vm.memory[display], 0x04);
+    assert!(vm.screen.get_pixel(5, 0));
+    assert!(!vm.screen.get_pixel(4, 0));
+
+    vm.cycle();
+    assert!(vm.memory[display..display + 0x100].iter().all(|&b| b == 0));
+    assert!(!vm.screen.bit_map.iter().any(|&on| on));
+}
+
+#[test]
+fn vip_layout_rejects_roms_reaching_the_stack() {
+    let mut vm = Chip8::create();
+    vm.layout = MemoryLayout::Vip;
+    let rom = vec![0; (VIP_STACK.start - 0x200) as usize + 1];
+    assert!(vm.load_rom(&rom).is_err());
+    assert!(vm.load_rom(&rom[1..]).is_ok());
+}
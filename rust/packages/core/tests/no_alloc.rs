@@ -0,0 +1,107 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::fs;
+use std::path::PathBuf;
+
+use chip8_core::hardware::{Memory, MAX_MEMORY_SIZE};
+use chip8_core::platform::Platform;
+use chip8_core::rng::RandomSource;
+use chip8_core::vm::{Chip8, MAX_STACK_DEPTH};
+
+/// 统计当前线程上的分配次数，测试并行运行时互不干扰
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+#[test]
+fn running_bundled_roms_does_not_allocate() {
+    let roms = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../../assets/roms/chip8");
+    for entry in fs::read_dir(roms).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|ext| ext != "ch8") {
+            continue;
+        }
+        let rom = fs::read(&path).unwrap();
+        let mut vm = Chip8::create();
+        vm.seed_rng(7);
+        vm.load_rom(&rom).unwrap();
+
+        let before = allocations();
+        for frame in 0..300 {
+            vm.keyboard.reset();
+            vm.keyboard.key_down(['w', 's', 'q', 'e'][frame / 30 % 4]);
+            for _ in 0..vm.rate / 60 {
+                vm.cycle();
+            }
+            vm.ticker();
+        }
+        let allocated = allocations() - before;
+        assert_eq!(allocated, 0, "{:?}: {} allocations", path, allocated);
+    }
+}
+
+#[derive(Debug)]
+struct Constant(u8);
+
+impl RandomSource for Constant {
+    fn next_u8(&mut self) -> u8 {
+        self.0
+    }
+}
+
+#[test]
+fn custom_random_source_drives_cxnn() {
+    let mut vm = Chip8::create();
+    vm.set_rng(Constant(0xA5));
+    vm.load_rom(&[0xC0, 0xFF, 0xC1, 0x0F]).unwrap();
+    vm.cycle();
+    vm.cycle();
+    assert_eq!((vm.r_v[0], vm.r_v[1]), (0xA5, 0x05));
+}
+
+#[test]
+fn stack_depth_is_bounded_by_the_fixed_stack() {
+    let mut vm = Chip8::create();
+    vm.set_stack_depth(40);
+    assert_eq!(vm.stack_depth(), MAX_STACK_DEPTH);
+    assert_eq!(vm.stack.len(), MAX_STACK_DEPTH as usize);
+
+    vm.set_stack_depth(2);
+    assert_eq!(vm.stack_depth(), 2);
+}
+
+#[test]
+fn memory_is_sized_to_the_platform() {
+    // 4 KiB 的内存内联在 VM 中，更大的内存不占 VM 本身的空间
+    assert!(size_of::<Memory>() < 0x1000 + 64);
+    assert!(size_of::<Chip8>() < 16 * 1024, "{}", size_of::<Chip8>());
+
+    for (platform, len) in [
+        (Platform::Chip8, 0x1000),
+        (Platform::XoChip, 0x10000),
+        (Platform::MegaChip, MAX_MEMORY_SIZE),
+    ] {
+        let vm = Chip8::with_platform(platform);
+        assert_eq!(vm.memory.len(), len, "{}", platform.name());
+    }
+}
@@ -0,0 +1,148 @@
+use chip8_core::platform::Platform;
+use chip8_core::vm::{Chip8, StepOutcome, VmError};
+
+mod common;
+
+use common::load;
+
+#[test]
+fn opcodes_outside_the_platform_are_unknown() {
+    // 00FF 只在有 SUPER-CHIP 扩展的平台上存在
+    for platform in Platform::ALL {
+        let mut vm = load(platform, &[0x00FF]);
+        // HIRES CHIP-8 的入口不是加载地址
+        vm.r_pc = vm.config().load_address;
+        let outcome = vm.cycle();
+        if matches!(
+            platform,
+            Platform::Vip
+                | Platform::HiresChip8
+                | Platform::Chip10
+                | Platform::Chip8E
+                | Platform::Chip8X
+                | Platform::Chip48
+        ) {
+            assert_eq!(
+                outcome,
+                StepOutcome::Fault(VmError::UnknownOpcode(0x00FF)),
+                "{}",
+                platform.name()
+            );
+        } else {
+            assert_eq!(outcome, StepOutcome::Ran, "{}", platform.name());
+            assert!(vm.high_res);
+        }
+    }
+
+    // 8XY8 在任何平台上都不是指令，出错时 pc 停在这条指令上
+    let mut vm = load(Platform::Chip8, &[0x6001, 0x8018]);
+    vm.cycle();
+    assert_eq!(
+        vm.cycle(),
+        StepOutcome::Fault(VmError::UnknownOpcode(0x8018))
+    );
+    assert_eq!(vm.r_pc, 0x202);
+    assert_eq!(
+        VmError::UnknownOpcode(0x8018).to_string(),
+        "unknown opcode 8018"
+    );
+}
+
+#[test]
+fn platforms_bundle_memory_map_display_and_timing() {
+    let vip = Chip8::with_platform(Platform::Vip);
+    assert_eq!(vip.stack_depth(), 12);
+    assert_eq!(vip.rate, 480);
+    assert_eq!(vip.display_size(), (64, 32));
+
+    let xo = Chip8::with_platform(Platform::XoChip);
+    assert_eq!(xo.memory.len(), 0x10000);
+    assert_eq!(xo.rate, 6000);
+
+    // HIRES CHIP-8 从 0x2C0 开始执行，0230 清屏
+    let mut rom = vec![0u8; 0xC0];
+    rom.extend([0x60, 0x00, 0xF0, 0x29, 0xD0, 0x05, 0x02, 0x30]);
+    let mut hires = Chip8::with_platform(Platform::HiresChip8);
+    hires.load_rom(&rom).unwrap();
+    assert_eq!(hires.r_pc, 0x2C0);
+    assert_eq!(hires.display_size(), (64, 64));
+    for _ in 0..3 {
+        hires.cycle();
+    }
+    assert!(hires.screen.get_pixel(0, 0));
+    // VIP 画完精灵后等待 vblank
+    hires.ticker();
+    assert_eq!(hires.cycle(), StepOutcome::Ran);
+    assert!(!hires.screen.get_pixel(0, 0));
+}
+
+#[rustfmt::skip]
+const XO_LONG_LOAD: [u16; 9] = [
+    0x6000, // 200: LD V0, 0x00
+    0x3000, // 202: SE V0, 0x00
+    0xF000, // 204: LD I, long 0x0400
+    0x0400,
+    0x6101, // 208: LD V1, 0x01
+    0x6202, // 20A: LD V2, 0x02
+    0x5122, // 20C: SAVE V1 - V2
+    0x5313, // 20E: LOAD V3 - V1
+    0x0000,
+];
+
+#[test]
+fn xochip_long_load_and_register_ranges() {
+    let mut vm = load(Platform::XoChip, &XO_LONG_LOAD);
+    vm.cycle();
+    // 跳过 4 字节的 F000 NNNN
+    vm.cycle();
+    assert_eq!(vm.r_pc, 0x208);
+
+    let mut vm = load(Platform::XoChip, &XO_LONG_LOAD[2..]);
+    vm.cycle();
+    assert_eq!((vm.r_i, vm.r_pc), (0x0400, 0x204));
+    for _ in 0..4 {
+        vm.cycle();
+    }
+    assert_eq!(&vm.memory[0x400..0x402], [0x01, 0x02]);
+    // X 大于 Y 时倒序：V3 = [I]，V2 = [I + 1]，V1 = [I + 2]
+    assert_eq!(&vm.r_v[1..4], [0x00, 0x02, 0x01]);
+    assert_eq!(vm.r_i, 0x0400);
+}
+
+#[test]
+fn xochip_opcodes_need_the_xochip_platform() {
+    let mut vm = load(Platform::SuperChip, &[0xF000, 0x0400]);
+    assert_eq!(
+        vm.cycle(),
+        StepOutcome::Fault(VmError::UnknownOpcode(0xF000))
+    );
+    let mut vm = load(Platform::Chip8, &[0x5122]);
+    assert_eq!(
+        vm.cycle(),
+        StepOutcome::Fault(VmError::UnknownOpcode(0x5122))
+    );
+}
+
+#[test]
+fn zero_zero_one_n_depends_on_the_platform() {
+    // 默认的 CHIP-8 上 0010~001F 都是退出
+    for code in [0x0010, 0x0011, 0x001F] {
+        let mut vm = load(Platform::Chip8, &[code]);
+        assert_eq!(vm.cycle(), StepOutcome::Halted, "{:04X}", code);
+        assert_eq!(vm.exit_code(), Some((code & 0xF) as u8));
+    }
+
+    // MegaChip 上 0011 打开 MegaChip 模式，0012 不是指令
+    let mut vm = load(Platform::MegaChip, &[0x0011, 0x0012]);
+    assert_eq!(vm.cycle(), StepOutcome::Ran);
+    assert_eq!(vm.exit_code(), None);
+    assert_eq!(
+        vm.cycle(),
+        StepOutcome::Fault(VmError::UnknownOpcode(0x0012))
+    );
+
+    // VIP 上是被忽略的机器码调用
+    let mut vm = load(Platform::Vip, &[0x0011]);
+    assert_eq!(vm.cycle(), StepOutcome::Ran);
+    assert_eq!(vm.exit_code(), None);
+}
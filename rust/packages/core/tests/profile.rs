@@ -0,0 +1,74 @@
+use chip8_core::profile::{HotLoop, Profiler, FAMILIES};
+use chip8_core::vm::{Chip8, StepOutcome};
+
+mod common;
+
+#[rustfmt::skip]
+const PROGRAM: [u16; 9] = [
+    0x6003, // 200: LD V0, 3
+    0xA20E, // 202: LD I, 0x20E
+    0xD001, // 204: DRW V0, V0, 1
+    0x70FF, // 206: ADD V0, 0xFF
+    0x3000, // 208: SE V0, 0
+    0x1204, // 20A: JP 0x204
+    0x120C, // 20C: JP 0x20C
+    0x8000, // 20E: sprite
+    0x6001, // 210: never executed
+];
+
+fn profile() -> (Chip8, Profiler) {
+    let mut vm = common::boot(&PROGRAM);
+    vm.set_profiler(Profiler::new(vm.memory.len()));
+    while vm.cycle() != StepOutcome::Halted {}
+    let profiler = vm.take_profiler().unwrap();
+    (vm, profiler)
+}
+
+#[test]
+fn counts_executions_families_and_data_reads() {
+    let (_, profiler) = profile();
+    assert_eq!(profiler.instructions(), 14);
+    assert_eq!(profiler.exec_count(0x200), 1);
+    assert_eq!(profiler.exec_count(0x204), 3);
+    assert_eq!(profiler.exec_count(0x210), 0);
+    assert_eq!(profiler.read_count(0x20E), 3);
+    assert_eq!(profiler.hot_spots(1), [(0x204, 3)]);
+
+    let dxyn = FAMILIES.iter().position(|name| *name == "DXYN").unwrap();
+    assert_eq!(profiler.family_counts()[dxyn], 3);
+    assert_eq!(profiler.family_counts().iter().sum::<u64>(), 14);
+}
+
+#[test]
+fn finds_hot_loops_and_unexecuted_code() {
+    let (_, profiler) = profile();
+    assert_eq!(
+        profiler.hot_loops(),
+        [
+            HotLoop {
+                start: 0x204,
+                end: 0x20A,
+                iterations: 2
+            },
+            HotLoop {
+                start: 0x20C,
+                end: 0x20C,
+                iterations: 1
+            },
+        ]
+    );
+    let unexecuted = profiler.unexecuted(0x200..0x212);
+    assert_eq!(unexecuted.len(), 1);
+    assert_eq!(unexecuted[0], 0x20F..0x212);
+}
+
+#[test]
+fn listing_maps_coverage_to_disassembly() {
+    let (vm, profiler) = profile();
+    let listing = profiler.listing(&vm.memory, 0x200..0x212);
+    let lines: Vec<&str> = listing.lines().collect();
+    assert_eq!(lines[2], "     3  0204  D001  DRW V0, V0, 1");
+    assert_eq!(lines[7], "  data  020E  80    DB 0x80  ; read 3");
+    assert_eq!(lines[8], "     -  020F  00    DB 0x00  ; read 0");
+    assert_eq!(lines[9], "     -  0210  6001  LD V0, 0x01");
+}
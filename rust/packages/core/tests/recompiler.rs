@@ -0,0 +1,144 @@
+use std::fs;
+
+use chip8_core::recompiler::{IrOp, Recompiler};
+use chip8_core::vm::{Backend, Interpreter, StepOutcome};
+
+mod common;
+
+use common::{assert_same_state, boot_rom, bundled_roms, press_keys, rom, FRAMES};
+
+/// 分别用解释器和重编译器运行 `rom`，每帧比较执行结果与 VM 状态
+fn assert_backends_agree(rom: &[u8], name: &str) {
+    let mut plain = boot_rom(rom);
+    let mut compiled = boot_rom(rom);
+    let mut interpreter = Interpreter;
+    let mut recompiler = Recompiler::new();
+
+    for frame in 0..FRAMES {
+        press_keys(frame, [&mut plain, &mut compiled]);
+        let cycles = (plain.rate / 60) as u32;
+        assert_eq!(
+            interpreter.run(&mut plain, cycles),
+            recompiler.run(&mut compiled, cycles),
+            "{} frame {}",
+            name,
+            frame
+        );
+        plain.ticker();
+        compiled.ticker();
+        assert_same_state(&plain, &compiled, &format!("{} frame {}", name, frame));
+    }
+}
+
+#[test]
+fn recompiler_matches_the_interpreter_on_bundled_roms() {
+    for path in bundled_roms() {
+        assert_backends_agree(&fs::read(&path).unwrap(), &format!("{:?}", path));
+    }
+}
+
+/// 跳到 0xFF + 0xFFF = 0x10FE，超出 4 KiB 内存，取指时绕回 0x0FE
+const JUMP_PAST_MEMORY: [u16; 2] = [0x60FF, 0xBFFF];
+
+/// 从 0xFF8 顺序执行到内存末尾之后，绕回 0x000 继续执行
+fn fall_off_memory() -> Vec<u8> {
+    let mut program = vec![0x1FF8; (0xFF8 - 0x200) / 2];
+    program.extend([0x7001, 0x7101, 0x7201, 0x7301]);
+    rom(&program)
+}
+
+#[test]
+fn recompiler_wraps_pc_past_the_end_of_memory() {
+    assert_backends_agree(&rom(&JUMP_PAST_MEMORY), "JUMP_PAST_MEMORY");
+    assert_backends_agree(&fall_off_memory(), "fall_off_memory");
+
+    let mut vm = boot_rom(&rom(&JUMP_PAST_MEMORY));
+    assert_eq!(Recompiler::new().run(&mut vm, 2), StepOutcome::Ran);
+    assert_eq!(vm.r_pc, 0x10FE);
+}
+
+#[rustfmt::skip]
+const CONSTANTS: [u16; 7] = [
+    0x6005, // 200: LD V0, 0x05，被 ADD 折叠
+    0x7003, // 202: ADD V0, 0x03
+    0x8100, // 204: LD V1, V0 -> V1 = 8
+    0x6007, // 206: LD V0, 0x07
+    0xA300, // 208: LD I, 0x300，被下一条覆盖
+    0xA310, // 20A: LD I, 0x310
+    0x120C, // 20C: JP 0x20C，块尾
+];
+
+#[test]
+fn folds_constant_loads_and_drops_dead_stores() {
+    let mut vm = boot_rom(&rom(&CONSTANTS));
+    let mut recompiler = Recompiler::new();
+    assert_eq!(recompiler.run(&mut vm, 100), StepOutcome::Halted);
+
+    let block = recompiler.block(0x200).unwrap();
+    assert_eq!(block.len(), 6);
+    assert_eq!(
+        block.ir(),
+        [
+            IrOp::SetReg { x: 1, value: 8 },
+            IrOp::SetReg { x: 0, value: 7 },
+            IrOp::SetI(0x310),
+        ]
+    );
+    assert_eq!((vm.r_v[0], vm.r_v[1], vm.r_i), (7, 8, 0x310));
+}
+
+#[rustfmt::skip]
+const REWRITE_IN_LOOP: [u16; 7] = [
+    0xA203, // 200: LD I, 0x203，指向 0x202 处指令的低字节
+    0x6100, // 202: LD V1, 0x00，之后每一轮都被改写为 LD V1, V0 的值
+    0x7001, // 204: ADD V0, 0x01
+    0xF055, // 206: LD [I], V0，块尾
+    0x3003, // 208: SE V0, 0x03
+    0x1202, // 20A: JP 0x202
+    0x120C, // 20C: JP 0x20C
+];
+
+#[test]
+fn writes_invalidate_compiled_blocks() {
+    let mut vm = boot_rom(&rom(&REWRITE_IN_LOOP));
+    let mut recompiler = Recompiler::new();
+    assert_eq!(recompiler.run(&mut vm, 100), StepOutcome::Halted);
+
+    assert_eq!((vm.r_v[0], vm.r_v[1]), (3, 2));
+    // 被改写的指令交给解释器执行，自修改代码照常记录
+    assert_eq!(vm.modified_code().len(), 1);
+    assert!(recompiler.compiled() > recompiler.block_count() as u64);
+
+    // 重新加载 ROM 后丢弃所有块。块尾的 JP 0x20C 随块执行，不单独编译
+    vm.reset();
+    vm.load_rom(&rom(&CONSTANTS)).unwrap();
+    recompiler.run(&mut vm, 100);
+    assert_eq!(recompiler.block_count(), 1);
+    assert_eq!(recompiler.block(0x200).map(|b| b.len()), Some(6));
+}
+
+#[rustfmt::skip]
+const REWRITE_TAIL: [u16; 10] = [
+    0xA207, // 200: LD I, 0x207，指向块尾的低字节
+    0x6103, // 202: LD V1, 0x03
+    0x7001, // 204: ADD V0, 0x01
+    0x3001, // 206: SE V0, 0x01，块尾，第二轮前被改写为 SE V0, 0x03
+    0x1212, // 208: JP 0x212
+    0x8010, // 20A: LD V0, V1
+    0xF055, // 20C: LD [I], V0
+    0x6000, // 20E: LD V0, 0x00
+    0x1204, // 210: JP 0x204
+    0x1212, // 212: JP 0x212
+];
+
+#[test]
+fn writes_to_a_block_tail_invalidate_it() {
+    let mut vm = boot_rom(&rom(&REWRITE_TAIL));
+    let mut recompiler = Recompiler::new();
+    assert_eq!(recompiler.run(&mut vm, 100), StepOutcome::Halted);
+
+    assert_eq!((vm.r_pc, vm.r_v[0]), (0x212, 1));
+    assert_eq!(vm.memory[0x207], 0x03);
+    let modified: Vec<u16> = vm.modified_code().iter().map(|code| code.addr).collect();
+    assert_eq!(modified, [0x206]);
+}
@@ -0,0 +1,135 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+use chip8_core::hash;
+use chip8_core::platform::Platform;
+use chip8_core::quirks::Quirks;
+use chip8_core::romdb;
+use chip8_core::vm::Chip8;
+
+fn roms_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../../assets/roms")
+}
+
+fn read_rom(path: &str) -> Vec<u8> {
+    fs::read(roms_dir().join(path)).unwrap()
+}
+
+#[test]
+fn every_bundled_rom_is_in_the_database() {
+    let mut seen = HashSet::new();
+    for (dir, platform) in [
+        ("chip8", Platform::Chip8),
+        ("chip8/TEST", Platform::Chip8),
+        ("schip8", Platform::SuperChip),
+        ("schip8/TEST", Platform::SuperChip),
+    ] {
+        for entry in fs::read_dir(roms_dir().join(dir)).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|ext| ext != "ch8") {
+                continue;
+            }
+            let sha1 = hash::sha1_hex(&fs::read(&path).unwrap());
+            let info = romdb::lookup(&sha1).unwrap_or_else(|| panic!("{:?} missing", path));
+            assert_eq!(info.platform, platform, "{:?}", path);
+            seen.insert(sha1);
+        }
+    }
+    assert_eq!(seen.len(), romdb::all().len());
+}
+
+#[test]
+fn load_rom_reports_database_entry() {
+    let mut vm = Chip8::create();
+    vm.load_rom(&read_rom("schip8/JOUST23.ch8")).unwrap();
+
+    let info = vm.rom_info().unwrap();
+    assert_eq!(info.title, "Joust");
+    assert_eq!(info.authors, ["Erin S. Catto"]);
+    assert_eq!(info.platform, Platform::SuperChip);
+    // 未开启自动配置时不修改 VM
+    assert_eq!(vm.quirks, Quirks::default());
+    assert_eq!(vm.rate, 480);
+
+    vm.reset();
+    assert!(vm.rom_info().is_none());
+    vm.load_rom(&[0x12, 0x00]).unwrap();
+    assert!(vm.rom_info().is_none());
+}
+
+#[test]
+fn auto_configure_applies_quirks_rate_and_keymap() {
+    let mut vm = Chip8::create();
+    vm.set_auto_configure(true);
+    vm.load_rom(&read_rom("schip8/ALIEN.ch8")).unwrap();
+
+    assert_eq!(vm.quirks, Quirks::schip());
+    assert_eq!(vm.rate, 30 * 60);
+    // ALIEN.c8k：3->4 A->5 C->6，按键 4（'q'）同时触发 3 和 4
+    vm.keyboard.key_down('q');
+    assert_eq!(vm.keyboard.mask(), 1 << 0x3 | 1 << 0x4);
+    vm.keyboard.key_up('q');
+    vm.keyboard.key_down('w');
+    assert_eq!(vm.keyboard.mask(), 1 << 0x5 | 1 << 0xA);
+
+    vm.reset();
+    vm.keyboard.key_down('q');
+    assert_eq!(vm.keyboard.mask(), 1 << 0x4);
+}
+
+#[test]
+fn auto_configure_switches_platform_and_undoes_it_for_unknown_roms() {
+    let mut vm = Chip8::create();
+    vm.set_auto_configure(true);
+    vm.load_rom(&read_rom("schip8/ALIEN.ch8")).unwrap();
+    assert_eq!(vm.platform(), Platform::SuperChip);
+    // 切换平台没有清掉刚写入的 ROM
+    assert_eq!(vm.memory[0x200..0x202], read_rom("schip8/ALIEN.ch8")[..2]);
+
+    // 未收录的 ROM 不沿用上一个 ROM 的平台、quirks、速度和按键映射
+    vm.load_rom(&[0x12, 0x00]).unwrap();
+    assert!(vm.rom_info().is_none());
+    assert_eq!(vm.platform(), Platform::Chip8);
+    assert_eq!(vm.quirks, Platform::Chip8.quirks());
+    assert_eq!(vm.rate, 480);
+    vm.change_mode(false);
+    assert_eq!(vm.rate, 480);
+    vm.keyboard.key_down('q');
+    assert_eq!(vm.keyboard.mask(), 1 << 0x4);
+
+    // 同一平台上收录的 ROM 也只改 quirks 与速度，之后同样撤销
+    vm.load_rom(&read_rom("chip8/BLINKY.ch8")).unwrap();
+    assert_eq!(vm.platform(), Platform::Chip8);
+    vm.load_rom(&[0x12, 0x00]).unwrap();
+    assert_eq!(vm.quirks, Platform::Chip8.quirks());
+}
+
+#[test]
+fn database_rate_survives_mode_changes_and_reset() {
+    let mut vm = Chip8::create();
+    vm.set_auto_configure(true);
+    vm.load_rom(&read_rom("schip8/ALIEN.ch8")).unwrap();
+
+    vm.change_mode(true);
+    assert_eq!(vm.rate, 30 * 60);
+    vm.change_mode(false);
+    assert_eq!(vm.rate, 30 * 60);
+    vm.reset();
+    assert_eq!(vm.rate, 30 * 60);
+
+    vm.set_platform(Platform::Chip8);
+    vm.change_mode(true);
+    assert_eq!(vm.rate, 700);
+}
+
+#[test]
+fn keymap_parses_c8k_digits() {
+    let info = romdb::lookup("D40ABC54374E4343639F993E897E00904DDF85D9").unwrap();
+    assert_eq!(info.title, "Blinky");
+    assert_eq!(
+        info.keymap(),
+        Some([0x0, 0x1, 0x2, 0x2, 0x4, 0x5, 0x8, 0x4, 0x6, 0x9, 0xA, 0xB, 0xC, 0xD, 0xE, 0x5])
+    );
+    assert_eq!(info.tick_rate(), Platform::Chip8.tick_rate());
+}
@@ -0,0 +1,89 @@
+use std::fs;
+
+use chip8_core::hash;
+use chip8_core::quirks::Quirks;
+use chip8_core::rpl::{FileRplStorage, MemoryRplStorage, RplStorage};
+use chip8_core::vm::{Chip8, StepOutcome, VmError};
+
+mod common;
+
+use common::rom;
+
+#[rustfmt::skip]
+const SAVE_SCORE: [u16; 4] = [
+    0x602A, // 200: LD V0, 0x2A
+    0x6107, // 202: LD V1, 0x07
+    0xF175, // 204: LD R, V1
+    0x1206, // 206: JP 0x206
+];
+
+fn run(storage: impl RplStorage + 'static, program: &[u16]) -> Chip8 {
+    let mut vm = Chip8::create();
+    vm.set_rpl_storage(storage);
+    vm.load_rom(&rom(program)).unwrap();
+    while vm.cycle() == StepOutcome::Ran {}
+    vm
+}
+
+#[test]
+fn sha1_matches_known_digests() {
+    assert_eq!(
+        hash::sha1_hex(b""),
+        "da39a3ee5e6b4b0d3255bfef95601890afd80709"
+    );
+    assert_eq!(
+        hash::sha1_hex(b"abc"),
+        "a9993e364706816aba3e25717850c26c9cd0d89d"
+    );
+    assert_eq!(
+        hash::sha1_hex(&[b'a'; 1000]),
+        "291e9a6c66994949b57ba5e650361e98fc36b1ba"
+    );
+}
+
+#[test]
+fn flags_survive_a_new_session() {
+    let storage = MemoryRplStorage::new();
+    let vm = run(storage.clone(), &SAVE_SCORE);
+    let key = hash::sha1_hex(&rom(&SAVE_SCORE));
+    assert_eq!(vm.rom_sha1(), Some(key.as_str()));
+    assert_eq!(storage.get(&key).unwrap()[..2], [0x2A, 0x07]);
+
+    // 新的 VM 加载同一个 ROM 时读回标志
+    let mut vm = Chip8::create();
+    vm.set_rpl_storage(storage.clone());
+    vm.load_rom(&rom(&SAVE_SCORE)).unwrap();
+    assert_eq!(vm.r_rpl[..2], [0x2A, 0x07]);
+
+    // 其他 ROM 的标志互不影响
+    let mut vm = Chip8::create();
+    vm.set_rpl_storage(storage);
+    vm.load_rom(&rom(&[0x1200])).unwrap();
+    assert_eq!(vm.r_rpl[..2], [0, 0]);
+}
+
+#[test]
+fn file_storage_round_trips() {
+    let dir = std::env::temp_dir().join(format!("chip8-rpl-{}", std::process::id()));
+    run(FileRplStorage::new(&dir), &SAVE_SCORE);
+
+    let mut vm = Chip8::create();
+    vm.set_rpl_storage(FileRplStorage::new(&dir));
+    vm.load_rom(&rom(&SAVE_SCORE)).unwrap();
+    assert_eq!(vm.r_rpl[..2], [0x2A, 0x07]);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn schip_limits_rpl_flags_to_eight() {
+    // F875: LD R, V8
+    let mut vm = Chip8::create();
+    vm.quirks = Quirks::schip();
+    vm.load_rom(&rom(&[0xF875])).unwrap();
+    assert_eq!(vm.cycle(), StepOutcome::Fault(VmError::RplOutOfRange(8)));
+
+    let mut vm = Chip8::create();
+    vm.quirks = Quirks::xochip();
+    vm.load_rom(&rom(&[0xF875])).unwrap();
+    assert_eq!(vm.cycle(), StepOutcome::Ran);
+}
@@ -0,0 +1,109 @@
+use chip8_core::hardware::Screen;
+use chip8_core::recompiler::Recompiler;
+use chip8_core::runner::{
+    self, AudioSink, DisplaySink, FixedClock, NoAudio, NoDisplay, NoInput, Runner, ScriptedInput,
+};
+use chip8_core::trace::InputEvent;
+use chip8_core::vm::StepOutcome;
+
+mod common;
+
+use common::boot;
+
+#[derive(Debug, Default)]
+struct Frames(Vec<String>);
+
+impl DisplaySink for Frames {
+    fn present(&mut self, screen: &Screen, size: (u8, u8)) {
+        self.0.push(runner::render_text(screen, size));
+    }
+}
+
+#[derive(Debug, Default)]
+struct Tones(Vec<bool>);
+
+impl AudioSink for Tones {
+    fn set_tone(&mut self, on: bool) {
+        self.0.push(on);
+    }
+}
+
+#[rustfmt::skip]
+const DRAW_AND_BEEP: [u16; 6] = [
+    0x6003, // 200: LD V0, 0x03
+    0xF018, // 202: LD ST, V0
+    0xF029, // 204: LD F, V0
+    0xD005, // 206: DRW V0, V0, 5
+    0x1208, // 208: JP 0x208
+    0x0000,
+];
+
+#[test]
+fn presents_changed_frames_and_toggles_the_tone() {
+    let mut vm = boot(&DRAW_AND_BEEP);
+    let mut runner = Runner::new(Frames::default(), Tones::default(), NoInput, FixedClock);
+
+    assert_eq!(runner.run(&mut vm, 60), StepOutcome::Halted);
+    assert_eq!(runner.frame(), 1);
+    assert!(!vm.draw_flag);
+    assert_eq!(runner.display.0.len(), 1);
+    // 字体 3 的第一行 0xF0 画在 (3, 3)，占第二行字符的下半格
+    let line = runner.display.0[0].lines().nth(1).unwrap();
+    assert!(line.starts_with("   ▄▄▄▄ "));
+
+    // 画面不变时不再输出；sound 计时器减到 0 时关闭蜂鸣器
+    for _ in 0..3 {
+        runner.run_frame(&mut vm);
+    }
+    assert_eq!(runner.display.0.len(), 1);
+    assert_eq!(runner.audio.0, [true, false]);
+}
+
+#[rustfmt::skip]
+const WAIT_FOR_KEY: [u16; 3] = [
+    0xF10A, // 200: LD V1, K
+    0x1202, // 202: JP 0x202
+    0x0000,
+];
+
+#[test]
+fn scripted_input_is_applied_on_its_frame() {
+    let mut vm = boot(&WAIT_FOR_KEY);
+    let inputs = ["5 w down", "6 w up"]
+        .into_iter()
+        .map(InputEvent::parse)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let mut runner = Runner::new(NoDisplay, NoAudio, ScriptedInput::new(inputs), FixedClock);
+
+    assert_eq!(runner.run(&mut vm, 5), StepOutcome::Idle);
+    assert!(vm.is_waiting_for_key());
+    assert_eq!(runner.run(&mut vm, 60), StepOutcome::Halted);
+    assert_eq!(runner.frame(), 7);
+    assert_eq!(vm.r_v[1], 0x5);
+}
+
+#[rustfmt::skip]
+const COUNT_FRAMES: [u16; 4] = [
+    0x7001, // 200: ADD V0, 0x01
+    0x6115, // 202: LD V1, 0x15
+    0x1200, // 204: JP 0x200
+    0x0000,
+];
+
+#[test]
+fn backends_are_interchangeable() {
+    let mut plain = boot(&COUNT_FRAMES);
+    let mut compiled = boot(&COUNT_FRAMES);
+    let mut interpreted = Runner::new(NoDisplay, NoAudio, NoInput, FixedClock);
+    let mut recompiled = Runner::new(NoDisplay, NoAudio, NoInput, FixedClock);
+    recompiled.set_backend(Recompiler::new());
+
+    assert_eq!(interpreted.run(&mut plain, 10), StepOutcome::Ran);
+    assert_eq!(recompiled.run(&mut compiled, 10), StepOutcome::Ran);
+    assert_eq!(interpreted.frame(), 10);
+    assert_eq!(plain.r_v, compiled.r_v);
+    assert_eq!(plain.r_pc, compiled.r_pc);
+    // 每帧 8 条指令，三条一轮
+    assert_eq!(plain.r_v[0], (10 * 8 / 3 + 1) as u8);
+}
@@ -0,0 +1,138 @@
+use chip8_core::hardware::Screen;
+use chip8_core::platform::Platform;
+use chip8_core::screenshot::{Colors, ImageFormat, Screenshot};
+use chip8_core::vm::Chip8;
+
+const WHITE_ON_BLACK: Colors = Colors {
+    foreground: [0xff, 0xff, 0xff],
+    background: [0x00, 0x00, 0x00],
+};
+
+/// 10x2 的画面，第一行最左与最右两个像素点亮
+fn small() -> Screenshot {
+    let mut screen = Screen::new();
+    screen.set_pixel(0, 0, true);
+    screen.set_pixel(9, 0, true);
+    Screenshot::from_screen(&screen, (10, 2), WHITE_ON_BLACK)
+}
+
+#[test]
+fn netpbm_formats_pack_rows_and_use_luma() {
+    let image = small();
+    let mut pbm = b"P4\n10 2\n".to_vec();
+    // 1 为黑色：点亮的白色像素是 0，每行补齐到 2 字节
+    pbm.extend_from_slice(&[0x7f, 0x80, 0xff, 0xc0]);
+    assert_eq!(image.encode(ImageFormat::Pbm), pbm);
+
+    let pgm = image.to_pgm();
+    let (header, pixels) = pgm.split_at(b"P5\n10 2\n255\n".len());
+    assert_eq!(header, b"P5\n10 2\n255\n");
+    assert_eq!(pixels.len(), 20);
+    assert_eq!(
+        (pixels[0], pixels[1], pixels[9], pixels[10]),
+        (255, 0, 255, 0)
+    );
+}
+
+/// 按顺序取出 PNG 的块，同时检查 CRC
+fn chunks(png: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    let mut chunks = Vec::new();
+    let mut rest = &png[8..];
+    while !rest.is_empty() {
+        let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+        let body = &rest[4..8 + len];
+        let crc = u32::from_be_bytes(rest[8 + len..12 + len].try_into().unwrap());
+        let mut expected = !0u32;
+        for &byte in body {
+            expected ^= byte as u32;
+            for _ in 0..8 {
+                expected = if expected & 1 != 0 {
+                    0xedb8_8320 ^ (expected >> 1)
+                } else {
+                    expected >> 1
+                };
+            }
+        }
+        assert_eq!(crc, !expected);
+        chunks.push((body[..4].try_into().unwrap(), body[4..].to_vec()));
+        rest = &rest[12 + len..];
+    }
+    chunks
+}
+
+/// 解开只含 stored 块的 zlib 数据
+fn inflate_stored(zlib: &[u8]) -> Vec<u8> {
+    assert_eq!(u16::from_be_bytes([zlib[0], zlib[1]]) % 31, 0);
+    let mut out = Vec::new();
+    let mut pos = 2;
+    loop {
+        let last = zlib[pos] & 1 != 0;
+        assert_eq!(zlib[pos] >> 1, 0, "only stored blocks");
+        let len = u16::from_le_bytes([zlib[pos + 1], zlib[pos + 2]]);
+        assert_eq!(!len, u16::from_le_bytes([zlib[pos + 3], zlib[pos + 4]]));
+        out.extend_from_slice(&zlib[pos + 5..pos + 5 + len as usize]);
+        pos += 5 + len as usize;
+        if last {
+            break;
+        }
+    }
+    assert_eq!(pos + 4, zlib.len());
+    out
+}
+
+#[test]
+fn png_is_scaled_rgb_with_valid_checksums() {
+    let image = small().scaled(3);
+    assert_eq!((image.width, image.height), (30, 6));
+    let chunks = chunks(&image.to_png());
+    let kinds: Vec<_> = chunks.iter().map(|(kind, _)| kind).collect();
+    assert_eq!(kinds, [b"IHDR", b"IDAT", b"IEND"]);
+    assert_eq!(
+        chunks[0].1,
+        [0, 0, 0, 30, 0, 0, 0, 6, 8, 2, 0, 0, 0].to_vec()
+    );
+
+    let raw = inflate_stored(&chunks[1].1);
+    assert_eq!(raw.len(), (30 * 3 + 1) * 6);
+    let rows: Vec<_> = raw.chunks(30 * 3 + 1).collect();
+    for row in &rows[..3] {
+        assert_eq!(row[0], 0);
+        assert_eq!(&row[1..10], [0xff; 9]);
+        assert_eq!(&row[10..13], [0; 3]);
+        assert_eq!(&row[28 * 3 + 1..], [0xff; 6]);
+    }
+    assert!(rows[3..].iter().all(|row| row[1..].iter().all(|&b| b == 0)));
+}
+
+#[test]
+fn large_images_span_several_stored_blocks() {
+    let mut screen = Screen::new();
+    screen.set_pixel(127, 63, true);
+    let image = Screenshot::from_screen(&screen, (128, 64), WHITE_ON_BLACK).scaled(4);
+    let chunks = chunks(&image.to_png());
+    let raw = inflate_stored(&chunks[1].1);
+    assert!(raw.len() > 0xffff);
+    assert_eq!(raw.len(), (512 * 3 + 1) * 256);
+    assert_eq!(raw[raw.len() - 3..], [0xff; 3]);
+}
+
+#[test]
+fn capture_follows_platform_size_and_colors() {
+    let mut vm = Chip8::with_platform(Platform::SuperChip);
+    vm.screen.set_pixel(1, 0, true);
+    let colors = Colors::parse("#ff0000, 0000ff").unwrap();
+    let image = Screenshot::capture(&vm, colors);
+    assert_eq!((image.width, image.height), (64, 32));
+    assert_eq!(image.pixels[..2], [[0, 0, 0xff], [0xff, 0, 0]]);
+    vm.change_mode(true);
+    assert_eq!(Screenshot::capture(&vm, colors).width, 128);
+
+    let chip8x = Chip8::with_platform(Platform::Chip8X);
+    let image = Screenshot::capture(&chip8x, colors);
+    assert_eq!(image.pixels[0], chip8x.pixel_color(0, 0));
+
+    assert_eq!(ImageFormat::from_path("bug.PGM"), Some(ImageFormat::Pgm));
+    assert_eq!(ImageFormat::from_path("bug.jpg"), None);
+    assert_eq!(Colors::parse("fff,000"), None);
+}
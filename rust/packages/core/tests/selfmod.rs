@@ -0,0 +1,104 @@
+use chip8_core::selfmod::{ModifiedCode, MODIFIED_CODE_CAPACITY};
+use chip8_core::vm::StepOutcome;
+
+mod common;
+
+use common::boot;
+
+#[rustfmt::skip]
+const PATCH_OPERAND: [u16; 7] = [
+    0xA20B, // 200: LD I, 0x20B，指向 0x20A 处指令的低字节
+    0x6005, // 202: LD V0, 0x05
+    0xF055, // 204: LD [I], V0
+    0x120A, // 206: JP 0x20A
+    0x0000, // 208
+    0x6100, // 20A: LD V1, 0x00，执行时已被改写为 LD V1, 0x05
+    0x120C, // 20C: JP 0x20C
+];
+
+#[test]
+fn reports_code_written_then_executed() {
+    let mut vm = boot(&PATCH_OPERAND);
+    while vm.cycle() == StepOutcome::Ran {}
+
+    assert_eq!(vm.r_v[1], 0x05);
+    assert_eq!(
+        vm.modified_code(),
+        [ModifiedCode {
+            addr: 0x20A,
+            writer: 0x204,
+            executions: 1,
+        }]
+    );
+    assert!(vm.code_writes().is_written(0x20B));
+    assert!(!vm.code_writes().is_written(0x20A));
+
+    vm.reset();
+    assert!(vm.modified_code().is_empty());
+}
+
+#[test]
+fn breaks_before_executing_modified_code() {
+    let mut vm = boot(&PATCH_OPERAND);
+    vm.set_break_on_modified_code(true);
+
+    let mut outcome = vm.cycle();
+    while outcome == StepOutcome::Ran {
+        outcome = vm.cycle();
+    }
+    assert_eq!(outcome, StepOutcome::Break(0x20A));
+    assert_eq!(vm.r_pc, 0x20A);
+    assert_eq!(vm.r_v[1], 0x00);
+
+    // 再次调用时执行这条指令，而不是又中断一次
+    assert_eq!(vm.cycle(), StepOutcome::Ran);
+    assert_eq!(vm.r_v[1], 0x05);
+}
+
+#[test]
+fn data_writes_are_not_reported() {
+    let mut vm = boot(&[
+        0xA300, // 200: LD I, 0x300
+        0x6042, // 202: LD V0, 0x42
+        0xF033, // 204: LD B, V0
+        0x1206, // 206: JP 0x206
+    ]);
+    while vm.cycle() == StepOutcome::Ran {}
+
+    assert!(vm.modified_code().is_empty());
+    assert_eq!(vm.code_writes().writer(0x301), Some(0x204));
+}
+
+#[test]
+fn modified_code_record_has_a_fixed_capacity() {
+    #[rustfmt::skip]
+    let mut vm = boot(&[
+        0xA300, // 200: LD I, 0x300
+        0x6072, // 202: LD V0, 0x72
+        0x6101, // 204: LD V1, 0x01
+        0x6302, // 206: LD V3, 2
+        0xF155, // 208: LD [I], V1，写入 ADD V2, 1
+        0xF31E, // 20A: ADD I, V3
+        0x7401, // 20C: ADD V4, 1
+        0x3400, // 20E: SE V4, 0
+        0x1208, // 210: JP 0x208，共 256 条
+        0xF155, // 212: LD [I], V1
+        0xF31E, // 214: ADD I, V3
+        0x7501, // 216: ADD V5, 1
+        0x3508, // 218: SE V5, 8
+        0x1212, // 21A: JP 0x212，再写 8 条
+        0x6015, // 21C: LD V0, 0x15
+        0x6110, // 21E: LD V1, 0x10
+        0xF155, // 220: LD [I], V1，0x510 处写入 JP 0x510
+        0x1300, // 222: JP 0x300
+    ]);
+    while vm.cycle() == StepOutcome::Ran {}
+
+    assert_eq!(vm.r_pc, 0x510);
+    assert_eq!(vm.r_v[2], (264 % 256) as u8);
+    let modified = vm.modified_code();
+    assert_eq!(modified.len(), MODIFIED_CODE_CAPACITY);
+    assert_eq!((modified[0].addr, modified[0].writer), (0x300, 0x208));
+    assert!(modified.windows(2).all(|pair| pair[0].addr < pair[1].addr));
+    assert_eq!(vm.code_writes().dropped(), 265 - MODIFIED_CODE_CAPACITY);
+}
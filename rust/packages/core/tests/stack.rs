@@ -0,0 +1,79 @@
+use chip8_core::vm::{
+    MemoryLayout, StackPolicy, StepOutcome, VmError, SCHIP_STACK_DEPTH, VIP_STACK, VIP_STACK_DEPTH,
+};
+
+mod common;
+
+use common::boot;
+
+#[test]
+fn ret_on_empty_stack_faults() {
+    // 00EE: RET
+    let mut vm = boot(&[0x00EE]);
+    assert_eq!(vm.stack_depth(), SCHIP_STACK_DEPTH);
+    assert_eq!(vm.cycle(), StepOutcome::Fault(VmError::StackUnderflow));
+    assert_eq!(vm.r_pc, 0x200);
+    assert_eq!(vm.cycle(), StepOutcome::Halted);
+}
+
+#[test]
+fn call_and_ret_move_the_stack_pointer() {
+    // 2204: CALL 0x204, 1202: JP 0x202, 00EE: RET
+    let mut vm = boot(&[0x2204, 0x1202, 0x00EE]);
+    vm.cycle();
+    assert_eq!((vm.r_sp, vm.stack[0]), (1, 0x202));
+    vm.cycle();
+    assert_eq!((vm.r_sp, vm.r_pc), (0, 0x202));
+}
+
+#[test]
+fn overflow_faults_at_the_configured_depth() {
+    for layout in [MemoryLayout::Flat, MemoryLayout::Vip] {
+        // 2200: CALL 0x200
+        let mut vm = boot(&[0x2200]);
+        vm.layout = layout;
+        vm.set_stack_depth(VIP_STACK_DEPTH);
+        for _ in 0..VIP_STACK_DEPTH {
+            assert_eq!(vm.cycle(), StepOutcome::Ran);
+        }
+        assert_eq!(vm.cycle(), StepOutcome::Fault(VmError::StackOverflow));
+        assert_eq!(vm.r_sp, VIP_STACK_DEPTH);
+    }
+}
+
+#[test]
+fn wrap_policy_keeps_running() {
+    // 2200: CALL 0x200
+    let mut vm = boot(&[0x2200]);
+    vm.set_stack_depth(2);
+    vm.stack_policy = StackPolicy::Wrap;
+    for _ in 0..5 {
+        assert_eq!(vm.cycle(), StepOutcome::Ran);
+    }
+    assert_eq!(vm.r_sp, 1);
+
+    // 00EE: RET
+    let mut vm = boot(&[0x00EE]);
+    vm.set_stack_depth(2);
+    vm.stack_policy = StackPolicy::Wrap;
+    vm.stack[1] = 0x300;
+    assert_eq!(vm.cycle(), StepOutcome::Ran);
+    assert_eq!((vm.r_sp, vm.r_pc), (1, 0x300));
+}
+
+#[test]
+fn vip_stack_never_leaves_its_memory_region() {
+    // 2200: CALL 0x200
+    let mut vm = boot(&[0x2200]);
+    vm.layout = MemoryLayout::Vip;
+    vm.set_stack_depth(u8::MAX);
+    let slots = (VIP_STACK.end - VIP_STACK.start) / 2;
+    assert_eq!(vm.stack_depth() as u16, slots);
+    for _ in 0..slots {
+        assert_eq!(vm.cycle(), StepOutcome::Ran);
+    }
+    assert_eq!(vm.cycle(), StepOutcome::Fault(VmError::StackOverflow));
+    let below = VIP_STACK.start as usize;
+    assert_eq!(vm.memory[below - 0x10..below], [0; 0x10]);
+    assert_eq!(vm.memory[below..below + 2], [0x02, 0x02]);
+}
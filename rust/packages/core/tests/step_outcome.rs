@@ -0,0 +1,156 @@
+use chip8_core::vm::StepOutcome;
+
+mod common;
+
+use common::boot;
+
+#[test]
+fn self_jump_halts() {
+    // 6001: V0 = 1, 1202: JP 0x202
+    let mut vm = boot(&[0x6001, 0x1202]);
+    assert_eq!(vm.cycle(), StepOutcome::Ran);
+    assert_eq!(vm.cycle(), StepOutcome::Halted);
+    assert_eq!(vm.r_pc, 0x202);
+}
+
+#[test]
+fn exit_halts_and_stops_executing() {
+    // 00FD: EXIT, 6001: V0 = 1
+    let mut vm = boot(&[0x00FD, 0x6001]);
+    assert_eq!(vm.cycle(), StepOutcome::Halted);
+    assert_eq!(vm.cycle(), StepOutcome::Halted);
+    assert_eq!(vm.r_v[0], 0);
+}
+
+#[test]
+fn exit_n_halts_with_the_exit_code() {
+    // 0012: EXIT 2
+    let mut vm = boot(&[0x0012]);
+    assert_eq!(vm.cycle(), StepOutcome::Halted);
+    assert_eq!(vm.exit_code(), Some(2));
+    assert_eq!(vm.cycle(), StepOutcome::Halted);
+
+    // 0010 在默认的 CHIP-8 上也是退出，而不是 MegaChip 的模式切换
+    let mut vm = boot(&[0x0010, 0x6001]);
+    assert_eq!(vm.cycle(), StepOutcome::Halted);
+    assert_eq!((vm.exit_code(), vm.r_v[0]), (Some(0), 0));
+
+    vm.reset();
+    assert_eq!(vm.exit_code(), None);
+}
+
+#[test]
+fn delay_timer_loop_is_idle_until_it_expires() {
+    #[rustfmt::skip]
+    let mut vm = boot(&[
+        0x6002, // V0 = 2
+        0xF015, // DT = V0
+        0xF107, // V1 = DT
+        0x3100, // SE V1, 0
+        0x1204, // JP 0x204
+        0x120A, // JP 0x20A
+    ]);
+    assert_eq!(vm.cycle(), StepOutcome::Ran);
+    assert_eq!(vm.cycle(), StepOutcome::Ran);
+    assert_eq!(vm.cycle(), StepOutcome::Ran);
+    assert_eq!(vm.cycle(), StepOutcome::Ran);
+    assert_eq!(vm.cycle(), StepOutcome::Idle);
+
+    vm.ticker();
+    vm.ticker();
+    while vm.r_pc != 0x20A {
+        assert_ne!(vm.cycle(), StepOutcome::Idle);
+    }
+    assert_eq!(vm.cycle(), StepOutcome::Halted);
+}
+
+#[test]
+fn delay_timer_loop_stays_idle_when_a_frame_splits_read_and_jump() {
+    #[rustfmt::skip]
+    let mut vm = boot(&[
+        0x6001, // V0 = 1
+        0xF015, // DT = V0
+        0xF107, // V1 = DT
+        0x3100, // SE V1, 0
+        0x1204, // JP 0x204
+        0x120A, // JP 0x20A
+    ]);
+    for _ in 0..3 {
+        assert_eq!(vm.cycle(), StepOutcome::Ran);
+    }
+    // V1 读到的是 1，计时器在跳转前归零
+    vm.ticker();
+    assert_eq!(vm.cycle(), StepOutcome::Ran);
+    assert_eq!(vm.cycle(), StepOutcome::Idle);
+
+    while vm.r_pc != 0x20A {
+        assert_ne!(vm.cycle(), StepOutcome::Halted);
+    }
+    assert_eq!(vm.cycle(), StepOutcome::Halted);
+}
+
+#[test]
+fn timer_loop_testing_another_register_halts() {
+    #[rustfmt::skip]
+    let mut vm = boot(&[
+        0x6005, // V0 = 5
+        0xF015, // DT = V0
+        0xF107, // V1 = DT
+        0x3201, // SE V2, 1
+        0x1204, // JP 0x204
+    ]);
+    for _ in 0..4 {
+        vm.cycle();
+    }
+    assert_eq!(vm.cycle(), StepOutcome::Halted);
+}
+
+#[test]
+fn loop_that_cannot_exit_halts() {
+    // 6001: V0 = 1, 3000: SE V0, 0, 1202: JP 0x202
+    let mut vm = boot(&[0x6001, 0x3000, 0x1202]);
+    vm.cycle();
+    vm.cycle();
+    assert_eq!(vm.cycle(), StepOutcome::Halted);
+}
+
+#[test]
+fn loop_with_side_effects_keeps_running() {
+    // 7001: V0 += 1, 1200: JP 0x200
+    let mut vm = boot(&[0x7001, 0x1200]);
+    vm.cycle();
+    assert_eq!(vm.cycle(), StepOutcome::Ran);
+}
+
+#[test]
+fn key_wait_is_idle_until_press_and_release() {
+    // F30A: V3 = KEY, 6001: V0 = 1
+    let mut vm = boot(&[0xF30A, 0x6001]);
+    vm.keyboard.key_down('w');
+    assert_eq!(vm.cycle(), StepOutcome::Ran);
+    assert!(vm.is_waiting_for_key());
+
+    // 进入等待前已按下的键不算
+    vm.keyboard.key_up('w');
+    assert_eq!(vm.cycle(), StepOutcome::Idle);
+
+    vm.keyboard.key_down('e');
+    assert_eq!(vm.cycle(), StepOutcome::Idle);
+    vm.keyboard.key_up('e');
+    assert_eq!(vm.cycle(), StepOutcome::Ran);
+    assert!(!vm.is_waiting_for_key());
+    assert_eq!(vm.r_v[3], 0x6);
+
+    vm.cycle();
+    assert_eq!(vm.r_v[0], 1);
+}
+
+#[test]
+fn key_wait_completes_on_press_without_release_quirk() {
+    let mut vm = boot(&[0xF30A]);
+    vm.quirks.key_wait_release = false;
+    vm.cycle();
+    vm.keyboard.key_down('v');
+    assert_eq!(vm.cycle(), StepOutcome::Ran);
+    assert_eq!(vm.r_v[3], 0xF);
+}
@@ -0,0 +1,296 @@
+use chip8_core::test_runner::{Check, Probe, TestPlatform, TestRunner};
+
+mod common;
+
+use common::rom;
+
+/// 按 Timendus 的约定写的小测试 ROM：读取 0x1FF 选择平台，把结果写到 0x300 起的内存，最后停在自跳转上
+#[rustfmt::skip]
+const QUIRKS_ROM: [u16; 19] = [
+    0x6F05, // VF = 5
+    0x6101, // V1 = 1
+    0x8011, // V0 |= V1，vF reset 时 VF 清零
+    0x81F0, // V1 = VF
+    0x6204, // V2 = 4
+    0x6308, // V3 = 8
+    0x8326, // V3 >>= 1，shift_vy 时使用 V2
+    0xA300, // I = 0x300
+    0xF355, // [0x300..0x303] = V0..V3，memory_increment 时 I = 0x304
+    0x60AA, // V0 = 0xAA
+    0xF055, // [I] = V0
+    0xA1FF, // I = 0x1FF
+    0xF065, // V0 = 平台编号
+    0xA305, // I = 0x305
+    0xF055, // [0x305] = V0
+    0x6000, // V0 = 0
+    0xF029, // I = 字符 0
+    0xD005, // 在 (0, 0) 画字符 0
+    0x1224, // 停机
+];
+
+const DIGIT_ZERO: [u8; 5] = [0xF0, 0x90, 0x90, 0x90, 0xF0];
+
+fn checks(platform: TestPlatform) -> Vec<Check> {
+    let vip = platform == TestPlatform::Chip8;
+    vec![
+        Check {
+            name: "vF reset",
+            probe: Probe::Memory {
+                address: 0x301,
+                pass: if vip { 0 } else { 5 },
+            },
+        },
+        Check {
+            name: "Shifting",
+            probe: Probe::Memory {
+                address: 0x303,
+                pass: if vip { 2 } else { 4 },
+            },
+        },
+        Check {
+            name: "Memory",
+            probe: Probe::Memory {
+                address: if vip { 0x304 } else { 0x300 },
+                pass: 0xAA,
+            },
+        },
+        Check {
+            name: "Platform",
+            probe: Probe::Memory {
+                address: 0x305,
+                pass: platform as u8,
+            },
+        },
+        Check {
+            name: "Font",
+            probe: Probe::Screen {
+                x: 0,
+                y: 0,
+                pass: &DIGIT_ZERO,
+            },
+        },
+    ]
+}
+
+#[test]
+fn passes_with_matching_quirks() {
+    for platform in [TestPlatform::Chip8, TestPlatform::Schip] {
+        let mut runner = TestRunner::for_platform(platform);
+        runner.load(&rom(&QUIRKS_ROM), Some(platform)).unwrap();
+        let report = runner.run(&checks(platform));
+
+        assert!(report.halted);
+        assert!(report.passed(), "{:?}: {:?}", platform, report);
+    }
+}
+
+#[test]
+fn reports_each_failing_check() {
+    let mut runner = TestRunner::for_platform(TestPlatform::Schip);
+    runner
+        .load(&rom(&QUIRKS_ROM), Some(TestPlatform::Schip))
+        .unwrap();
+    let report = runner.run(&checks(TestPlatform::Chip8));
+
+    let failures: Vec<&str> = report.failures().map(|check| check.name).collect();
+    assert_eq!(failures, ["vF reset", "Shifting", "Memory", "Platform"]);
+}
+
+#[test]
+fn stops_at_frame_limit_without_halt() {
+    let mut runner = TestRunner::for_platform(TestPlatform::Chip8);
+    runner.max_frames = 10;
+    // 1200: JP 0x202, 1202: JP 0x200
+    runner.load(&[0x12, 0x02, 0x12, 0x00], None).unwrap();
+    let report = runner.run(&[]);
+
+    assert!(!report.halted);
+    assert_eq!(report.frames, 10);
+    assert!(!report.passed());
+}
+
+/// 仿照 Timendus 的 4-flags：8XY4、8XY5、8XY7 的结果与 VF，包括 VF 同时作为 VX 时标志位覆盖结果
+#[rustfmt::skip]
+const FLAGS_ROM: [u16; 19] = [
+    0x61FF, // 200: V1 = 0xFF
+    0x6202, // 202: V2 = 2
+    0x8124, // 204: V1 += V2
+    0x83F0, // 206: V3 = VF
+    0x6401, // 208: V4 = 1
+    0x8425, // 20A: V4 -= V2
+    0x85F0, // 20C: V5 = VF
+    0x6601, // 20E: V6 = 1
+    0x8627, // 210: V6 = V2 - V6
+    0x87F0, // 212: V7 = VF
+    0x6FFE, // 214: VF = 0xFE
+    0x8F24, // 216: VF += V2，结果为 0，进位为 1
+    0x88F0, // 218: V8 = VF
+    0x6F01, // 21A: VF = 1
+    0x8F25, // 21C: VF -= V2，结果为 0xFF，借位标志为 0
+    0x89F0, // 21E: V9 = VF
+    0xA300, // 220: I = 0x300
+    0xF955, // 222: [0x300..0x309] = V0..V9
+    0x1224, // 224: 停机
+];
+
+fn flag_checks() -> Vec<Check> {
+    [
+        ("8XY4 result", 0x301, 0x01),
+        ("8XY4 carry", 0x303, 1),
+        ("8XY5 result", 0x304, 0xFF),
+        ("8XY5 no borrow", 0x305, 0),
+        ("8XY7 result", 0x306, 0x01),
+        ("8XY7 no borrow", 0x307, 1),
+        ("8FY4 flag wins", 0x308, 1),
+        ("8FY5 flag wins", 0x309, 0),
+    ]
+    .into_iter()
+    .map(|(name, address, pass)| Check {
+        name,
+        probe: Probe::Memory { address, pass },
+    })
+    .collect()
+}
+
+#[test]
+fn flags_are_the_same_on_every_platform() {
+    for platform in [
+        TestPlatform::Chip8,
+        TestPlatform::Schip,
+        TestPlatform::XoChip,
+    ] {
+        let mut runner = TestRunner::for_platform(platform);
+        runner.load(&rom(&FLAGS_ROM), Some(platform)).unwrap();
+        let report = runner.run(&flag_checks());
+        assert!(report.passed(), "{:?}: {:?}", platform, report);
+    }
+}
+
+/// 仿照 Timendus 5-quirks 中的 display wait、clipping 与 jumping 三项。
+/// 先等到一帧开始，再在同一帧内设置 DT = 10 并连续绘制三次：等待 vblank 时每次绘制都会经过一次 tick，DT 读出 7
+#[rustfmt::skip]
+const TIMING_ROM: [u16; 30] = [
+    0xA23A, // 200: I = 精灵 0xFF
+    0x603C, // 202: V0 = 60
+    0x610A, // 204: V1 = 10
+    0x650A, // 206: V5 = 10
+    0x6301, // 208: V3 = 1
+    0xF315, // 20A: DT = V3
+    0xF307, // 20C: V3 = DT，等到 DT 归零
+    0x3300, // 20E: SE V3, 0
+    0x120C, // 210: JP 0x20C
+    0xF515, // 212: DT = 10
+    0xD011, // 214: 在 (60, 10) 画 8 像素宽的精灵，越过右边缘
+    0x7102, // 216: V1 += 2
+    0xD011, // 218
+    0x7102, // 21A
+    0xD011, // 21C
+    0xF607, // 21E: V6 = DT
+    0x6701, // 220: V7 = 1
+    0x3607, // 222: SE V6, 7
+    0x6700, // 224: V7 = 0，没有等待 vblank
+    0x6000, // 226: V0 = 0
+    0x6800, // 228: V8 = 0
+    0x6202, // 22A: V2 = 2
+    0xB230, // 22C: jump_vx 时跳到 0x230 + V2，否则跳到 0x230 + V0
+    0x0000, // 22E
+    0x1234, // 230: JP 0x234
+    0x6801, // 232: V8 = 1
+    0xA300, // 234: I = 0x300
+    0xF855, // 236: [0x300..0x308] = V0..V8
+    0x1238, // 238: 停机
+    0xFF00, // 23A: 精灵
+];
+
+/// Timendus 5-quirks 对各平台的预期：(display wait, clipping, jumping)
+fn timing_checks(platform: TestPlatform) -> Vec<Check> {
+    let (display_wait, clipping, jumping) = match platform {
+        TestPlatform::Chip8 => (true, true, false),
+        TestPlatform::Schip => (false, true, true),
+        TestPlatform::XoChip => (false, false, false),
+    };
+    vec![
+        Check {
+            name: "Display wait",
+            probe: Probe::Memory {
+                address: 0x307,
+                pass: display_wait as u8,
+            },
+        },
+        Check {
+            name: "Clipping",
+            probe: Probe::Screen {
+                x: 0,
+                y: 10,
+                pass: if clipping { &[0x00] } else { &[0xF0] },
+            },
+        },
+        Check {
+            name: "Jumping",
+            probe: Probe::Memory {
+                address: 0x308,
+                pass: jumping as u8,
+            },
+        },
+    ]
+}
+
+#[test]
+fn every_quirk_profile_passes_display_wait_clipping_and_jumping() {
+    for platform in [
+        TestPlatform::Chip8,
+        TestPlatform::Schip,
+        TestPlatform::XoChip,
+    ] {
+        let mut runner = TestRunner::for_platform(platform);
+        runner.load(&rom(&TIMING_ROM), Some(platform)).unwrap();
+        let report = runner.run(&timing_checks(platform));
+        assert!(report.passed(), "{:?}: {:?}", platform, report);
+    }
+
+    // 用 SUPER-CHIP 的 quirks 运行时，按 VIP 的预期检查：两者都裁剪，只有另外两项不通过
+    let mut runner = TestRunner::for_platform(TestPlatform::Schip);
+    runner.load(&rom(&TIMING_ROM), None).unwrap();
+    let report = runner.run(&timing_checks(TestPlatform::Chip8));
+    let failures: Vec<&str> = report.failures().map(|check| check.name).collect();
+    assert_eq!(failures, ["Display wait", "Jumping"]);
+}
+
+/// 用到 XO-CHIP 指令的测试 ROM：F000 NNNN 载入 16 位地址、5XY2 保存寄存器区间、FN01 选择位平面
+#[rustfmt::skip]
+const XOCHIP_ROM: [u16; 8] = [
+    0x6011, // 200: V0 = 0x11
+    0x6122, // 202: V1 = 0x22
+    0xF000, // 204: I = 0x0300
+    0x0300,
+    0x5012, // 208: [0x300..0x301] = V0..V1
+    0xF101, // 20A: 选择第 1 个位平面
+    0x00E0, // 20C: CLS
+    0x120E, // 20E: 停机
+];
+
+#[test]
+fn xochip_tests_run_on_the_xochip_platform() {
+    let checks = [
+        Check {
+            name: "5XY2 V0",
+            probe: Probe::Memory {
+                address: 0x300,
+                pass: 0x11,
+            },
+        },
+        Check {
+            name: "5XY2 V1",
+            probe: Probe::Memory {
+                address: 0x301,
+                pass: 0x22,
+            },
+        },
+    ];
+    let mut runner = TestRunner::for_platform(TestPlatform::XoChip);
+    runner
+        .load(&rom(&XOCHIP_ROM), Some(TestPlatform::XoChip))
+        .unwrap();
+    let report = runner.run(&checks);
+    assert!(report.passed(), "{:?}", report);
+}
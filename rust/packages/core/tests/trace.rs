@@ -0,0 +1,216 @@
+use std::cell::RefCell;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use chip8_core::disasm::OpcodeClass;
+use chip8_core::trace::{
+    self, first_divergence, BinarySink, InputEvent, JsonLinesSink, MemoryWrite, RegisterDelta,
+    TextSink, TraceEntry, TraceFilter, TraceOptions, TraceRecord, TraceSink, Tracer,
+};
+use chip8_core::vm::{Chip8, StepOutcome};
+
+/// LD V0, 0xFB; LD I, 0x300; LD B, V0; ADD V1, 1; JP 0x208
+const BCD_ROM: [u8; 10] = [0x60, 0xFB, 0xA3, 0x00, 0xF0, 0x33, 0x71, 0x01, 0x12, 0x08];
+
+fn log_bcd_rom(filter: TraceFilter) -> Vec<TraceEntry> {
+    let entries = Rc::new(RefCell::new(vec![]));
+    let mut vm = Chip8::create();
+    vm.load_rom(&BCD_ROM).unwrap();
+    vm.set_tracer(Tracer::new(entries.clone()).with_filter(filter));
+    while vm.cycle() != StepOutcome::Halted {}
+    vm.take_tracer().unwrap().finish().unwrap();
+
+    let entries = entries.borrow().clone();
+    entries
+}
+
+fn record_trace(seed: u32, options: &TraceOptions) -> Vec<TraceRecord> {
+    let rom = fs::read(
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../../assets/roms/chip8/BRIX.ch8"),
+    )
+    .unwrap();
+    let mut vm = Chip8::create();
+    vm.seed_rng(seed);
+    vm.load_rom(&rom).unwrap();
+
+    let mut records = vec![];
+    trace::run(&mut vm, options, |record| records.push(record.clone()));
+    records
+}
+
+#[test]
+fn same_seed_and_inputs_replay_identically() {
+    let mut options = TraceOptions::new(4000);
+    options.inputs = vec![
+        InputEvent::parse("20 q down").unwrap(),
+        InputEvent::parse("40 q up").unwrap(),
+    ];
+
+    let left = record_trace(7, &options);
+    let right = record_trace(7, &options);
+    assert_eq!(left.len(), 4000);
+    assert_eq!(first_divergence(&left, &right), None);
+}
+
+#[test]
+fn reports_first_divergence() {
+    let options = TraceOptions::new(4000);
+    let left = record_trace(1, &options);
+    let right = record_trace(2, &options);
+
+    let divergence = first_divergence(&left, &right).unwrap();
+    assert_eq!(left[..divergence.index], right[..divergence.index]);
+    assert_eq!(left[divergence.index].ir_code & 0xF000, 0xC000);
+    assert!(!divergence.fields.is_empty());
+}
+
+#[test]
+fn text_format_round_trips() {
+    let records = record_trace(3, &TraceOptions::new(100));
+    for record in records {
+        assert_eq!(TraceRecord::parse(&record.to_string()).unwrap(), record);
+    }
+
+    let shorter = record_trace(3, &TraceOptions::new(50));
+    let longer = record_trace(3, &TraceOptions::new(60));
+    let divergence = first_divergence(&shorter, &longer).unwrap();
+    assert_eq!(divergence.index, 50);
+    assert_eq!(divergence.left, None);
+}
+
+#[test]
+fn tracer_records_deltas_and_memory_writes() {
+    let entries = log_bcd_rom(TraceFilter::default());
+    let asm: Vec<&str> = entries.iter().map(|entry| entry.disasm.as_str()).collect();
+    assert_eq!(
+        asm,
+        [
+            "LD V0, 0xFB",
+            "LD I, 0x300",
+            "LD B, V0",
+            "ADD V1, 0x01",
+            "JP 0x208"
+        ]
+    );
+
+    assert_eq!(
+        entries[0].deltas,
+        [RegisterDelta {
+            reg: 0,
+            old: 0,
+            new: 0xFB
+        }]
+    );
+    assert_eq!(entries[1].i, 0x300);
+    assert_eq!(
+        entries[2].writes,
+        [
+            MemoryWrite {
+                addr: 0x300,
+                value: 2
+            },
+            MemoryWrite {
+                addr: 0x301,
+                value: 5
+            },
+            MemoryWrite {
+                addr: 0x302,
+                value: 1
+            },
+        ]
+    );
+    assert!(entries[2].deltas.is_empty());
+    assert_eq!(entries[4].cycle, 4);
+}
+
+#[test]
+fn tracer_filters_by_pc_range_and_class() {
+    let entries = log_bcd_rom(TraceFilter {
+        pc_range: Some(0x202..=0x206),
+        classes: vec![],
+    });
+    let pcs: Vec<u16> = entries.iter().map(|entry| entry.pc).collect();
+    assert_eq!(pcs, [0x202, 0x204, 0x206]);
+
+    let entries = log_bcd_rom(TraceFilter {
+        pc_range: None,
+        classes: vec![OpcodeClass::Memory, OpcodeClass::Flow],
+    });
+    let cycles: Vec<u64> = entries.iter().map(|entry| entry.cycle).collect();
+    assert_eq!(cycles, [1, 2, 4]);
+}
+
+#[test]
+fn sinks_encode_entries() {
+    let entry = &log_bcd_rom(TraceFilter::default())[2];
+
+    let mut text = TextSink::new(vec![]);
+    text.write(entry).unwrap();
+    assert_eq!(
+        String::from_utf8(text.into_inner()).unwrap(),
+        "00000002 0204 F033 LD B, V0          I:0300 [0300]=02 [0301]=05 [0302]=01\n"
+    );
+
+    let mut json = JsonLinesSink::new(vec![]);
+    json.write(entry).unwrap();
+    assert_eq!(
+        String::from_utf8(json.into_inner()).unwrap(),
+        concat!(
+            r#"{"cycle":2,"pc":516,"opcode":61491,"asm":"LD B, V0","regs":[],"i":768,"#,
+            r#""writes":[{"addr":768,"value":2},{"addr":769,"value":5},{"addr":770,"value":1}]}"#,
+            "\n"
+        )
+    );
+
+    let mut binary = BinarySink::new(vec![]);
+    binary.write(entry).unwrap();
+    assert_eq!(
+        binary.into_inner(),
+        [
+            2, 0, 0, 0, 0, 0, 0, 0, // cycle
+            0x04, 0x02, 0x33, 0xF0, // pc, opcode
+            0x00, 0x03, 0x00, 0x00, // I
+            0,    // deltas
+            3,    // writes
+            0x00, 0x03, 0x00, 0x00, 2, // [0300]=02
+            0x01, 0x03, 0x00, 0x00, 5, // [0301]=05
+            0x02, 0x03, 0x00, 0x00, 1, // [0302]=01
+        ]
+    );
+}
+
+#[test]
+fn key_wait_completion_is_recorded() {
+    let entries = Rc::new(RefCell::new(vec![]));
+    let mut vm = Chip8::create();
+    vm.quirks.key_wait_release = false;
+    // F30A: LD V3, K
+    vm.load_rom(&[0xF3, 0x0A]).unwrap();
+    vm.set_tracer(Tracer::new(entries.clone()));
+    vm.cycle();
+    assert_eq!(vm.cycle(), StepOutcome::Idle);
+    vm.keyboard.key_down('v');
+    assert_eq!(vm.cycle(), StepOutcome::Ran);
+    vm.take_tracer().unwrap().finish().unwrap();
+
+    let entries = entries.borrow();
+    assert_eq!(entries.len(), 2);
+    assert_eq!((entries[1].pc, entries[1].ir_code), (0x200, 0xF30A));
+    assert_eq!(
+        entries[1].deltas,
+        [RegisterDelta {
+            reg: 3,
+            old: 0,
+            new: 0xF
+        }]
+    );
+
+    // 助记符中的引号与反斜杠需要转义
+    let mut entry = entries[1].clone();
+    entry.disasm = "DB \"\\".to_string();
+    let mut json = JsonLinesSink::new(vec![]);
+    json.write(&entry).unwrap();
+    let line = String::from_utf8(json.into_inner()).unwrap();
+    assert!(line.contains(r#""asm":"DB \"\\""#), "{}", line);
+}
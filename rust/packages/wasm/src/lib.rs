@@ -2,32 +2,87 @@
 
 extern crate wasm_bindgen;
 
-use chip8_core::vm::Chip8;
+use chip8_core::hardware::Screen;
+use chip8_core::platform::Platform;
+use chip8_core::rpl::RplStorage;
+use chip8_core::runner::{DisplaySink, FixedClock, NoAudio, NoInput, Runner};
+use chip8_core::vm::{Chip8, StepOutcome};
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
-extern "C" {}
+extern "C" {
+    // 存储被禁用或配额用完时 localStorage 会抛异常，catch 后以 Err 返回，不会抛进帧循环
+    #[wasm_bindgen(catch, js_namespace = localStorage, js_name = getItem)]
+    fn local_storage_get(key: &str) -> Result<Option<String>, JsValue>;
+
+    #[wasm_bindgen(catch, js_namespace = localStorage, js_name = setItem)]
+    fn local_storage_set(key: &str, value: &str) -> Result<(), JsValue>;
+}
+
+/// 把 RPL 标志以十六进制字符串保存在浏览器的 localStorage 中
+#[derive(Debug)]
+struct LocalStorageRpl;
+
+impl LocalStorageRpl {
+    fn item_key(key: &str) -> String {
+        format!("chip8-rpl-{}", key)
+    }
+}
+
+impl RplStorage for LocalStorageRpl {
+    fn load(&mut self, key: &str) -> Option<Vec<u8>> {
+        let hex = local_storage_get(&Self::item_key(key)).ok()??;
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+            .collect()
+    }
+
+    fn store(&mut self, key: &str, flags: &[u8]) -> Result<(), String> {
+        let hex: String = flags.iter().map(|b| format!("{:02x}", b)).collect();
+        local_storage_set(&Self::item_key(key), &hex)
+            .map_err(|err| format!("failed to save RPL flags: {:?}", err))
+    }
+}
+
+/// 记下画面有变化，由 JS 在下一次绘制时读取像素并清除
+#[derive(Debug, Default)]
+struct PendingDraw {
+    dirty: bool,
+}
+
+impl DisplaySink for PendingDraw {
+    fn present(&mut self, _screen: &Screen, _size: (u8, u8)) {
+        self.dirty = true;
+    }
+}
 
 #[wasm_bindgen]
 pub struct Emulator {
     chip8: Chip8,
+    /// 声音与按键由 JS 直接读写，这里只借用帧循环
+    runner: Runner<PendingDraw, NoAudio, NoInput, FixedClock>,
 }
 
 #[wasm_bindgen]
 impl Emulator {
     #[wasm_bindgen(constructor)]
     pub fn new() -> Self {
+        let mut chip8 = Chip8::create();
+        chip8.set_auto_configure(true);
         Emulator {
-            chip8: Chip8::create(),
+            chip8,
+            runner: Runner::new(PendingDraw::default(), NoAudio, NoInput, FixedClock),
         }
     }
 
     pub fn is_draw_flag(&self) -> bool {
-        self.chip8.draw_flag
+        self.chip8.draw_flag || self.runner.display.dirty
     }
 
     pub fn set_draw_flag(&mut self, flag: bool) {
         self.chip8.draw_flag = flag;
+        self.runner.display.dirty = flag;
     }
 
     pub fn get_columns(&self) -> u8 {
@@ -42,6 +97,39 @@ impl Emulator {
         self.chip8.high_res
     }
 
+    /// 当前平台与模式下画面的宽度，MegaChip 模式下为 256
+    pub fn get_width(&self) -> u16 {
+        self.chip8.framebuffer_size().0
+    }
+
+    pub fn get_height(&self) -> u16 {
+        self.chip8.framebuffer_size().1
+    }
+
+    /// 按平台 id 切换机器并重置，id 无效时返回 false
+    pub fn set_platform(&mut self, id: &str) -> bool {
+        match Platform::from_id(id) {
+            Some(platform) => {
+                self.chip8.set_platform(platform);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 画面的 RGBA 像素，按行排列。CHIP-8X 按颜色层着色，MegaChip 模式下为彩色画面
+    pub fn get_framebuffer(&self) -> Vec<u8> {
+        let (width, height) = self.chip8.framebuffer_size();
+        let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+        for y in 0..height {
+            for x in 0..width {
+                rgba.extend(self.chip8.pixel_color(x as u8, y as u8));
+                rgba.push(0xff);
+            }
+        }
+        rgba
+    }
+
     pub fn get_pixel(&self, x: u8, y: u8) -> bool {
         self.chip8.screen.get_pixel(x, y)
     }
@@ -50,14 +138,27 @@ impl Emulator {
         self.chip8.rate
     }
 
-    pub fn cycle(&mut self) {
-        self.chip8.cycle();
+    /// 返回 false 表示 CPU 已停机或在空转，本帧剩余的周期可以跳过
+    pub fn cycle(&mut self) -> bool {
+        self.chip8.cycle() == StepOutcome::Ran
     }
 
     pub fn ticker(&mut self) {
         self.chip8.ticker();
     }
 
+    /// 运行一帧并更新计时器，返回 false 表示程序已停机或出错
+    pub fn run_frame(&mut self) -> bool {
+        !matches!(
+            self.runner.run_frame(&mut self.chip8),
+            StepOutcome::Halted | StepOutcome::Fault(_)
+        )
+    }
+
+    pub fn is_waiting_for_key(&self) -> bool {
+        self.chip8.is_waiting_for_key()
+    }
+
     pub fn key_down(&mut self, key: char) -> bool {
         self.chip8.keyboard.key_down(key)
     }
@@ -70,10 +171,20 @@ impl Emulator {
         self.chip8.reset();
     }
 
+    /// 在 localStorage 中保存 RPL 标志，之后加载的 ROM 会读回上次的标志
+    pub fn persist_rpl_flags(&mut self) {
+        self.chip8.set_rpl_storage(LocalStorageRpl);
+    }
+
     pub fn load_rom(&mut self, rom: Vec<u8>) {
         let _ = self.chip8.load_rom(&rom);
     }
 
+    /// 已加载 ROM 在内置数据库中的标题，未收录时为空
+    pub fn get_rom_title(&self) -> Option<String> {
+        self.chip8.rom_info().map(|info| info.title.to_string())
+    }
+
     pub fn toggle_running(&mut self) -> bool {
         self.chip8.running = !self.chip8.running;
         self.chip8.running